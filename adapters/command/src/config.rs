@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use adapter_common::{Adapter, WriteTarget};
+use greenhouse_core::{
+    smart_device_dto::config::{TypeOption, ValidationErrors},
+    units::Unit,
+};
+use serde::{Deserialize, Serialize};
+
+/// Device specific part of the adapter config, stored in `additional_config`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdapterConfig {
+    /// Commands whose output is reported as a channel on `/read`.
+    #[serde(default)]
+    pub commands: Vec<CommandConfig>,
+    /// Command run on `/write`, the adapter is read-only without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<WriteCommandConfig>,
+    /// Commands running longer are killed.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// The only environment variables commands see besides `PATH`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+}
+
+impl Default for AdapterConfig {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            write: None,
+            timeout_ms: default_timeout_ms(),
+            env: BTreeMap::new(),
+            working_dir: None,
+        }
+    }
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+/// A command run as is, without a shell.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommandConfig {
+    /// Channel the output is reported as.
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub output: OutputFormat,
+    /// Numbers with a unit are reported as measurements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Runs the command in the background every `interval_secs` and reports
+    /// the last sample. Without it the command runs on every read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_secs: Option<u64>,
+}
+
+/// How the stdout of a command is parsed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Number,
+    Boolean,
+    Text,
+    /// A serialized `Type`, or plain JSON mapped onto one.
+    Json,
+}
+
+/// Command run on writes. `{{value}}` in the arguments is replaced by the
+/// written value; it is passed as a single argument and never through a shell.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WriteCommandConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(rename = "type")]
+    pub input_type: TypeOption,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub true_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub false_value: Option<String>,
+}
+
+impl Adapter for AdapterConfig {
+    type Write = WriteCommandConfig;
+
+    /// Checks the channel names, programs, units and intervals.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let mut names = Vec::new();
+        for (i, command) in self.commands.iter().enumerate() {
+            let field = format!("commands[{i}]");
+            if command.name.is_empty() {
+                errors.add(format!("{field}.name"), "must not be empty");
+            } else if names.contains(&&command.name) {
+                errors.add(format!("{field}.name"), "is used by another command");
+            }
+            names.push(&command.name);
+            if command.program.trim().is_empty() {
+                errors.add(format!("{field}.program"), "must not be empty");
+            }
+            if command.interval_secs == Some(0) {
+                errors.add(format!("{field}.interval_secs"), "must be at least 1");
+            }
+            if let Some(unit) = &command.unit
+                && let Err(e) = unit.parse::<Unit>()
+            {
+                errors.add(format!("{field}.unit"), e.to_string());
+            }
+        }
+        if let Some(write) = &self.write
+            && write.program.trim().is_empty()
+        {
+            errors.add("write.program", "must not be empty");
+        }
+        if self.timeout_ms == 0 {
+            errors.add("timeout_ms", "must be at least 1");
+        }
+        errors.into_result(())
+    }
+
+    fn write(&self) -> Option<&WriteCommandConfig> {
+        self.write.as_ref()
+    }
+
+    /// What runs, and in which environment, is only set in the config file;
+    /// remotely, commands can only be reordered or change how they report.
+    fn local_only_changes(&self, old: &Self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        for (i, command) in self.commands.iter().enumerate() {
+            let unchanged = old.commands.iter().any(|old| {
+                old.name == command.name
+                    && old.program == command.program
+                    && old.args == command.args
+            });
+            if !unchanged {
+                errors.add(
+                    format!("commands[{i}].program"),
+                    "can only be added or changed in the config file",
+                );
+            }
+        }
+        let run = |write: &WriteCommandConfig| (write.program.clone(), write.args.clone());
+        if self.write.as_ref().map(run) != old.write.as_ref().map(run) {
+            errors.add("write.program", "can only be changed in the config file");
+        }
+        if self.env != old.env {
+            errors.add("env", "can only be changed in the config file");
+        }
+        if self.working_dir != old.working_dir {
+            errors.add("working_dir", "can only be changed in the config file");
+        }
+        errors
+    }
+}
+
+impl WriteTarget for WriteCommandConfig {
+    fn input_type(&self) -> &TypeOption {
+        &self.input_type
+    }
+
+    fn true_value(&self) -> Option<&str> {
+        self.true_value.as_deref()
+    }
+
+    fn false_value(&self) -> Option<&str> {
+        self.false_value.as_deref()
+    }
+}
@@ -0,0 +1,182 @@
+//! Smart device adapter running local commands or scripts. Their stdout is
+//! reported as channels on reads, writes run a command with the value as an
+//! argument.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use futures::future::join_all;
+use greenhouse_core::{
+    smart_device_dto::{
+        Type,
+        action::{ActionDto, ActionRequestDto, ActionResponseDto},
+        write::WriteResponseDto,
+    },
+    smart_device_interface::{config::Config, device_builder::DeviceBuilder},
+};
+
+pub mod config;
+pub mod parse;
+pub mod run;
+
+use config::{AdapterConfig, CommandConfig};
+
+pub use adapter_common::{Error, Result};
+
+const PLACEHOLDER: &str = "{{value}}";
+
+/// Last sample of every interval command, with the command that produced it
+/// so a changed config doesn't report stale values.
+static SAMPLES: LazyLock<Mutex<HashMap<String, (CommandConfig, Type)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Builds the adapter from the config at `config_path`. It is an output device
+/// reporting an object of channels, and a hybrid device if a write command is
+/// configured. The `refresh` action samples the interval commands on demand.
+pub fn adapter_device(config_path: &str) -> Result<DeviceBuilder<AdapterConfig>> {
+    adapter_common::adapter_device(config_path, read_handler, write_handler).map(|device| {
+        device.with_action(
+            ActionDto::new("refresh")
+                .with_description("Run the interval commands now instead of waiting"),
+            refresh_action,
+        )
+    })
+}
+
+pub async fn read_handler(config: Arc<Config<AdapterConfig>>) -> Type {
+    let adapter = &config.additional_config;
+    let values = join_all(adapter.commands.iter().map(|command| async move {
+        if command.interval_secs.is_some()
+            && let Some(sample) = cached_sample(command)
+        {
+            return sample;
+        }
+        sample(command, adapter).await
+    }))
+    .await;
+
+    Type::Object(
+        adapter
+            .commands
+            .iter()
+            .map(|command| command.name.clone())
+            .zip(values)
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+fn cached_sample(command: &CommandConfig) -> Option<Type> {
+    let samples = SAMPLES.lock().unwrap_or_else(PoisonError::into_inner);
+    samples
+        .get(&command.name)
+        .filter(|(sampled, _)| sampled == command)
+        .map(|(_, value)| value.clone())
+}
+
+/// Runs `command` and parses its output, remembering the value of interval
+/// commands. Failures are reported as `Type::None`.
+async fn sample(command: &CommandConfig, adapter: &AdapterConfig) -> Type {
+    let value = match run::run(&command.program, &command.args, adapter).await {
+        Ok(stdout) => parse::parse_output(&stdout, command.output, command.unit.as_deref())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Output of {} could not be parsed: {e}", command.name);
+                Type::None
+            }),
+        Err(e) => {
+            tracing::warn!("Command {} failed: {e}", command.name);
+            Type::None
+        }
+    };
+    if command.interval_secs.is_some() {
+        SAMPLES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(command.name.clone(), (command.clone(), value.clone()));
+    }
+    value
+}
+
+/// Runs the interval commands of the current config when they are due.
+/// Meant to be spawned next to the device router.
+pub async fn sample_periodically(device_service: DeviceBuilder<AdapterConfig>) {
+    let mut last_runs: HashMap<String, Instant> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+        let config = device_service
+            .config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        let adapter = &config.additional_config;
+        let now = Instant::now();
+        let due: Vec<_> = adapter
+            .commands
+            .iter()
+            .filter(|command| {
+                command.interval_secs.is_some_and(|interval| {
+                    last_runs.get(&command.name).is_none_or(|last| {
+                        now.duration_since(*last) >= Duration::from_secs(interval)
+                    })
+                })
+            })
+            .collect();
+        for command in &due {
+            last_runs.insert(command.name.clone(), now);
+        }
+        join_all(due.into_iter().map(|command| sample(command, adapter))).await;
+    }
+}
+
+async fn refresh_action(
+    _request: ActionRequestDto,
+    config: Arc<Config<AdapterConfig>>,
+) -> ActionResponseDto {
+    let adapter = &config.additional_config;
+    let interval_commands = adapter
+        .commands
+        .iter()
+        .filter(|command| command.interval_secs.is_some());
+    let samples = join_all(
+        interval_commands
+            .clone()
+            .map(|command| sample(command, adapter)),
+    )
+    .await;
+    ActionResponseDto::completed().with_result(Type::Object(
+        interval_commands
+            .map(|command| command.name.clone())
+            .zip(samples)
+            .collect(),
+    ))
+}
+
+pub async fn write_handler(data: Type, config: Arc<Config<AdapterConfig>>) -> WriteResponseDto {
+    let adapter = &config.additional_config;
+    let Some(write) = &adapter.write else {
+        return WriteResponseDto::rejected("Adapter has no write command configured");
+    };
+    if !write.input_type.accepts(&data) {
+        return WriteResponseDto::rejected(format!("Expected {:?}", write.input_type));
+    }
+    let Some(value) = adapter_common::render_value(&data, write) else {
+        return WriteResponseDto::rejected("Value can't be passed to the command");
+    };
+
+    let args: Vec<_> = write
+        .args
+        .iter()
+        .map(|arg| arg.replace(PLACEHOLDER, &value))
+        .collect();
+    match run::run(&write.program, &args, adapter).await {
+        Ok(_) => WriteResponseDto::applied(data),
+        Err(run::Error::Failed { status, stderr }) => WriteResponseDto::failed(format!(
+            "Command exited with {}: {stderr}",
+            status.map_or_else(|| String::from("a signal"), |code| code.to_string())
+        )),
+        Err(e) => WriteResponseDto::failed(format!("Command could not be run: {e}")),
+    }
+}
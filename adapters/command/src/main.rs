@@ -0,0 +1,17 @@
+use command_adapter::{adapter_device, config::AdapterConfig, sample_periodically};
+
+#[tokio::main]
+async fn main() {
+    let config_path =
+        adapter_common::init::<AdapterConfig>("./config/command_adapter/config.json", 6011);
+
+    let device_service = match adapter_device(&config_path) {
+        Ok(device_service) => device_service,
+        Err(e) => {
+            tracing::error!("Invalid adapter config {config_path}: {e}");
+            std::process::exit(1);
+        }
+    };
+    tokio::spawn(sample_periodically(device_service.clone()));
+    adapter_common::serve(device_service).await;
+}
@@ -0,0 +1,91 @@
+use greenhouse_core::smart_device_dto::{Measurement, Type};
+use serde_json::Value;
+
+use crate::config::OutputFormat;
+
+/// Parses the stdout of a command into a channel value.
+pub fn parse_output(
+    stdout: &str,
+    format: OutputFormat,
+    unit: Option<&str>,
+) -> Result<Type, String> {
+    let output = stdout.trim();
+    match format {
+        OutputFormat::Number => {
+            let number: f64 = output
+                .parse()
+                .map_err(|_| format!("{output:?} is not a number"))?;
+            Ok(match unit {
+                Some(unit) => Type::Measurement(Measurement {
+                    value: number,
+                    unit: unit.to_string(),
+                }),
+                None => Type::Number(number),
+            })
+        }
+        OutputFormat::Boolean => match output.to_ascii_lowercase().as_str() {
+            "true" | "on" | "1" | "yes" => Ok(Type::Boolean(true)),
+            "false" | "off" | "0" | "no" => Ok(Type::Boolean(false)),
+            _ => Err(format!("{output:?} is not a boolean")),
+        },
+        OutputFormat::Text => Ok(Type::Text(output.to_string())),
+        OutputFormat::Json => {
+            let json: Value = serde_json::from_str(output).map_err(|e| e.to_string())?;
+            Ok(serde_json::from_value(json.clone()).unwrap_or_else(|_| from_json(json)))
+        }
+    }
+}
+
+fn from_json(json: Value) -> Type {
+    match json {
+        Value::Null => Type::None,
+        Value::Bool(boolean) => Type::Boolean(boolean),
+        Value::Number(number) => number.as_f64().map_or(Type::None, Type::Number),
+        Value::String(text) => Type::Text(text),
+        Value::Array(values) => Type::Array(values.into_iter().map(from_json).collect()),
+        Value::Object(fields) => Type::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, from_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_command_output() {
+        assert_eq!(
+            parse_output("21.5\n", OutputFormat::Number, Some("°C")),
+            Ok(Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("°C")
+            }))
+        );
+        assert_eq!(
+            parse_output("ON\n", OutputFormat::Boolean, None),
+            Ok(Type::Boolean(true))
+        );
+        assert!(parse_output("n/a", OutputFormat::Number, None).is_err());
+
+        let Ok(Type::Object(fields)) = parse_output(
+            r#"{"load": 0.5, "disks": ["sda"]}"#,
+            OutputFormat::Json,
+            None,
+        ) else {
+            panic!("Expected an object");
+        };
+        assert_eq!(fields["load"], Type::Number(0.5));
+        assert_eq!(
+            fields["disks"],
+            Type::Array(vec![Type::Text(String::from("sda"))])
+        );
+        assert_eq!(
+            parse_output(r#"{"Enum": "OPEN"}"#, OutputFormat::Json, None),
+            Ok(Type::Enum(String::from("OPEN")))
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{process::Stdio, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+};
+
+use crate::config::AdapterConfig;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Spawn(std::io::Error),
+    Timeout,
+    Failed { status: Option<i32>, stderr: String },
+    InvalidOutput,
+    OutputTooLarge,
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+/// Search path of commands unless the config sets its own `PATH`.
+const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// Captured stdout and stderr per run, the rest is discarded.
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Runs `program` in the sandbox described by `adapter` and returns its
+/// stdout. The environment is cleared, stdin is closed and the process runs in
+/// its own group, which is killed with everything it started once the timeout
+/// passes.
+pub async fn run(program: &str, args: &[String], adapter: &AdapterConfig) -> Result<String> {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .env_clear()
+        .env("PATH", DEFAULT_PATH)
+        .envs(&adapter.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true);
+    if let Some(working_dir) = &adapter.working_dir {
+        command.current_dir(working_dir);
+    }
+
+    let mut child = command.spawn().map_err(Error::Spawn)?;
+    let group = child.id();
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err(Error::Spawn(std::io::ErrorKind::BrokenPipe.into()));
+    };
+    let output = tokio::time::timeout(Duration::from_millis(adapter.timeout_ms), async {
+        tokio::join!(read_capped(stdout), read_capped(stderr), child.wait())
+    })
+    .await;
+    let ((stdout, stdout_truncated), (stderr, _), status) = match output {
+        Ok(output) => output,
+        Err(_) => {
+            if let Some(group) = group.and_then(|pid| i32::try_from(pid).ok()) {
+                // SAFETY: only signals the group started for this command
+                unsafe { libc::kill(-group, libc::SIGKILL) };
+            }
+            return Err(Error::Timeout);
+        }
+    };
+    let status = status.map_err(Error::Spawn)?;
+
+    if !status.success() {
+        return Err(Error::Failed {
+            status: status.code(),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        });
+    }
+    if stdout_truncated {
+        return Err(Error::OutputTooLarge);
+    }
+    String::from_utf8(stdout).map_err(|_| Error::InvalidOutput)
+}
+
+/// Reads up to `MAX_OUTPUT` bytes and drains the rest, so the command doesn't
+/// block on a full pipe. Also returns whether anything was discarded.
+async fn read_capped(mut pipe: impl AsyncRead + Unpin) -> (Vec<u8>, bool) {
+    let mut output = Vec::new();
+    let _ = (&mut pipe)
+        .take(MAX_OUTPUT as u64)
+        .read_to_end(&mut output)
+        .await;
+    let discarded = tokio::io::copy(&mut pipe, &mut tokio::io::sink())
+        .await
+        .is_ok_and(|discarded| discarded > 0);
+    (output, discarded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::*;
+
+    fn adapter(timeout_ms: u64) -> AdapterConfig {
+        AdapterConfig {
+            timeout_ms,
+            env: BTreeMap::from([(String::from("GREENHOUSE"), String::from("1"))]),
+            ..Default::default()
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn clears_the_environment() {
+        let stdout = run("env", &[], &adapter(1000)).await.unwrap();
+
+        assert_eq!(
+            stdout.lines().collect::<BTreeSet<_>>(),
+            BTreeSet::from([format!("PATH={DEFAULT_PATH}").as_str(), "GREENHOUSE=1"])
+        );
+    }
+
+    #[tokio::test]
+    async fn kills_started_processes_on_timeout() {
+        let pid_path =
+            std::env::temp_dir().join(format!("command_adapter_pid_{}", std::process::id()));
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_path.display());
+
+        let result = run("sh", &args(&["-c", &script]), &adapter(300)).await;
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let pid = std::fs::read_to_string(&pid_path).unwrap();
+        std::fs::remove_file(&pid_path).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Gone, or a zombie waiting to be reaped by init
+        if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())) {
+            assert!(stat.contains(") Z "), "{stat}");
+        }
+    }
+
+    #[tokio::test]
+    async fn caps_the_output() {
+        let huge = args(&["-c", "head -c 2000000 /dev/zero"]);
+        let result = run("sh", &huge, &adapter(5000)).await;
+        assert!(matches!(result, Err(Error::OutputTooLarge)));
+
+        let failing = args(&["-c", "head -c 2000000 /dev/zero >&2; exit 1"]);
+        match run("sh", &failing, &adapter(5000)).await {
+            Err(Error::Failed { stderr, .. }) => assert!(stderr.len() <= MAX_OUTPUT),
+            other => panic!("Expected a failure, got {other:?}"),
+        }
+    }
+}
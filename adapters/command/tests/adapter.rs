@@ -0,0 +1,224 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode, header},
+};
+use command_adapter::adapter_device;
+use greenhouse_core::{
+    smart_device_dto::{
+        Measurement, Type,
+        read::ReadResponseDto,
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
+    },
+    smart_device_interface::hybrid_device::init_hybrid_router,
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("command_adapter_{name}_{}", std::process::id()))
+}
+
+fn write_config(name: &str, additional_config: Value) -> String {
+    let path = temp_path(&format!("{name}.json"));
+    let config = json!({
+        "port": 0,
+        "datasource_id": "",
+        "additional_config": additional_config,
+    });
+    std::fs::write(&path, config.to_string()).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+async fn call(router: Router, request: Request<Body>, status: StatusCode) -> Value {
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), status);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn read(router: Router) -> std::collections::BTreeMap<String, Type> {
+    let request = Request::get("/read").body(Body::empty()).unwrap();
+    let read: ReadResponseDto =
+        serde_json::from_value(call(router, request, StatusCode::OK).await).unwrap();
+    match read.data {
+        Type::Object(channels) => channels,
+        other => panic!("Expected an object, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn runs_commands_in_a_sandbox() {
+    let target = temp_path("relay");
+    let config_path = write_config(
+        "sandbox",
+        json!({
+            "commands": [
+                {"name": "temperature", "program": "echo", "args": ["21.5"], "output": "Number", "unit": "°C"},
+                {"name": "pump", "program": "sh", "args": ["-c", "echo on"], "output": "Boolean"},
+                {"name": "disk", "program": "sh", "args": ["-c", "echo '{\"free\": 12, \"mounts\": [\"/\"]}'"], "output": "Json"},
+                {"name": "home", "program": "sh", "args": ["-c", "echo ${HOME:-unset} $GREENHOUSE"], "output": "Text"},
+                {"name": "slow", "program": "sleep", "args": ["5"], "output": "Number"},
+                {"name": "failing", "program": "sh", "args": ["-c", "exit 3"], "output": "Number"},
+                {"name": "sampled", "program": "sh", "args": ["-c", "date +%s%N"], "output": "Number", "interval_secs": 3600}
+            ],
+            "write": {
+                "program": "sh",
+                "args": ["-c", "printf %s \"$1\" > \"$2\"", "sh", "{{value}}", target],
+                "type": "Boolean",
+                "true_value": "on; rm -rf /",
+                "false_value": "off"
+            },
+            "timeout_ms": 500,
+            "env": {"GREENHOUSE": "1"}
+        }),
+    );
+    let router = init_hybrid_router(adapter_device(&config_path).unwrap());
+
+    let channels = read(router.clone()).await;
+    assert_eq!(
+        channels["temperature"],
+        Type::Measurement(Measurement {
+            value: 21.5,
+            unit: String::from("°C")
+        })
+    );
+    assert_eq!(channels["pump"], Type::Boolean(true));
+    assert!(
+        matches!(&channels["disk"], Type::Object(fields) if fields["free"] == Type::Number(12.0))
+    );
+    assert_eq!(channels["home"], Type::Text(String::from("unset 1")));
+    assert_eq!(channels["slow"], Type::None);
+    assert_eq!(channels["failing"], Type::None);
+    assert_eq!(read(router.clone()).await["sampled"], channels["sampled"]);
+
+    let refreshed = call(
+        router.clone(),
+        Request::post("/actions/refresh")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"parameters": {}}"#))
+            .unwrap(),
+        StatusCode::OK,
+    )
+    .await;
+    assert_ne!(read(router.clone()).await["sampled"], channels["sampled"]);
+    assert_eq!(refreshed["result"]["Object"].as_object().unwrap().len(), 1);
+
+    let write = |data| {
+        Request::post("/write")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&WriteRequestDto {
+                    data,
+                    idempotency_key: None,
+                })
+                .unwrap(),
+            ))
+            .unwrap()
+    };
+    let applied: WriteResponseDto = serde_json::from_value(
+        call(router.clone(), write(Type::Boolean(true)), StatusCode::OK).await,
+    )
+    .unwrap();
+    assert_eq!(applied.status, WriteStatus::Applied);
+    // The value is a single argument and never interpreted by a shell
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "on; rm -rf /");
+
+    let rejected: WriteResponseDto = serde_json::from_value(
+        call(
+            router,
+            write(Type::Text(String::from("on"))),
+            StatusCode::BAD_REQUEST,
+        )
+        .await,
+    )
+    .unwrap();
+    assert_eq!(rejected.status, WriteStatus::Rejected);
+
+    std::fs::remove_file(target).unwrap();
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_invalid_configs() {
+    let config_path = write_config(
+        "invalid",
+        json!({
+            "commands": [
+                {"name": "a", "program": "echo", "output": "Number"},
+                {"name": "a", "program": " ", "output": "Number", "interval_secs": 0}
+            ]
+        }),
+    );
+    assert!(adapter_device(&config_path).is_err());
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_unknown_units() {
+    let config_path = write_config(
+        "unit",
+        json!({
+            "commands": [
+                {"name": "a", "program": "echo", "output": "Number", "unit": "degrees"}
+            ]
+        }),
+    );
+    assert!(adapter_device(&config_path).is_err());
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_remote_changes_of_what_runs() {
+    let config_path = write_config(
+        "remote",
+        json!({
+            "commands": [{"name": "temperature", "program": "echo", "args": ["21.5"], "output": "Number"}],
+            "write": {"program": "true", "type": "Boolean"}
+        }),
+    );
+    let router = init_hybrid_router(adapter_device(&config_path).unwrap());
+    let update = |additional_config: Value| {
+        Request::post("/config")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({ "additional_config": additional_config }).to_string(),
+            ))
+            .unwrap()
+    };
+    let write = json!({"program": "true", "type": "Boolean"});
+
+    let errors = call(
+        router.clone(),
+        update(json!({
+            "commands": [{"name": "temperature", "program": "sh", "args": ["-c", "id"], "output": "Number"}],
+            "write": write
+        })),
+        StatusCode::UNPROCESSABLE_ENTITY,
+    )
+    .await;
+    assert_eq!(errors["errors"][0]["field"], "commands[0].program");
+    for changed in [
+        json!({"write": {"program": "sh", "args": ["-c", "id"], "type": "Boolean"}}),
+        json!({"write": write, "env": {"LD_PRELOAD": "/tmp/evil.so"}}),
+        json!({"write": write, "working_dir": "/tmp"}),
+    ] {
+        call(
+            router.clone(),
+            update(changed),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .await;
+    }
+    // How the output is reported may still change
+    let response = router
+        .oneshot(update(json!({
+            "commands": [{"name": "temperature", "program": "echo", "args": ["21.5"], "output": "Number", "unit": "°C"}],
+            "write": write
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    std::fs::remove_file(config_path).unwrap();
+}
@@ -0,0 +1,200 @@
+//! Parts shared by the smart device adapters: building the device from its
+//! config file, the status and config handlers, rendering written values and
+//! serving the device.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::Router;
+use greenhouse_core::{
+    smart_device_dto::{
+        Type,
+        config::{ConfigRequestDto, TypeOption, ValidationErrors},
+        status::{DeviceStatusDto, DeviceStatusResponseDto},
+    },
+    smart_device_interface::{
+        self,
+        config::{Config, read_config_file_with_path, update_config_file_with_path},
+        device_builder::{DeviceBuilder, ReadFuture, ReadHandlerFn, WriteFuture, WriteHandlerFn},
+        hybrid_device::init_hybrid_router,
+        output_device::init_output_router,
+        tls, tunnel,
+    },
+};
+use serde::{Serialize, de::DeserializeOwned};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Device(smart_device_interface::Error),
+    InvalidConfig(ValidationErrors),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+/// Device specific part of an adapter config, stored in `additional_config`.
+pub trait Adapter: Serialize + DeserializeOwned + Clone + Default + Send + Sync + 'static {
+    type Write: WriteTarget;
+
+    fn validate(&self) -> core::result::Result<(), ValidationErrors>;
+
+    /// Writes the adapter forwards, it is read-only without them.
+    fn write(&self) -> Option<&Self::Write>;
+
+    /// Errors for the changes from `old` only the config file may make, e.g.
+    /// the programs an adapter runs.
+    fn local_only_changes(&self, _old: &Self) -> ValidationErrors {
+        ValidationErrors::new()
+    }
+}
+
+/// How an adapter forwards writes.
+pub trait WriteTarget {
+    fn input_type(&self) -> &TypeOption;
+
+    /// Rendering of `true`, e.g. `on` for a relay.
+    fn true_value(&self) -> Option<&str>;
+
+    fn false_value(&self) -> Option<&str>;
+}
+
+/// Builds the adapter from the config at `config_path`. It is an output device
+/// reporting an object of channels, and a hybrid device if writes are configured.
+pub fn adapter_device<A, RH, RF, WH, WF>(
+    config_path: &str,
+    read_handler: RH,
+    write_handler: WH,
+) -> Result<DeviceBuilder<A>>
+where
+    A: Adapter,
+    RH: ReadHandlerFn<A, RF>,
+    RF: ReadFuture,
+    WH: WriteHandlerFn<A, WF>,
+    WF: WriteFuture,
+{
+    let config: Config<A> = read_config_file_with_path(config_path).map_err(Error::Device)?;
+    config
+        .additional_config
+        .validate()
+        .map_err(Error::InvalidConfig)?;
+
+    match config.additional_config.write() {
+        Some(write) => DeviceBuilder::new_hybrid_device_with_config_path(
+            read_handler,
+            write_handler,
+            status_handler,
+            config_interceptor_handler,
+            config_path,
+            write.input_type().clone(),
+            TypeOption::Object,
+        ),
+        None => DeviceBuilder::new_output_device_with_config_path(
+            read_handler,
+            status_handler,
+            config_interceptor_handler,
+            config_path,
+            TypeOption::Object,
+        ),
+    }
+    .map_err(Error::Device)
+}
+
+/// Text a written value is passed on as, `None` for values without a plain
+/// text form such as objects.
+pub fn render_value(value: &Type, write: &impl WriteTarget) -> Option<String> {
+    match value {
+        Type::Number(number) => Some(number.to_string()),
+        Type::Boolean(true) => Some(write.true_value().unwrap_or("true").to_string()),
+        Type::Boolean(false) => Some(write.false_value().unwrap_or("false").to_string()),
+        Type::Text(text) | Type::Enum(text) => Some(text.clone()),
+        Type::Measurement(measurement) => Some(measurement.value.to_string()),
+        _ => None,
+    }
+}
+
+pub async fn status_handler<A: Adapter>(config: Arc<Config<A>>) -> DeviceStatusResponseDto {
+    DeviceStatusResponseDto {
+        status: DeviceStatusDto::Online,
+        datasource_id: config.datasource_id.clone(),
+    }
+}
+
+/// Only the adapter part of the config can be changed remotely, and only to a
+/// valid one. Whether the adapter accepts writes, and of which type, decides
+/// the router it is served with, so that needs a restart.
+pub async fn config_interceptor_handler<A: Adapter>(
+    config: ConfigRequestDto<A>,
+    old_config: Arc<Config<A>>,
+) -> core::result::Result<Config<A>, ValidationErrors> {
+    config.additional_config.validate()?;
+    let input_type = |adapter: &A| adapter.write().map(|write| write.input_type().clone());
+    let mut errors = config
+        .additional_config
+        .local_only_changes(&old_config.additional_config);
+    if input_type(&config.additional_config) != input_type(&old_config.additional_config) {
+        errors.add(
+            "write",
+            "can only be added, removed or change its type in the config file",
+        );
+    }
+    errors.into_result(Config {
+        additional_config: config.additional_config,
+        ..old_config.as_ref().clone()
+    })
+}
+
+/// Sets up logging and returns the config path passed as the first argument,
+/// or `default_path`. A default config listening on `default_port` is written
+/// if there is none yet.
+pub fn init<A: Adapter>(default_path: &str, default_port: u16) -> String {
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let config_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| default_path.to_string());
+    if read_config_file_with_path::<A>(&config_path).is_err() {
+        let default_config = Config::<A> {
+            port: default_port,
+            ..Default::default()
+        };
+        if let Some(parent) = std::path::Path::new(&config_path).parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        update_config_file_with_path(&default_config, &config_path).unwrap();
+    }
+    config_path
+}
+
+/// Serves the adapter on the port of its config, through its tunnel too if
+/// one is configured.
+pub async fn serve<A: Adapter>(device_service: DeviceBuilder<A>) {
+    let config = device_service
+        .config
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_default();
+    let router: Router = match config.additional_config.write() {
+        Some(_) => init_hybrid_router(device_service.clone()),
+        None => init_output_router(device_service.clone()),
+    };
+
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
+    tracing::info!(
+        "listening on {address}, using config file {}",
+        device_service.config_path
+    );
+    tunnel::spawn_tunnel(&device_service, router.clone());
+    tls::serve(&device_service, router, address).await.unwrap();
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use adapter_common::{Adapter, WriteTarget};
+use greenhouse_core::{
+    smart_device_dto::config::{TypeOption, ValidationErrors},
+    units::Unit,
+};
+use serde::{Deserialize, Serialize};
+use serde_json_path::JsonPath;
+
+/// Device specific part of the adapter config, stored in `additional_config`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdapterConfig {
+    /// Vendor endpoints polled on every read.
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+    /// Request sent on `/write`, the adapter is read-only without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<WriteConfig>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for AdapterConfig {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            write: None,
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SourceConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub channels: Vec<ChannelConfig>,
+}
+
+/// A value extracted from a source response.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelConfig {
+    pub name: String,
+    /// JSONPath selecting the value, e.g. `$.tmp.tC` on a Shelly.
+    pub path: String,
+    /// `Number`, `Boolean`, `Text` or `Enum`.
+    #[serde(rename = "type")]
+    pub channel_type: TypeOption,
+    /// Numbers with a unit are reported as measurements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+/// Request template for writes. `{{value}}` in the url and body is replaced by
+/// the written value.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WriteConfig {
+    pub url: String,
+    #[serde(default = "default_write_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(rename = "type")]
+    pub input_type: TypeOption,
+    /// Rendering of `true`, e.g. `on` for a Shelly relay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub true_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub false_value: Option<String>,
+}
+
+fn default_write_method() -> String {
+    String::from("POST")
+}
+
+impl Adapter for AdapterConfig {
+    type Write = WriteConfig;
+
+    /// Checks the JSONPaths, channel types, units and write method.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let mut names = Vec::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            if reqwest::Url::parse(&source.url).is_err() {
+                errors.add(format!("sources[{i}].url"), "is not a valid url");
+            }
+            for (j, channel) in source.channels.iter().enumerate() {
+                let field = format!("sources[{i}].channels[{j}]");
+                if names.contains(&&channel.name) {
+                    errors.add(format!("{field}.name"), "is used by another channel");
+                }
+                names.push(&channel.name);
+                if let Err(e) = JsonPath::parse(&channel.path) {
+                    errors.add(format!("{field}.path"), format!("is not a JSONPath: {e}"));
+                }
+                if !matches!(
+                    channel.channel_type,
+                    TypeOption::Number
+                        | TypeOption::Boolean
+                        | TypeOption::Text
+                        | TypeOption::Enum(_)
+                ) {
+                    errors.add(
+                        format!("{field}.type"),
+                        "must be Number, Boolean, Text or Enum",
+                    );
+                }
+                if let Some(unit) = &channel.unit
+                    && let Err(e) = unit.parse::<Unit>()
+                {
+                    errors.add(format!("{field}.unit"), e.to_string());
+                }
+            }
+        }
+        if let Some(write) = &self.write
+            && reqwest::Method::from_bytes(write.method.as_bytes()).is_err()
+        {
+            errors.add("write.method", "is not an HTTP method");
+        }
+        errors.into_result(())
+    }
+
+    fn write(&self) -> Option<&WriteConfig> {
+        self.write.as_ref()
+    }
+}
+
+impl WriteTarget for WriteConfig {
+    fn input_type(&self) -> &TypeOption {
+        &self.input_type
+    }
+
+    fn true_value(&self) -> Option<&str> {
+        self.true_value.as_deref()
+    }
+
+    fn false_value(&self) -> Option<&str> {
+        self.false_value.as_deref()
+    }
+}
@@ -0,0 +1,112 @@
+use greenhouse_core::smart_device_dto::{Measurement, Type, config::TypeOption};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use crate::config::ChannelConfig;
+
+/// Selects the value of `channel` in a source response and converts it to the
+/// declared type. Vendors are loose with types, so numbers may come as strings
+/// and booleans as `"on"`/`"off"` or `0`/`1`.
+pub fn extract(response: &Value, channel: &ChannelConfig) -> Result<Type, String> {
+    let path = JsonPath::parse(&channel.path).map_err(|e| e.to_string())?;
+    let value = path
+        .query(response)
+        .at_most_one()
+        .map_err(|_| format!("{} selects more than one value", channel.path))?
+        .ok_or_else(|| format!("{} selects nothing", channel.path))?;
+
+    match &channel.channel_type {
+        TypeOption::Number => {
+            let number = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::String(text) => text.trim().parse().ok(),
+                _ => None,
+            }
+            .ok_or_else(|| format!("{value} is not a number"))?;
+            Ok(match &channel.unit {
+                Some(unit) => Type::Measurement(Measurement {
+                    value: number,
+                    unit: unit.clone(),
+                }),
+                None => Type::Number(number),
+            })
+        }
+        TypeOption::Boolean => match value {
+            Value::Bool(boolean) => Some(*boolean),
+            Value::Number(number) => number.as_f64().map(|number| number != 0.0),
+            Value::String(text) => match text.trim().to_ascii_lowercase().as_str() {
+                "true" | "on" | "1" | "yes" | "open" => Some(true),
+                "false" | "off" | "0" | "no" | "closed" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+        .map(Type::Boolean)
+        .ok_or_else(|| format!("{value} is not a boolean")),
+        TypeOption::Text => Ok(Type::Text(text(value))),
+        TypeOption::Enum(options) => {
+            let option = text(value);
+            if options.contains(&option) {
+                Ok(Type::Enum(option))
+            } else {
+                Err(format!("{option} is not one of {options:?}"))
+            }
+        }
+        other => Err(format!("{other:?} channels are not supported")),
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn channel(path: &str, channel_type: TypeOption, unit: Option<&str>) -> ChannelConfig {
+        ChannelConfig {
+            name: String::from("channel"),
+            path: String::from(path),
+            channel_type,
+            unit: unit.map(String::from),
+        }
+    }
+
+    #[test]
+    fn converts_vendor_values() {
+        let response = json!({
+            "tmp": {"tC": 21.5},
+            "relays": [{"ison": true}],
+            "StatusSNS": {"ENERGY": {"Power": "12"}},
+            "POWER": "ON"
+        });
+
+        let temperature = extract(
+            &response,
+            &channel("$.tmp.tC", TypeOption::Number, Some("°C")),
+        );
+        assert!(
+            matches!(temperature, Ok(Type::Measurement(m)) if m.value == 21.5 && m.unit == "°C")
+        );
+        let relay = extract(
+            &response,
+            &channel("$.relays[0].ison", TypeOption::Boolean, None),
+        );
+        assert!(matches!(relay, Ok(Type::Boolean(true))));
+        let power = extract(
+            &response,
+            &channel("$.StatusSNS.ENERGY.Power", TypeOption::Number, None),
+        );
+        assert!(matches!(power, Ok(Type::Number(n)) if n == 12.0));
+        let state = extract(&response, &channel("$.POWER", TypeOption::Boolean, None));
+        assert!(matches!(state, Ok(Type::Boolean(true))));
+
+        assert!(extract(&response, &channel("$.missing", TypeOption::Number, None)).is_err());
+        assert!(extract(&response, &channel("$.POWER", TypeOption::Number, None)).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Smart device adapter for devices that speak their own HTTP JSON API, e.g.
+//! Shelly or Tasmota. Reads poll the configured sources and extract channels
+//! with JSONPath, writes are sent as templated requests.
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use futures::future::join_all;
+use greenhouse_core::{
+    smart_device_dto::{Type, write::WriteResponseDto},
+    smart_device_interface::{config::Config, device_builder::DeviceBuilder},
+};
+use reqwest::{
+    Method,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde_json::Value;
+
+pub mod config;
+pub mod extract;
+pub mod template;
+
+use config::{AdapterConfig, SourceConfig};
+
+pub use adapter_common::{Error, Result};
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Builds the adapter from the config at `config_path`.
+pub fn adapter_device(config_path: &str) -> Result<DeviceBuilder<AdapterConfig>> {
+    adapter_common::adapter_device(config_path, read_handler, write_handler)
+}
+
+pub async fn read_handler(config: Arc<Config<AdapterConfig>>) -> Type {
+    let adapter = &config.additional_config;
+    let timeout = Duration::from_millis(adapter.timeout_ms);
+    let responses = join_all(
+        adapter
+            .sources
+            .iter()
+            .map(|source| poll_source(source, timeout)),
+    )
+    .await;
+
+    let mut channels = BTreeMap::new();
+    for (source, response) in adapter.sources.iter().zip(responses) {
+        for channel in &source.channels {
+            let value = match &response {
+                Some(response) => extract::extract(response, channel).unwrap_or_else(|e| {
+                    tracing::warn!("Channel {} could not be read: {e}", channel.name);
+                    Type::None
+                }),
+                None => Type::None,
+            };
+            channels.insert(channel.name.clone(), value);
+        }
+    }
+    Type::Object(channels)
+}
+
+async fn poll_source(source: &SourceConfig, timeout: Duration) -> Option<Value> {
+    let response = CLIENT
+        .get(&source.url)
+        .headers(headers(&source.headers))
+        .timeout(timeout)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    match response {
+        Ok(response) => response
+            .json()
+            .await
+            .inspect_err(|e| tracing::warn!("{} returned no JSON: {e}", source.url))
+            .ok(),
+        Err(e) => {
+            tracing::warn!("Polling {} failed: {e}", source.url);
+            None
+        }
+    }
+}
+
+pub async fn write_handler(data: Type, config: Arc<Config<AdapterConfig>>) -> WriteResponseDto {
+    let adapter = &config.additional_config;
+    let Some(write) = &adapter.write else {
+        return WriteResponseDto::rejected("Adapter has no write configured");
+    };
+    if !write.input_type.accepts(&data) {
+        return WriteResponseDto::rejected(format!("Expected {:?}", write.input_type));
+    }
+    let Some(value) = adapter_common::render_value(&data, write) else {
+        return WriteResponseDto::rejected("Value can't be rendered into the request");
+    };
+    let Ok(method) = Method::from_str(&write.method) else {
+        return WriteResponseDto::failed(format!("Invalid method {}", write.method));
+    };
+
+    let mut request = CLIENT
+        .request(method, template::render_url(&write.url, &value))
+        .headers(headers(&write.headers))
+        .timeout(Duration::from_millis(adapter.timeout_ms));
+    if let Some(body) = &write.body {
+        request = request.body(template::render_body(body, &value));
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => WriteResponseDto::applied(data),
+        Ok(response) => {
+            WriteResponseDto::failed(format!("Device answered with {}", response.status()))
+        }
+        Err(e) => WriteResponseDto::failed(format!("Device not reachable: {e}")),
+    }
+}
+
+fn headers(headers: &BTreeMap<String, String>) -> HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_str(name).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            ))
+        })
+        .collect()
+}
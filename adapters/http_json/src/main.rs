@@ -0,0 +1,16 @@
+use http_json_adapter::{adapter_device, config::AdapterConfig};
+
+#[tokio::main]
+async fn main() {
+    let config_path =
+        adapter_common::init::<AdapterConfig>("./config/http_json_adapter/config.json", 6010);
+
+    let device_service = match adapter_device(&config_path) {
+        Ok(device_service) => device_service,
+        Err(e) => {
+            tracing::error!("Invalid adapter config {config_path}: {e}");
+            std::process::exit(1);
+        }
+    };
+    adapter_common::serve(device_service).await;
+}
@@ -0,0 +1,54 @@
+const PLACEHOLDER: &str = "{{value}}";
+
+/// Url of the write request, with the value percent-encoded.
+pub fn render_url(template: &str, value: &str) -> String {
+    template.replace(PLACEHOLDER, &percent_encode(value))
+}
+
+/// Body of the write request, with the value escaped for a JSON string so
+/// quotes or backslashes in it can't change the structure of the body.
+pub fn render_body(template: &str, value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    template.replace(PLACEHOLDER, &quoted[1..quoted.len() - 1])
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_templates() {
+        assert_eq!(
+            render_url("http://shelly/relay/0?turn={{value}}", "on"),
+            "http://shelly/relay/0?turn=on"
+        );
+        assert_eq!(
+            render_url("http://tasmota/cm?cmnd=Power {{value}}", "ON OFF"),
+            "http://tasmota/cm?cmnd=Power ON%20OFF"
+        );
+        assert_eq!(
+            render_body(r#"{"target": {{value}}}"#, "21.5"),
+            r#"{"target": 21.5}"#
+        );
+    }
+
+    #[test]
+    fn escapes_body_values() {
+        let value = r#"a\", "admin": "true"#;
+        let body = render_body(r#"{"name": "{{value}}"}"#, value);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body, serde_json::json!({ "name": value }));
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Json, Router,
+    body::{Body, Bytes, to_bytes},
+    extract::{RawQuery, State},
+    http::{Request, StatusCode, header},
+    routing::{get, post},
+};
+use greenhouse_core::{
+    smart_device_dto::{
+        Type,
+        read::ReadResponseDto,
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
+    },
+    smart_device_interface::hybrid_device::init_hybrid_router,
+};
+use http_json_adapter::adapter_device;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+type Recorded = Arc<Mutex<Vec<(Option<String>, String)>>>;
+
+/// Shelly-like vendor API recording the relay requests it receives.
+async fn mock_server() -> (String, Recorded) {
+    let recorded = Recorded::default();
+    let router = Router::new()
+        .route(
+            "/status",
+            get(|| async {
+                Json(json!({
+                    "tmp": {"tC": 21.5},
+                    "relays": [{"ison": true}],
+                    "wifi_sta": {"ssid": "greenhouse"},
+                    "meters": [{"power": "12.5"}]
+                }))
+            }),
+        )
+        .route(
+            "/relay/0",
+            post(
+                |State(recorded): State<Recorded>, RawQuery(query): RawQuery, body: Bytes| async move {
+                    let body = String::from_utf8_lossy(&body).to_string();
+                    recorded.lock().unwrap().push((query, body));
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(recorded.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    (format!("http://{address}"), recorded)
+}
+
+fn write_config(name: &str, additional_config: Value) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "http_json_adapter_{name}_{}.json",
+        std::process::id()
+    ));
+    let config = json!({
+        "port": 0,
+        "datasource_id": "",
+        "additional_config": additional_config,
+    });
+    std::fs::write(&path, config.to_string()).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+async fn call(router: Router, request: Request<Body>, status: StatusCode) -> Value {
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), status);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn polls_and_writes_through_the_vendor_api() {
+    let (url, recorded) = mock_server().await;
+    let config_path = write_config(
+        "shelly",
+        json!({
+            "sources": [
+                {
+                    "url": format!("{url}/status"),
+                    "channels": [
+                        {"name": "temperature", "path": "$.tmp.tC", "type": "Number", "unit": "°C"},
+                        {"name": "relay", "path": "$.relays[0].ison", "type": "Boolean"},
+                        {"name": "ssid", "path": "$.wifi_sta.ssid", "type": "Text"},
+                        {"name": "power", "path": "$.meters[0].power", "type": "Number"},
+                        {"name": "missing", "path": "$.ext_temperature", "type": "Number"}
+                    ]
+                },
+                {
+                    "url": format!("{url}/offline"),
+                    "channels": [{"name": "offline", "path": "$.value", "type": "Number"}]
+                }
+            ],
+            "write": {
+                "url": format!("{url}/relay/0?turn={{{{value}}}}"),
+                "body": "{\"source\": \"greenhouse\", \"turn\": \"{{value}}\"}",
+                "type": "Boolean",
+                "true_value": "on",
+                "false_value": "off"
+            },
+            "timeout_ms": 1000
+        }),
+    );
+    let device = adapter_device(&config_path).unwrap();
+    let router = init_hybrid_router(device);
+
+    let read: ReadResponseDto = serde_json::from_value(
+        call(
+            router.clone(),
+            Request::get("/read").body(Body::empty()).unwrap(),
+            StatusCode::OK,
+        )
+        .await,
+    )
+    .unwrap();
+    let Type::Object(channels) = read.data else {
+        panic!("Expected an object, got {:?}", read.data);
+    };
+    assert!(
+        matches!(&channels["temperature"], Type::Measurement(m) if m.value == 21.5 && m.unit == "°C")
+    );
+    assert!(matches!(channels["relay"], Type::Boolean(true)));
+    assert!(matches!(&channels["ssid"], Type::Text(ssid) if ssid == "greenhouse"));
+    assert!(matches!(channels["power"], Type::Number(n) if n == 12.5));
+    assert!(matches!(channels["missing"], Type::None));
+    assert!(matches!(channels["offline"], Type::None));
+
+    let write = |data| {
+        Request::post("/write")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&WriteRequestDto {
+                    data,
+                    idempotency_key: None,
+                })
+                .unwrap(),
+            ))
+            .unwrap()
+    };
+    let applied: WriteResponseDto = serde_json::from_value(
+        call(router.clone(), write(Type::Boolean(false)), StatusCode::OK).await,
+    )
+    .unwrap();
+    assert_eq!(applied.status, WriteStatus::Applied);
+    assert_eq!(
+        recorded.lock().unwrap().as_slice(),
+        [(
+            Some(String::from("turn=off")),
+            String::from("{\"source\": \"greenhouse\", \"turn\": \"off\"}")
+        )]
+    );
+
+    let rejected: WriteResponseDto = serde_json::from_value(
+        call(router, write(Type::Number(1.0)), StatusCode::BAD_REQUEST).await,
+    )
+    .unwrap();
+    assert_eq!(rejected.status, WriteStatus::Rejected);
+    assert_eq!(recorded.lock().unwrap().len(), 1);
+
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_invalid_configs() {
+    let config_path = write_config(
+        "invalid",
+        json!({
+            "sources": [{
+                "url": "not a url",
+                "channels": [{"name": "a", "path": "tmp..", "type": "Object"}]
+            }]
+        }),
+    );
+    assert!(adapter_device(&config_path).is_err());
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_unknown_units() {
+    let config_path = write_config(
+        "unit",
+        json!({
+            "sources": [{
+                "url": "http://localhost/status",
+                "channels": [{"name": "a", "path": "$.tmp", "type": "Number", "unit": "degrees"}]
+            }]
+        }),
+    );
+    assert!(adapter_device(&config_path).is_err());
+    std::fs::remove_file(config_path).unwrap();
+}
+
+#[tokio::test]
+async fn refuses_remote_changes_of_the_router_type() {
+    let config_path = write_config(
+        "router_type",
+        json!({
+            "write": {"url": "http://relay/{{value}}", "type": "Boolean"}
+        }),
+    );
+    let router = init_hybrid_router(adapter_device(&config_path).unwrap());
+    let update = |additional_config: Value| {
+        Request::post("/config")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({ "additional_config": additional_config }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let errors = call(
+        router.clone(),
+        update(json!({})),
+        StatusCode::UNPROCESSABLE_ENTITY,
+    )
+    .await;
+    assert_eq!(errors["errors"][0]["field"], "write");
+    call(
+        router.clone(),
+        update(json!({"write": {"url": "http://relay/{{value}}", "type": "Number"}})),
+        StatusCode::UNPROCESSABLE_ENTITY,
+    )
+    .await;
+    // The request itself may still change
+    let response = router
+        .oneshot(update(
+            json!({"write": {"url": "http://other-relay/{{value}}", "type": "Boolean"}}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    std::fs::remove_file(config_path).unwrap();
+}
@@ -12,17 +12,18 @@ use crate::{AppState, auth::Error};
 pub(crate) async fn check_token(
     State(AppState { config }): State<AppState>,
     cookies: Cookies,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Response {
     if let Ok(token) = cookies
         .get(AUTH_TOKEN)
         .map(|c| c.value().to_string())
         .ok_or(Error::CookieNotFound)
-        && service::check_token(&config.service_addresses.scripting_service, &token)
-            .await
-            .is_ok()
+        && let Ok(key) =
+            service::check_token(&config.service_addresses.scripting_service, &token).await
     {
+        // Handed to the handlers so they know who sent the request
+        req.extensions_mut().insert(key);
         return next.run(req).await;
     }
 
@@ -5,10 +5,14 @@ use crate::{
 
 use greenhouse_core::{
     http_error::ErrorResponseBody,
-    scripting_service_dto::{endpoints, token::TokenDto},
+    scripting_service_dto::{
+        endpoints,
+        token::{ScriptingKeyDto, TokenDto},
+    },
 };
 
-pub(crate) async fn check_token(base_ulr: &str, token: &str) -> Result<()> {
+/// Checks the scripting key, returns the datasource it was issued to.
+pub(crate) async fn check_token(base_ulr: &str, token: &str) -> Result<ScriptingKeyDto> {
     let resp = reqwest::Client::new()
         .post(base_ulr.to_string() + endpoints::CHECK_TOKEN)
         .json(&TokenDto {
@@ -29,7 +33,11 @@ pub(crate) async fn check_token(base_ulr: &str, token: &str) -> Result<()> {
             Error::Request(e)
         })?;
     if resp.status().is_success() {
-        return Ok(());
+        return resp.json::<ScriptingKeyDto>().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
     }
     Err(Error::Api(ApiError {
         status: resp.status(),
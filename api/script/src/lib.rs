@@ -13,6 +13,7 @@ use tower_http::{cors::CorsLayer, trace::TraceLayer};
 pub(crate) mod alert;
 pub(crate) mod auth;
 pub(crate) mod helper;
+pub(crate) mod logs;
 
 #[derive(Clone, Deserialize)]
 pub struct ServiceAddresses {
@@ -59,6 +60,7 @@ pub fn app(config: Config) -> Router {
         ]);
     Router::new()
         .nest("/alert", alert::router::routes(state.clone()))
+        .nest("/logs", logs::router::routes(state.clone()))
         .layer(middleware::from_fn_with_state(state.clone(), check_token))
         .layer(CookieManagerLayer::new())
         .layer(cors)
@@ -0,0 +1,74 @@
+use crate::AppState;
+use axum::{Extension, Json, Router, routing::post};
+use greenhouse_core::{
+    scripting_service_dto::token::ScriptingKeyDto,
+    smart_device_dto::logs::{LogBatchDto, LogLevel},
+};
+use reqwest::StatusCode;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new().route("/", post(ship_logs)).with_state(state)
+}
+
+/// Re-emits the records of a device so they end up in the central log
+/// pipeline, tagged with the datasource the scripting key was issued to. The
+/// datasource in the batch is ignored, a device could claim any other one.
+#[axum::debug_handler]
+pub(crate) async fn ship_logs(
+    Extension(key): Extension<ScriptingKeyDto>,
+    Json(batch): Json<LogBatchDto>,
+) -> StatusCode {
+    let Some(datasource_id) = key.datasource_id else {
+        tracing::debug!("Refused logs sent with a key not bound to a datasource");
+        return StatusCode::FORBIDDEN;
+    };
+    for record in batch.records {
+        let datasource_id = datasource_id.as_str();
+        let timestamp = record.timestamp.to_rfc3339();
+        let target = record.target.as_str();
+        let message = record.message.as_str();
+        match record.level {
+            LogLevel::Trace => {
+                tracing::trace!(
+                    datasource_id,
+                    timestamp,
+                    device_target = target,
+                    "{message}"
+                )
+            }
+            LogLevel::Debug => {
+                tracing::debug!(
+                    datasource_id,
+                    timestamp,
+                    device_target = target,
+                    "{message}"
+                )
+            }
+            LogLevel::Info => {
+                tracing::info!(
+                    datasource_id,
+                    timestamp,
+                    device_target = target,
+                    "{message}"
+                )
+            }
+            LogLevel::Warn => {
+                tracing::warn!(
+                    datasource_id,
+                    timestamp,
+                    device_target = target,
+                    "{message}"
+                )
+            }
+            LogLevel::Error => {
+                tracing::error!(
+                    datasource_id,
+                    timestamp,
+                    device_target = target,
+                    "{message}"
+                )
+            }
+        }
+    }
+    StatusCode::OK
+}
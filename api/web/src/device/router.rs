@@ -1,19 +1,38 @@
 use crate::{AppState, device::service, helper::error::HttpResult};
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::{Path, Query, State},
     http::HeaderValue,
     response::IntoResponse,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
 };
-use greenhouse_core::device_service_dto::{
-    endpoints::{ACTIVATE, CONFIG, STATUS},
-    get_device::{DeviceResponseDto, DevicesResponseDto},
-    get_timeseries::GetTimeseriesDto,
-    operations::OperationsDto,
-    post_device::PostDeviceDtoRequest,
-    put_device::PutDeviceDtoRequest,
-    query::PromQuery,
+use greenhouse_core::{
+    auth_service_dto::token::TokenResponseDto,
+    device_service_dto::{
+        availability::AvailabilityHistoryDto,
+        delete_device::DeleteDeviceQuery,
+        endpoints::{
+            ACTIONS, ACTIVATE, AVAILABILITY, CALIBRATION, CONFIG, LOGS, RESTORE, STATUS, TUNNEL,
+            WRITE, WRITES,
+        },
+        get_device::{DeviceResponseDto, DevicesQuery, DevicesResponseDto},
+        get_timeseries::GetTimeseriesDto,
+        operations::OperationsDto,
+        post_device::PostDeviceDtoRequest,
+        put_device::PutDeviceDtoRequest,
+        query::PromQuery,
+        tunnel::TunnelTokenResponseDto,
+        write::{WriteAuditsDto, WriteQuery},
+    },
+    smart_device_dto::{
+        action::{ActionRequestDto, ActionResponseDto, ActionsResponseDto},
+        calibration::{
+            Calibration, CalibrationReferenceRequestDto, CalibrationRequestDto,
+            CalibrationsResponseDto,
+        },
+        logs::{LogsQuery, LogsResponseDto},
+        write::{WriteRequestDto, WriteResponseDto},
+    },
 };
 use reqwest::{StatusCode, header};
 use uuid::Uuid;
@@ -24,10 +43,37 @@ pub(crate) fn routes(state: AppState) -> Router {
         .route("/", get(get_devices))
         .route("/{id}", put(update_device))
         .route("/{id}", get(get_device))
+        .route("/{id}", delete(delete_device))
+        .route(&format!("/{{id}}/{RESTORE}"), post(restore_device))
         .route(&format!("/{{id}}/{ACTIVATE}"), put(activate_device))
         .route(&format!("/{{id}}/{CONFIG}"), put(update_device_config))
         .route(&format!("/{{id}}/{CONFIG}"), get(get_device_config))
         .route(&format!("/{{id}}/{STATUS}"), get(get_device_status))
+        .route(
+            &format!("/{{id}}/{AVAILABILITY}"),
+            get(get_device_availability),
+        )
+        .route(&format!("/{{id}}/{LOGS}"), get(get_device_logs))
+        .route(
+            &format!("/{{id}}/{CALIBRATION}"),
+            get(get_device_calibration),
+        )
+        .route(
+            &format!("/{{id}}/{CALIBRATION}/{{channel}}"),
+            put(update_device_calibration).delete(delete_device_calibration),
+        )
+        .route(
+            &format!("/{{id}}/{CALIBRATION}/{{channel}}/reference"),
+            post(reference_device_calibration),
+        )
+        .route(&format!("/{{id}}/{ACTIONS}"), get(get_device_actions))
+        .route(
+            &format!("/{{id}}/{ACTIONS}/{{name}}"),
+            post(invoke_device_action),
+        )
+        .route(&format!("/{{id}}/{WRITE}"), post(write_device))
+        .route(&format!("/{{id}}/{WRITES}"), get(get_device_writes))
+        .route(&format!("/{{id}}/{TUNNEL}"), post(issue_tunnel_token))
         .route("/{id}/timeseries", get(get_device_timeseries))
         .route("/{id}/options", get(get_device_operations))
         .with_state(state)
@@ -44,8 +90,9 @@ pub(crate) async fn create_device(
 #[axum::debug_handler]
 pub(crate) async fn get_devices(
     State(AppState { config }): State<AppState>,
+    Query(query): Query<DevicesQuery>,
 ) -> HttpResult<DevicesResponseDto> {
-    Ok(service::get_devices(&config.service_addresses.device_service).await?)
+    Ok(service::get_devices(&config.service_addresses.device_service, query).await?)
 }
 
 #[axum::debug_handler]
@@ -107,6 +154,145 @@ pub(crate) async fn get_device_status(
     ))
 }
 
+#[axum::debug_handler]
+pub(crate) async fn get_device_logs(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<LogsQuery>,
+) -> HttpResult<Json<LogsResponseDto>> {
+    Ok(Json(
+        service::get_device_logs(&config.service_addresses.device_service, id, query).await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_calibration(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<Json<CalibrationsResponseDto>> {
+    Ok(Json(
+        service::get_device_calibration(&config.service_addresses.device_service, id).await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_device_calibration(
+    State(AppState { config }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+    Json(body): Json<CalibrationRequestDto>,
+) -> HttpResult<Json<Calibration>> {
+    Ok(Json(
+        service::update_device_calibration(
+            &config.service_addresses.device_service,
+            id,
+            &channel,
+            body,
+        )
+        .await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_device_calibration(
+    State(AppState { config }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+) -> HttpResult<StatusCode> {
+    service::delete_device_calibration(&config.service_addresses.device_service, id, &channel)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn reference_device_calibration(
+    State(AppState { config }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+    Json(body): Json<CalibrationReferenceRequestDto>,
+) -> HttpResult<Json<Calibration>> {
+    Ok(Json(
+        service::reference_device_calibration(
+            &config.service_addresses.device_service,
+            id,
+            &channel,
+            body,
+        )
+        .await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_availability(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<Json<AvailabilityHistoryDto>> {
+    Ok(Json(
+        service::get_device_availability(&config.service_addresses.device_service, id).await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_actions(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<Json<ActionsResponseDto>> {
+    Ok(Json(
+        service::get_device_actions(&config.service_addresses.device_service, id).await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn invoke_device_action(
+    State(AppState { config }): State<AppState>,
+    Path((id, name)): Path<(Uuid, String)>,
+    Json(body): Json<ActionRequestDto>,
+) -> HttpResult<Json<ActionResponseDto>> {
+    Ok(Json(
+        service::invoke_device_action(&config.service_addresses.device_service, id, &name, body)
+            .await?,
+    ))
+}
+
+/// Writes are recorded with the name of the user doing them.
+#[axum::debug_handler]
+pub(crate) async fn write_device(
+    State(AppState { config }): State<AppState>,
+    Extension(user): Extension<TokenResponseDto>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<WriteRequestDto>,
+) -> HttpResult<Json<WriteResponseDto>> {
+    let query = WriteQuery {
+        actor: Some(user.user_name),
+    };
+    Ok(Json(
+        service::write_device(&config.service_addresses.device_service, id, query, body).await?,
+    ))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_writes(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<WriteAuditsDto> {
+    Ok(service::get_device_writes(&config.service_addresses.device_service, id).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_device(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteDeviceQuery>,
+) -> HttpResult<StatusCode> {
+    service::delete_device(&config.service_addresses.device_service, id, query).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn restore_device(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<DeviceResponseDto> {
+    Ok(service::restore_device(&config.service_addresses.device_service, id).await?)
+}
+
 #[axum::debug_handler]
 pub(crate) async fn activate_device(
     State(AppState { config }): State<AppState>,
@@ -116,6 +302,14 @@ pub(crate) async fn activate_device(
     Ok(StatusCode::OK)
 }
 
+#[axum::debug_handler]
+pub(crate) async fn issue_tunnel_token(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<TunnelTokenResponseDto> {
+    Ok(service::issue_tunnel_token(&config.service_addresses.device_service, id).await?)
+}
+
 #[axum::debug_handler]
 pub(crate) async fn get_device_timeseries(
     State(AppState { config }): State<AppState>,
@@ -1,15 +1,29 @@
 use greenhouse_core::{
     device_service_dto::{
+        availability::AvailabilityHistoryDto,
+        delete_device::DeleteDeviceQuery,
         endpoints,
-        get_device::{DeviceResponseDto, DevicesResponseDto},
+        get_device::{DeviceResponseDto, DevicesQuery, DevicesResponseDto},
         get_timeseries::GetTimeseriesDto,
         operations::OperationsDto,
         post_device::PostDeviceDtoRequest,
         put_device::PutDeviceDtoRequest,
         query::PromQuery,
+        tunnel::TunnelTokenResponseDto,
+        write::{WriteAuditsDto, WriteQuery},
     },
     http_error::ErrorResponseBody,
+    smart_device_dto::{
+        action::{ActionRequestDto, ActionResponseDto, ActionsResponseDto},
+        calibration::{
+            Calibration, CalibrationReferenceRequestDto, CalibrationRequestDto,
+            CalibrationsResponseDto,
+        },
+        logs::{LogsQuery, LogsResponseDto},
+        write::{WriteRequestDto, WriteResponseDto},
+    },
 };
+use reqwest::StatusCode;
 use uuid::Uuid;
 
 use crate::{
@@ -246,6 +260,18 @@ pub(crate) async fn update_device_config(
     if resp.status().is_success() {
         return Ok(());
     }
+    if resp.status() == StatusCode::UNPROCESSABLE_ENTITY {
+        let body = resp.json::<ErrorResponseBody>().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        })?;
+        let errors = body
+            .context()
+            .and_then(|context| serde_json::from_value(context.clone()).ok())
+            .unwrap_or_default();
+        return Err(Error::ConfigValidation(errors));
+    }
     Err(Error::Api(ApiError {
         status: resp.status(),
         message: resp.text().await.map_err(|e| {
@@ -256,9 +282,10 @@ pub(crate) async fn update_device_config(
     }))
 }
 
-pub(crate) async fn get_devices(base_url: &str) -> Result<DevicesResponseDto> {
+pub(crate) async fn get_devices(base_url: &str, query: DevicesQuery) -> Result<DevicesResponseDto> {
     let resp = reqwest::Client::new()
         .get(base_url.to_string())
+        .query(&query)
         .send()
         .await
         .map_err(|e| {
@@ -318,6 +345,34 @@ pub(crate) async fn activate_device(base_url: &str, id: Uuid) -> Result<()> {
     }))
 }
 
+pub(crate) async fn issue_tunnel_token(base_url: &str, id: Uuid) -> Result<TunnelTokenResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::TUNNEL)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
 pub(crate) async fn get_device_timeseries(
     base_url: &str,
     id: Uuid,
@@ -386,3 +441,441 @@ pub(crate) async fn get_device_operations(base_url: &str, id: Uuid) -> Result<Op
             .error,
     }))
 }
+
+pub(crate) async fn get_device_calibration(
+    base_url: &str,
+    id: Uuid,
+) -> Result<CalibrationsResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::CALIBRATION)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn update_device_calibration(
+    base_url: &str,
+    id: Uuid,
+    channel: &str,
+    body: CalibrationRequestDto,
+) -> Result<Calibration> {
+    let resp = reqwest::Client::new()
+        .put(
+            base_url.to_string()
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::CALIBRATION
+                + "/"
+                + channel,
+        )
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn delete_device(
+    base_url: &str,
+    id: Uuid,
+    query: DeleteDeviceQuery,
+) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(base_url.to_string() + "/" + &id.to_string())
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn restore_device(base_url: &str, id: Uuid) -> Result<DeviceResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::RESTORE)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn delete_device_calibration(
+    base_url: &str,
+    id: Uuid,
+    channel: &str,
+) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(
+            base_url.to_string()
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::CALIBRATION
+                + "/"
+                + channel,
+        )
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn reference_device_calibration(
+    base_url: &str,
+    id: Uuid,
+    channel: &str,
+    body: CalibrationReferenceRequestDto,
+) -> Result<Calibration> {
+    let resp = reqwest::Client::new()
+        .post(
+            base_url.to_string()
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::CALIBRATION
+                + "/"
+                + channel
+                + "/reference",
+        )
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn get_device_availability(
+    base_url: &str,
+    id: Uuid,
+) -> Result<AvailabilityHistoryDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::AVAILABILITY)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn get_device_actions(base_url: &str, id: Uuid) -> Result<ActionsResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::ACTIONS)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp.text().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        })?,
+    }))
+}
+
+pub(crate) async fn invoke_device_action(
+    base_url: &str,
+    id: Uuid,
+    name: &str,
+    body: ActionRequestDto,
+) -> Result<ActionResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::ACTIONS + "/" + name)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    if resp.status() == StatusCode::UNPROCESSABLE_ENTITY {
+        let body = resp.json::<ErrorResponseBody>().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        })?;
+        let errors = body
+            .context()
+            .and_then(|context| serde_json::from_value(context.clone()).ok())
+            .unwrap_or_default();
+        return Err(Error::ActionValidation(errors));
+    }
+    let status = resp.status();
+    let message = resp.text().await.map_err(|e| {
+        sentry::capture_error(&e);
+        tracing::error!("Error in post to service: {:?}", e);
+        Error::Json(e)
+    })?;
+    // Rejected and failed actions carry the reason given by the device
+    let message = serde_json::from_str::<ActionResponseDto>(&message)
+        .ok()
+        .and_then(|response| response.error)
+        .unwrap_or(message);
+    Err(Error::Api(ApiError { status, message }))
+}
+
+pub(crate) async fn write_device(
+    base_url: &str,
+    id: Uuid,
+    query: WriteQuery,
+    body: WriteRequestDto,
+) -> Result<WriteResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::WRITE)
+        .query(&query)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    if resp.status() == StatusCode::UNPROCESSABLE_ENTITY {
+        let body = resp.json::<ErrorResponseBody>().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        })?;
+        let errors = body
+            .context()
+            .and_then(|context| serde_json::from_value(context.clone()).ok())
+            .unwrap_or_default();
+        return Err(Error::WriteValidation(errors));
+    }
+    let status = resp.status();
+    let message = resp.text().await.map_err(|e| {
+        sentry::capture_error(&e);
+        tracing::error!("Error in post to service: {:?}", e);
+        Error::Json(e)
+    })?;
+    // Rejected and failed writes carry the reason given by the device
+    let message = serde_json::from_str::<WriteResponseDto>(&message)
+        .ok()
+        .and_then(|response| response.error)
+        .unwrap_or(message);
+    Err(Error::Api(ApiError { status, message }))
+}
+
+pub(crate) async fn get_device_writes(base_url: &str, id: Uuid) -> Result<WriteAuditsDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::WRITES)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_device_logs(
+    base_url: &str,
+    id: Uuid,
+    query: LogsQuery,
+) -> Result<LogsResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + "/" + &id.to_string() + "/" + endpoints::LOGS)
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
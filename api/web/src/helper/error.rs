@@ -1,5 +1,8 @@
 use axum::http::StatusCode;
-use greenhouse_core::http_error::{HttpErrorMapping, HttpErrorResponse};
+use greenhouse_core::{
+    http_error::{HttpErrorMapping, HttpErrorResponse},
+    smart_device_dto::config::ValidationErrors,
+};
 
 pub(crate) type Result<T> = core::result::Result<T, Error>;
 pub(crate) type HttpResult<T> = core::result::Result<T, HttpErrorResponse<Error>>;
@@ -18,6 +21,9 @@ pub(crate) enum Error {
     Api(ApiError),
     Request(reqwest::Error),
     Json(reqwest::Error),
+    ConfigValidation(ValidationErrors),
+    ActionValidation(ValidationErrors),
+    WriteValidation(ValidationErrors),
 }
 
 // region:    --- Error Boilerplate
@@ -41,6 +47,9 @@ impl HttpErrorMapping for Error {
                 None => StatusCode::INTERNAL_SERVER_ERROR,
             },
             Error::Json(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ConfigValidation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::ActionValidation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::WriteValidation(_) => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 
@@ -52,6 +61,18 @@ impl HttpErrorMapping for Error {
             Error::Api(e) => e.message.clone(),
             Error::Request(e) => e.to_string(),
             Error::Json(e) => e.to_string(),
+            Error::ConfigValidation(_) => String::from("Invalid config"),
+            Error::ActionValidation(_) => String::from("Invalid action parameters"),
+            Error::WriteValidation(_) => String::from("Invalid write"),
+        }
+    }
+
+    fn to_error_context(&self) -> Option<serde_json::Value> {
+        match self {
+            Error::ConfigValidation(errors)
+            | Error::ActionValidation(errors)
+            | Error::WriteValidation(errors) => serde_json::to_value(errors).ok(),
+            _ => None,
         }
     }
 }
@@ -15,7 +15,10 @@ pub(crate) mod auth;
 pub(crate) mod device;
 pub(crate) mod diary;
 pub(crate) mod helper;
+pub(crate) mod rule;
+pub(crate) mod schedule;
 pub(crate) mod settings;
+pub(crate) mod zone;
 
 #[derive(Clone, Deserialize)]
 pub struct ServiceAddresses {
@@ -67,6 +70,9 @@ pub fn app(config: Config) -> Router {
         .nest("/api/diary", diary::router::routes(state.clone()))
         .nest("/api/alert", alert::router::routes(state.clone()))
         .nest("/api/device", device::router::routes(state.clone()))
+        .nest("/api/zone", zone::router::routes(state.clone()))
+        .nest("/api/schedule", schedule::router::routes(state.clone()))
+        .nest("/api/rule", rule::router::routes(state.clone()))
         .nest("/api/user", auth::router::user_routes(state.clone()))
         .layer(middleware::from_fn_with_state(state.clone(), check_token))
         .merge(auth::router::auth_routes(state))
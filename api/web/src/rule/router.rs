@@ -0,0 +1,91 @@
+use crate::{AppState, helper::error::HttpResult, rule::service};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use greenhouse_core::device_service_dto::{
+    endpoints::{DISABLE, ENABLE, EVALUATIONS},
+    rule::{
+        RuleEvaluationsDto, RuleEvaluationsQuery, RuleRequestDto, RuleResponseDto, RulesResponseDto,
+    },
+};
+use uuid::Uuid;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_rules).post(create_rule))
+        .route("/{id}", get(get_rule).put(update_rule).delete(delete_rule))
+        .route(&format!("/{{id}}/{ENABLE}"), post(enable_rule))
+        .route(&format!("/{{id}}/{DISABLE}"), post(disable_rule))
+        .route(&format!("/{{id}}/{EVALUATIONS}"), get(get_evaluations))
+        .with_state(state)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_rules(
+    State(AppState { config }): State<AppState>,
+) -> HttpResult<RulesResponseDto> {
+    Ok(service::get_rules(&config.service_addresses.device_service).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_rule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(service::get_rule(&config.service_addresses.device_service, id).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_rule(
+    State(AppState { config }): State<AppState>,
+    Json(rule): Json<RuleRequestDto>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(service::create_rule(&config.service_addresses.device_service, rule).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_rule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(rule): Json<RuleRequestDto>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(service::update_rule(&config.service_addresses.device_service, id, rule).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_rule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    service::delete_rule(&config.service_addresses.device_service, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn enable_rule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(service::set_rule_enabled(&config.service_addresses.device_service, id, true).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn disable_rule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(service::set_rule_enabled(&config.service_addresses.device_service, id, false).await?)
+}
+
+/// Logged evaluations of the rule, newest first.
+#[axum::debug_handler]
+pub(crate) async fn get_evaluations(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<RuleEvaluationsQuery>,
+) -> HttpResult<RuleEvaluationsDto> {
+    Ok(service::get_evaluations(&config.service_addresses.device_service, id, query).await?)
+}
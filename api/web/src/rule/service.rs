@@ -0,0 +1,274 @@
+use greenhouse_core::{
+    device_service_dto::{
+        endpoints,
+        rule::{
+            RuleEvaluationsDto, RuleEvaluationsQuery, RuleRequestDto, RuleResponseDto,
+            RulesResponseDto,
+        },
+    },
+    http_error::ErrorResponseBody,
+};
+use uuid::Uuid;
+
+use crate::{
+    helper::error::ApiError,
+    rule::{Error, Result},
+};
+
+pub(crate) async fn get_rules(base_url: &str) -> Result<RulesResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::RULE)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_rule(base_url: &str, id: Uuid) -> Result<RuleResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::RULE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn create_rule(base_url: &str, rule: RuleRequestDto) -> Result<RuleResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + endpoints::RULE)
+        .json(&rule)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in post to service: {:?} with rule: {:?} for url {}",
+                e,
+                rule,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in post to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn update_rule(
+    base_url: &str,
+    id: Uuid,
+    rule: RuleRequestDto,
+) -> Result<RuleResponseDto> {
+    let resp = reqwest::Client::new()
+        .put(base_url.to_string() + endpoints::RULE + "/" + &id.to_string())
+        .json(&rule)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in put to service: {:?} with rule: {:?} for url {}",
+                e,
+                rule,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in put to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn delete_rule(base_url: &str, id: Uuid) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(base_url.to_string() + endpoints::RULE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in delete to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+/// Enables or disables the rule.
+pub(crate) async fn set_rule_enabled(
+    base_url: &str,
+    id: Uuid,
+    enabled: bool,
+) -> Result<RuleResponseDto> {
+    let action = if enabled {
+        endpoints::ENABLE
+    } else {
+        endpoints::DISABLE
+    };
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + endpoints::RULE + "/" + &id.to_string() + "/" + action)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in post to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_evaluations(
+    base_url: &str,
+    id: Uuid,
+    query: RuleEvaluationsQuery,
+) -> Result<RuleEvaluationsDto> {
+    let resp = reqwest::Client::new()
+        .get(
+            base_url.to_string()
+                + endpoints::RULE
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::EVALUATIONS,
+        )
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
@@ -0,0 +1,105 @@
+use crate::{AppState, helper::error::HttpResult, schedule::service};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use greenhouse_core::device_service_dto::{
+    endpoints::{DISABLE, ENABLE, EXECUTIONS, NEXT_RUNS},
+    schedule::{
+        NextRunsDto, NextRunsQuery, ScheduleExecutionsDto, ScheduleRequestDto, ScheduleResponseDto,
+        SchedulesResponseDto,
+    },
+};
+use uuid::Uuid;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_schedules).post(create_schedule))
+        .route(
+            "/{id}",
+            get(get_schedule)
+                .put(update_schedule)
+                .delete(delete_schedule),
+        )
+        .route(&format!("/{{id}}/{ENABLE}"), post(enable_schedule))
+        .route(&format!("/{{id}}/{DISABLE}"), post(disable_schedule))
+        .route(&format!("/{{id}}/{NEXT_RUNS}"), get(get_next_runs))
+        .route(&format!("/{{id}}/{EXECUTIONS}"), get(get_executions))
+        .with_state(state)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_schedules(
+    State(AppState { config }): State<AppState>,
+) -> HttpResult<SchedulesResponseDto> {
+    Ok(service::get_schedules(&config.service_addresses.device_service).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_schedule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(service::get_schedule(&config.service_addresses.device_service, id).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_schedule(
+    State(AppState { config }): State<AppState>,
+    Json(schedule): Json<ScheduleRequestDto>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(service::create_schedule(&config.service_addresses.device_service, schedule).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_schedule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(schedule): Json<ScheduleRequestDto>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(service::update_schedule(&config.service_addresses.device_service, id, schedule).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_schedule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    service::delete_schedule(&config.service_addresses.device_service, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn enable_schedule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(service::set_schedule_enabled(&config.service_addresses.device_service, id, true).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn disable_schedule(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(service::set_schedule_enabled(&config.service_addresses.device_service, id, false).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_next_runs(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<NextRunsQuery>,
+) -> HttpResult<NextRunsDto> {
+    Ok(service::get_next_runs(&config.service_addresses.device_service, id, query).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_executions(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleExecutionsDto> {
+    Ok(service::get_executions(&config.service_addresses.device_service, id).await?)
+}
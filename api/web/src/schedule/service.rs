@@ -0,0 +1,316 @@
+use greenhouse_core::{
+    device_service_dto::{
+        endpoints,
+        schedule::{
+            NextRunsDto, NextRunsQuery, ScheduleExecutionsDto, ScheduleRequestDto,
+            ScheduleResponseDto, SchedulesResponseDto,
+        },
+    },
+    http_error::ErrorResponseBody,
+};
+use uuid::Uuid;
+
+use crate::{
+    helper::error::ApiError,
+    schedule::{Error, Result},
+};
+
+pub(crate) async fn get_schedules(base_url: &str) -> Result<SchedulesResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::SCHEDULE)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_schedule(base_url: &str, id: Uuid) -> Result<ScheduleResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::SCHEDULE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn create_schedule(
+    base_url: &str,
+    schedule: ScheduleRequestDto,
+) -> Result<ScheduleResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + endpoints::SCHEDULE)
+        .json(&schedule)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in post to service: {:?} with schedule: {:?} for url {}",
+                e,
+                schedule,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in post to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn update_schedule(
+    base_url: &str,
+    id: Uuid,
+    schedule: ScheduleRequestDto,
+) -> Result<ScheduleResponseDto> {
+    let resp = reqwest::Client::new()
+        .put(base_url.to_string() + endpoints::SCHEDULE + "/" + &id.to_string())
+        .json(&schedule)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in put to service: {:?} with schedule: {:?} for url {}",
+                e,
+                schedule,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in put to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn delete_schedule(base_url: &str, id: Uuid) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(base_url.to_string() + endpoints::SCHEDULE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in delete to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+/// Enables or disables the schedule.
+pub(crate) async fn set_schedule_enabled(
+    base_url: &str,
+    id: Uuid,
+    enabled: bool,
+) -> Result<ScheduleResponseDto> {
+    let action = if enabled {
+        endpoints::ENABLE
+    } else {
+        endpoints::DISABLE
+    };
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + endpoints::SCHEDULE + "/" + &id.to_string() + "/" + action)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in post to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_next_runs(
+    base_url: &str,
+    id: Uuid,
+    query: NextRunsQuery,
+) -> Result<NextRunsDto> {
+    let resp = reqwest::Client::new()
+        .get(
+            base_url.to_string()
+                + endpoints::SCHEDULE
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::NEXT_RUNS,
+        )
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_executions(base_url: &str, id: Uuid) -> Result<ScheduleExecutionsDto> {
+    let resp = reqwest::Client::new()
+        .get(
+            base_url.to_string()
+                + endpoints::SCHEDULE
+                + "/"
+                + &id.to_string()
+                + "/"
+                + endpoints::EXECUTIONS,
+        )
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
@@ -0,0 +1,3 @@
+pub(crate) mod router;
+pub(crate) mod service;
+pub(crate) use crate::helper::error::{Error, Result};
@@ -0,0 +1,117 @@
+use crate::{AppState, alert, device, helper::error::HttpResult, zone::service};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use greenhouse_core::{
+    data_storage_service_dto::alert_dto::{alert::AlertsDto, query::AlertQuery},
+    device_service_dto::{
+        get_device::DevicesQuery,
+        query::PromQuery,
+        zone::{ZoneRequestDto, ZoneResponseDto, ZoneTimeseriesDto, ZonesResponseDto},
+    },
+};
+use uuid::Uuid;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_zones).post(create_zone))
+        .route("/{id}", get(get_zone).put(update_zone).delete(delete_zone))
+        .route("/{id}/timeseries", get(get_zone_timeseries))
+        .route("/{id}/alert", get(get_zone_alerts))
+        .with_state(state)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_zones(
+    State(AppState { config }): State<AppState>,
+) -> HttpResult<ZonesResponseDto> {
+    Ok(service::get_zones(&config.service_addresses.device_service).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_zone(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ZoneResponseDto> {
+    Ok(service::get_zone(&config.service_addresses.device_service, id).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_zone(
+    State(AppState { config }): State<AppState>,
+    Json(zone): Json<ZoneRequestDto>,
+) -> HttpResult<ZoneResponseDto> {
+    Ok(service::create_zone(&config.service_addresses.device_service, zone).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_zone(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(zone): Json<ZoneRequestDto>,
+) -> HttpResult<ZoneResponseDto> {
+    Ok(service::update_zone(&config.service_addresses.device_service, id, zone).await?)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_zone(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    service::delete_zone(&config.service_addresses.device_service, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_zone_timeseries(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<PromQuery>,
+) -> HttpResult<ZoneTimeseriesDto> {
+    Ok(service::get_zone_timeseries(&config.service_addresses.device_service, id, query).await?)
+}
+
+/// Alerts of every device in the zone and the zones below it, newest first.
+/// The filters of `/api/alert/filter` apply, except for `datasource_id`.
+#[axum::debug_handler]
+pub(crate) async fn get_zone_alerts(
+    State(AppState { config }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AlertQuery>,
+) -> HttpResult<AlertsDto> {
+    service::get_zone(&config.service_addresses.device_service, id).await?;
+    let devices = device::service::get_devices(
+        &config.service_addresses.device_service,
+        DevicesQuery {
+            deleted: false,
+            zone: Some(id),
+            tag: None,
+        },
+    )
+    .await?;
+    let datasource_ids: Vec<Uuid> = devices
+        .devices
+        .iter()
+        .filter_map(|device| device.id.parse().ok())
+        .collect();
+    if datasource_ids.is_empty() {
+        return Ok(Vec::new().into());
+    }
+    let mut alerts = alert::service::get_filtered_alert(
+        &config.service_addresses.data_storage_service,
+        AlertQuery {
+            severity: query.severity,
+            identifier: query.identifier,
+            created_at: query.created_at,
+            datasource_id: None,
+            datasource_ids: Some(datasource_ids),
+        },
+    )
+    .await?
+    .alerts;
+    alerts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(alerts.into())
+}
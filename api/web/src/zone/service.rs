@@ -0,0 +1,223 @@
+use greenhouse_core::{
+    device_service_dto::{
+        endpoints,
+        query::PromQuery,
+        zone::{ZoneRequestDto, ZoneResponseDto, ZoneTimeseriesDto, ZonesResponseDto},
+    },
+    http_error::ErrorResponseBody,
+};
+use uuid::Uuid;
+
+use crate::{
+    helper::error::ApiError,
+    zone::{Error, Result},
+};
+
+pub(crate) async fn get_zones(base_url: &str) -> Result<ZonesResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::ZONE)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_zone(base_url: &str, id: Uuid) -> Result<ZoneResponseDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::ZONE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn create_zone(base_url: &str, zone: ZoneRequestDto) -> Result<ZoneResponseDto> {
+    let resp = reqwest::Client::new()
+        .post(base_url.to_string() + endpoints::ZONE)
+        .json(&zone)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in post to service: {:?} with zone: {:?} for url {}",
+                e,
+                zone,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in post to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in post to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn update_zone(
+    base_url: &str,
+    id: Uuid,
+    zone: ZoneRequestDto,
+) -> Result<ZoneResponseDto> {
+    let resp = reqwest::Client::new()
+        .put(base_url.to_string() + endpoints::ZONE + "/" + &id.to_string())
+        .json(&zone)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!(
+                "Error in put to service: {:?} with zone: {:?} for url {}",
+                e,
+                zone,
+                base_url
+            );
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in put to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in put to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn delete_zone(base_url: &str, id: Uuid) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(base_url.to_string() + endpoints::ZONE + "/" + &id.to_string())
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in delete to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in delete to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
+
+pub(crate) async fn get_zone_timeseries(
+    base_url: &str,
+    id: Uuid,
+    query: PromQuery,
+) -> Result<ZoneTimeseriesDto> {
+    let resp = reqwest::Client::new()
+        .get(base_url.to_string() + endpoints::ZONE + "/" + &id.to_string() + "/timeseries")
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Request(e)
+        })?;
+
+    if resp.status().is_success() {
+        return resp.json().await.map_err(|e| {
+            sentry::capture_error(&e);
+            tracing::error!("Error in get to service: {:?}", e);
+            Error::Json(e)
+        });
+    }
+    Err(Error::Api(ApiError {
+        status: resp.status(),
+        message: resp
+            .json::<ErrorResponseBody>()
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                tracing::error!("Error in get to service: {:?}", e);
+                Error::Json(e)
+            })?
+            .error,
+    }))
+}
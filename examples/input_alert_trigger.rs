@@ -1,18 +1,19 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use axum::http::StatusCode;
 use greenhouse_core::{
     data_storage_service_dto::alert_dto::alert::Severity,
     smart_device_dto::{
         Type,
         config::{ConfigRequestDto, TypeOption},
         status::{DeviceStatusDto, DeviceStatusResponseDto},
+        write::WriteResponseDto,
     },
     smart_device_interface::{
         config::{Config, read_config_file_with_path, update_config_file_with_path},
         device_builder::DeviceBuilder,
         device_service::{AlertCreation, trigger_alert},
         hybrid_device::init_hybrid_router,
+        tls, tunnel,
     },
 };
 use serde_derive::{Deserialize, Serialize};
@@ -43,6 +44,9 @@ async fn main() {
                 datasource_id: "7a224a14-6e07-45a3-91da-b7584a5731c1".to_string(),
                 additional_config: ExampleDeviceConfig { min: 0, max: 10 },
                 scripting_api: None,
+                calibration: Default::default(),
+                tunnel: None,
+                state: Default::default(),
             };
 
             // Create config directory if it doesn't exist
@@ -70,20 +74,20 @@ async fn main() {
         TypeOption::Number,
     )
     .unwrap();
-    let router = init_hybrid_router(device_service);
+    let router = init_hybrid_router(device_service.clone());
 
-    let url = format!("0.0.0.0:{}", config.port);
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
 
-    let listener = tokio::net::TcpListener::bind(url).await.unwrap();
-    println!("listening on {}", listener.local_addr().unwrap());
+    println!("listening on {address}");
     println!("using config file: {config_path}");
-    axum::serve(listener, router).await.unwrap();
+    tunnel::spawn_tunnel(&device_service, router.clone());
+    tls::serve(&device_service, router, address).await.unwrap();
 }
 
-async fn write_handler(data: Type, config: Arc<Config<ExampleDeviceConfig>>) -> StatusCode {
+async fn write_handler(data: Type, config: Arc<Config<ExampleDeviceConfig>>) -> WriteResponseDto {
     let number = match data {
         Type::Number(number) => number,
-        _ => return StatusCode::BAD_REQUEST,
+        _ => return WriteResponseDto::rejected("Expected a number"),
     };
     if number > config.additional_config.max as f64 {
         trigger_alert(
@@ -111,7 +115,7 @@ async fn write_handler(data: Type, config: Arc<Config<ExampleDeviceConfig>>) ->
         .await
         .unwrap();
     }
-    StatusCode::OK
+    WriteResponseDto::applied(Type::Number(number))
 }
 
 async fn status_handler(config: Arc<Config<ExampleDeviceConfig>>) -> DeviceStatusResponseDto {
@@ -135,5 +139,8 @@ async fn config_interceptor_handler(
             }
         },
         scripting_api: old_config.scripting_api.clone(),
+        calibration: old_config.calibration.clone(),
+        tunnel: old_config.tunnel.clone(),
+        state: old_config.state.clone(),
     }
 }
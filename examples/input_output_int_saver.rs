@@ -1,28 +1,39 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use axum::http::StatusCode;
 use greenhouse_core::{
     smart_device_dto::{
         Type,
-        config::{ConfigRequestDto, TypeOption},
+        action::{ActionDto, ActionRequestDto, ActionResponseDto},
+        config::{ConfigRequestDto, TypeOption, ValidationErrors},
         status::{DeviceStatusDto, DeviceStatusResponseDto},
+        write::WriteResponseDto,
     },
     smart_device_interface::{
         config::{Config, read_config_file_with_path, update_config_file_with_path},
         device_builder::DeviceBuilder,
         hybrid_device::init_hybrid_router,
+        tls, tunnel,
     },
 };
 use serde_derive::{Deserialize, Serialize};
 
-static mut SAVED_NUMBER: i32 = 20;
-
 #[derive(Serialize, Deserialize, Clone, Default)]
 struct ExampleDeviceConfig {
     pub min: i32,
     pub max: i32,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct SaverState {
+    saved_number: i32,
+}
+
+impl Default for SaverState {
+    fn default() -> Self {
+        SaverState { saved_number: 20 }
+    }
+}
+
 #[allow(clippy::needless_return)]
 #[tokio::main]
 async fn main() {
@@ -39,6 +50,9 @@ async fn main() {
                 datasource_id: "7a224a14-6e07-45a3-91da-b7584a5731c1".to_string(),
                 additional_config: ExampleDeviceConfig { min: 0, max: 100 },
                 scripting_api: None,
+                calibration: Default::default(),
+                tunnel: None,
+                state: Default::default(),
             };
 
             // Create config directory if it doesn't exist
@@ -67,35 +81,75 @@ async fn main() {
         TypeOption::Number,
         TypeOption::Number,
     )
-    .unwrap();
-    let router = init_hybrid_router(device_service);
+    .unwrap()
+    .with_state()
+    .unwrap()
+    .with_action(
+        ActionDto::new("reset")
+            .with_description("Set the saved number back to its default")
+            .with_optional_parameter("value", TypeOption::Number),
+        reset_action,
+    );
+    let router = init_hybrid_router(device_service.clone());
 
-    let url = format!("0.0.0.0:{}", config.port);
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
 
-    let listener = tokio::net::TcpListener::bind(url).await.unwrap();
-    println!("listening on {}", listener.local_addr().unwrap());
+    println!("listening on {address}");
     println!("using config file: {config_path}");
-    axum::serve(listener, router).await.unwrap();
+    tunnel::spawn_tunnel(&device_service, router.clone());
+    tls::serve(&device_service, router, address).await.unwrap();
 }
 
-async fn read_handler(_: Arc<Config<ExampleDeviceConfig>>) -> Type {
-    Type::Number(unsafe { SAVED_NUMBER } as f64)
+async fn read_handler(config: Arc<Config<ExampleDeviceConfig, SaverState>>) -> Type {
+    Type::Number(config.state.get().saved_number as f64)
 }
 
-async fn write_handler(data: Type, config: Arc<Config<ExampleDeviceConfig>>) -> StatusCode {
+async fn write_handler(
+    data: Type,
+    config: Arc<Config<ExampleDeviceConfig, SaverState>>,
+) -> WriteResponseDto {
     let number = match data {
         Type::Number(number) => number,
-        _ => return StatusCode::BAD_REQUEST,
+        _ => return WriteResponseDto::rejected("Expected a number"),
     };
-    unsafe { SAVED_NUMBER = number as i32 };
-    if config.additional_config.min > number as i32 || config.additional_config.max < number as i32
+    let clamped = (number as i32).clamp(config.additional_config.min, config.additional_config.max);
+    let previous = match config
+        .state
+        .update(|state| std::mem::replace(&mut state.saved_number, clamped))
+        .await
     {
-        return StatusCode::INTERNAL_SERVER_ERROR;
+        Ok(previous) => previous,
+        Err(_) => return WriteResponseDto::failed("Could not persist the saved number"),
+    };
+    if previous == clamped {
+        return WriteResponseDto::unchanged(Type::Number(clamped as f64));
     }
-    StatusCode::OK
+    WriteResponseDto::applied(Type::Number(clamped as f64)).with_state(Type::Number(clamped as f64))
 }
 
-async fn status_handler(config: Arc<Config<ExampleDeviceConfig>>) -> DeviceStatusResponseDto {
+async fn reset_action(
+    request: ActionRequestDto,
+    config: Arc<Config<ExampleDeviceConfig, SaverState>>,
+) -> ActionResponseDto {
+    let value = match request.parameters.get("value") {
+        Some(Type::Number(value)) => *value as i32,
+        _ => SaverState::default().saved_number,
+    };
+    match config
+        .state
+        .set(SaverState {
+            saved_number: value,
+        })
+        .await
+    {
+        Ok(()) => ActionResponseDto::completed().with_result(Type::Number(value as f64)),
+        Err(_) => ActionResponseDto::failed("Could not persist the saved number"),
+    }
+}
+
+async fn status_handler(
+    config: Arc<Config<ExampleDeviceConfig, SaverState>>,
+) -> DeviceStatusResponseDto {
     DeviceStatusResponseDto {
         status: DeviceStatusDto::Online,
         datasource_id: config.datasource_id.clone(),
@@ -104,9 +158,14 @@ async fn status_handler(config: Arc<Config<ExampleDeviceConfig>>) -> DeviceStatu
 
 async fn config_interceptor_handler(
     config: ConfigRequestDto<ExampleDeviceConfig>,
-    old_config: Arc<Config<ExampleDeviceConfig>>,
-) -> Config<ExampleDeviceConfig> {
-    Config {
+    old_config: Arc<Config<ExampleDeviceConfig, SaverState>>,
+) -> Result<Config<ExampleDeviceConfig, SaverState>, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    if config.additional_config.min > config.additional_config.max {
+        errors.add("min", "must not be greater than max");
+    }
+
+    errors.into_result(Config {
         port: old_config.port,
         datasource_id: old_config.datasource_id.clone(),
         additional_config: {
@@ -116,5 +175,8 @@ async fn config_interceptor_handler(
             }
         },
         scripting_api: old_config.scripting_api.clone(),
-    }
+        calibration: old_config.calibration.clone(),
+        tunnel: old_config.tunnel.clone(),
+        state: old_config.state.clone(),
+    })
 }
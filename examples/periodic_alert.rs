@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    net::SocketAddr,
     sync::{Arc, LazyLock},
 };
 
@@ -16,6 +17,7 @@ use greenhouse_core::{
         device_builder::DeviceBuilder,
         device_service::{AlertCreation, trigger_alert},
         hybrid_device::init_hybrid_router,
+        tls, tunnel,
     },
 };
 use rand::Rng;
@@ -77,6 +79,9 @@ async fn main() {
                     random_jitter: 5,
                 },
                 scripting_api: None,
+                calibration: Default::default(),
+                tunnel: None,
+                state: Default::default(),
             };
 
             // Create config directory if it doesn't exist
@@ -104,12 +109,11 @@ async fn main() {
         TypeOption::Object,
     )
     .unwrap();
-    let router = init_hybrid_router(device_service);
+    let router = init_hybrid_router(device_service.clone());
 
-    let url = format!("0.0.0.0:{}", config.port);
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
 
-    let listener = tokio::net::TcpListener::bind(url).await.unwrap();
-    println!("listening on {}", listener.local_addr().unwrap());
+    println!("listening on {address}");
     println!("using config file: {config_path}");
 
     // Run periodic alerts in a background task, but avoid moving non-Send types into the task.
@@ -119,11 +123,12 @@ async fn main() {
         }
     });
 
-    axum::serve(listener, router).await.unwrap();
+    tunnel::spawn_tunnel(&device_service, router.clone());
+    tls::serve(&device_service, router, address).await.unwrap();
 }
 
 async fn read_handler(_: Arc<Config<ExampleDeviceConfig>>) -> Type {
-    Type::Object(HashMap::from_iter(ALERTS_MUTEX.read().await.iter().map(
+    Type::Object(BTreeMap::from_iter(ALERTS_MUTEX.read().await.iter().map(
         |alert| {
             (
                 alert.identifier.to_string(),
@@ -154,6 +159,9 @@ async fn config_interceptor_handler(
             }
         },
         scripting_api: old_config.scripting_api.clone(),
+        calibration: old_config.calibration.clone(),
+        tunnel: old_config.tunnel.clone(),
+        state: old_config.state.clone(),
     }
 }
 
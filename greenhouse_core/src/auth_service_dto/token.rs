@@ -6,7 +6,10 @@ pub struct TokenRequestDto {
     pub token_type: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TokenResponseDto {
     pub role: String,
+    /// User the token belongs to.
+    #[serde(default)]
+    pub user_name: String,
 }
@@ -1,16 +1,26 @@
+use std::collections::HashMap;
+
 use greenhouse_macro::IntoJsonResponse;
 use serde::{Deserialize, Serialize};
 
+use crate::units::{Quantity, Unit};
+
 #[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
 pub struct UserPreferencesRequestDto {
     pub dashboard_preferences: serde_json::Value,
     pub alert_preferences: serde_json::Value,
+    /// Unit each quantity should be displayed in, e.g. Temperature -> °F
+    #[serde(default)]
+    pub unit_preferences: HashMap<Quantity, Unit>,
 }
 
 #[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
 pub struct UserPreferencesResponseDto {
     pub dashboard_preferences: serde_json::Value,
     pub alert_preferences: serde_json::Value,
+    /// Unit each quantity should be displayed in, e.g. Temperature -> °F
+    #[serde(default)]
+    pub unit_preferences: HashMap<Quantity, Unit>,
 }
 
 #[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
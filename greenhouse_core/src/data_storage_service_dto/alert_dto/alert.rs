@@ -1,7 +1,7 @@
 use greenhouse_macro::IntoJsonResponse;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Severity {
     Info,
     Warning,
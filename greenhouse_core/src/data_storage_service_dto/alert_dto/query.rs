@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
 use super::alert::Severity;
@@ -10,6 +10,14 @@ pub struct AlertQuery {
     pub identifier: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub datasource_id: Option<Uuid>,
+    /// Alerts of any of these datasources, e.g. the devices of a zone. Sent
+    /// comma separated.
+    #[serde(
+        default,
+        with = "comma_separated",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub datasource_ids: Option<Vec<Uuid>>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -17,3 +25,65 @@ pub struct IntervalQuery {
     pub start: Option<DateTime<Utc>>,
     pub end: Option<DateTime<Utc>>,
 }
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeleteAlertsQuery {
+    pub datasource_id: Uuid,
+}
+
+/// Lists in query strings, which can't repeat a key.
+mod comma_separated {
+    use super::*;
+
+    pub(super) fn serialize<S: Serializer>(
+        ids: &Option<Vec<Uuid>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match ids {
+            Some(ids) => serializer.serialize_str(
+                &ids.iter()
+                    .map(Uuid::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<Uuid>>, D::Error> {
+        let Some(ids) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        ids.split(',')
+            .filter(|id| !id.is_empty())
+            .map(|id| id.trim().parse().map_err(serde::de::Error::custom))
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_datasource_ids_comma_separated() {
+        let ids = vec![Uuid::new_v4(), Uuid::new_v4()];
+        let query = AlertQuery {
+            severity: None,
+            identifier: None,
+            created_at: None,
+            datasource_id: None,
+            datasource_ids: Some(ids.clone()),
+        };
+
+        let json = serde_json::to_value(&query).unwrap();
+        assert_eq!(json["datasource_ids"], format!("{},{}", ids[0], ids[1]));
+        let parsed: AlertQuery = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.datasource_ids, Some(ids));
+        let omitted: AlertQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(omitted.datasource_ids, None);
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+
+/// Whether a device answers its scrapes. `Unknown` until it was scraped.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Availability {
+    #[default]
+    Unknown,
+    Online,
+    Offline,
+}
+
+/// A change of the availability of a device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AvailabilityChangeDto {
+    pub availability: Availability,
+    pub changed_at: DateTime<Utc>,
+    /// Scrapes that had failed in a row when the availability changed.
+    pub consecutive_failures: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct AvailabilityHistoryDto {
+    pub changes: Vec<AvailabilityChangeDto>,
+}
+
+impl From<Vec<AvailabilityChangeDto>> for AvailabilityHistoryDto {
+    fn from(changes: Vec<AvailabilityChangeDto>) -> Self {
+        Self { changes }
+    }
+}
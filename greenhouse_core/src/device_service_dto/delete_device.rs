@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Deleting a device is soft by default: it disappears, but it and its
+/// readings and alerts are archived until it is restored or purged.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DeleteDeviceQuery {
+    /// Removes the device with its readings and alerts for good.
+    #[serde(default)]
+    pub purge: bool,
+}
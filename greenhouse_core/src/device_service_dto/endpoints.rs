@@ -1,4 +1,21 @@
 pub const CONFIG: &str = "config";
 pub const STATUS: &str = "status";
 pub const ACTIVATE: &str = "activate";
+pub const WRITE: &str = "write";
+pub const CALIBRATION: &str = "calibration";
+pub const LOGS: &str = "logs";
+pub const ACTIONS: &str = "actions";
 pub const DEVICE: &str = "/device";
+pub const TUNNEL: &str = "tunnel";
+pub const READINGS: &str = "readings";
+pub const AVAILABILITY: &str = "availability";
+pub const RESTORE: &str = "restore";
+pub const ZONE: &str = "/zone";
+pub const WRITES: &str = "writes";
+pub const SCHEDULE: &str = "/schedule";
+pub const ENABLE: &str = "enable";
+pub const DISABLE: &str = "disable";
+pub const NEXT_RUNS: &str = "next";
+pub const EXECUTIONS: &str = "executions";
+pub const RULE: &str = "/rule";
+pub const EVALUATIONS: &str = "evaluations";
@@ -1,6 +1,11 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use greenhouse_macro::IntoJsonResponse;
 use serde::{Deserialize, Serialize};
 
+use super::availability::Availability;
+use crate::smart_device_dto::version::Capability;
+use uuid::Uuid;
+
 #[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
 pub struct DeviceResponseDto {
     pub id: String,
@@ -9,6 +14,58 @@ pub struct DeviceResponseDto {
     pub description: String,
     pub canscript: bool,
     pub scraping: bool,
+    /// Seconds between two scrapes.
+    #[serde(default)]
+    pub scrape_interval: u32,
+    /// Seconds a scrape may take.
+    #[serde(default)]
+    pub scrape_timeout: u32,
+    /// Protocol version negotiated with the device, `None` until it answered.
+    #[serde(default)]
+    pub protocol_version: Option<u32>,
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+    #[serde(default)]
+    pub availability: Availability,
+    /// When the device last answered a scrape.
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Scrapes that failed in a row since then.
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// Set while the device is soft deleted.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub zone_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub asset: AssetDto,
+}
+
+/// Where and since when a device is installed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AssetDto {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_on: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DevicesQuery {
+    /// Lists the soft deleted devices instead of the active ones.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Only devices in this zone or the zones below it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<Uuid>,
+    /// Only devices with this tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
@@ -1,3 +1,5 @@
+pub mod availability;
+pub mod delete_device;
 pub mod endpoints;
 pub mod get_device;
 pub mod get_timeseries;
@@ -5,3 +7,9 @@ pub mod operations;
 pub mod post_device;
 pub mod put_device;
 pub mod query;
+pub mod readings;
+pub mod rule;
+pub mod schedule;
+pub mod tunnel;
+pub mod write;
+pub mod zone;
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::get_device::AssetDto;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PostDeviceDtoRequest {
@@ -7,4 +10,17 @@ pub struct PostDeviceDtoRequest {
     pub address: String,
     pub can_script: bool,
     pub scraping: bool,
+    /// Seconds between two scrapes, the defaults of 5 and 4 seconds if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_interval: Option<u32>,
+    /// Seconds a scrape may take, at most `scrape_interval`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_timeout: Option<u32>,
+    /// Zone the device is placed in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub asset: AssetDto,
 }
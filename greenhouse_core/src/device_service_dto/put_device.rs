@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use super::get_device::AssetDto;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PutDeviceDtoRequest {
@@ -7,4 +10,74 @@ pub struct PutDeviceDtoRequest {
     pub address: String,
     pub can_script: bool,
     pub scraping: bool,
+    /// Seconds between two scrapes, the current values if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_interval: Option<u32>,
+    /// Seconds a scrape may take, at most `scrape_interval`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_timeout: Option<u32>,
+    /// Zone the device is placed in, `null` takes it out of its zone. The
+    /// current zone if unset.
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub zone_id: Option<Option<Uuid>>,
+    /// The current tags if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// The current asset metadata if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<AssetDto>,
+}
+
+/// Tells a field set to `null` from an omitted one, which stays `None`.
+fn present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(fields: serde_json::Value) -> PutDeviceDtoRequest {
+        let mut request = serde_json::json!({
+            "name": "sensor",
+            "description": "",
+            "address": "http://sensor",
+            "can_script": false,
+            "scraping": true
+        });
+        request
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(request).unwrap()
+    }
+
+    #[test]
+    fn keeps_omitted_placement() {
+        let request = request(serde_json::json!({}));
+        assert_eq!(request.zone_id, None);
+        assert_eq!(request.tags, None);
+        assert_eq!(request.asset, None);
+    }
+
+    #[test]
+    fn tells_a_cleared_zone_from_an_omitted_one() {
+        let zone_id = Uuid::new_v4();
+        assert_eq!(
+            request(serde_json::json!({"zone_id": null})).zone_id,
+            Some(None)
+        );
+        assert_eq!(
+            request(serde_json::json!({"zone_id": zone_id})).zone_id,
+            Some(Some(zone_id))
+        );
+    }
 }
@@ -7,4 +7,6 @@ pub struct PromQuery {
     pub end: DateTime<Utc>,
     pub sub_property: Option<String>,
     pub step: Option<String>,
+    /// Converts measurements into this unit, e.g. "°F"
+    pub unit: Option<String>,
 }
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::value::Type;
+
+/// Readings pushed to device_service instead of being scraped, e.g. values a
+/// device buffered while it was offline.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IngestReadingsDto {
+    pub readings: Vec<ReadingDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReadingDto {
+    /// When the value was read, the time of ingestion if unset. Timestamped
+    /// values carry their own time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub data: Type,
+}
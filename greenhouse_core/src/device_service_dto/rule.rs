@@ -0,0 +1,196 @@
+use chrono::{DateTime, Utc};
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{data_storage_service_dto::alert_dto::alert::Severity, smart_device_dto::Type};
+
+/// When a rule fires, groups combine conditions on readings of devices.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// Holds if every condition holds.
+    All {
+        conditions: Vec<RuleCondition>,
+    },
+    /// Holds if any condition holds.
+    Any {
+        conditions: Vec<RuleCondition>,
+    },
+    Reading(ReadingCondition),
+}
+
+/// Compares the readings of a device channel against a value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReadingCondition {
+    pub device_id: Uuid,
+    /// Channel as stored by the device service, empty for scalar reads.
+    #[serde(default)]
+    pub channel: String,
+    pub operator: Operator,
+    /// Booleans are compared as `0` and `1`.
+    pub value: ConditionValue,
+    /// How long every reading has to match, only the latest one if `0`.
+    #[serde(default)]
+    pub for_seconds: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// Numbers are compared with numeric readings, texts with text and enum
+/// readings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ConditionValue {
+    Number(f64),
+    Text(String),
+}
+
+/// What a rule does when it fires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleAction {
+    Write {
+        device_id: Uuid,
+        data: Type,
+        /// Writes another value once the time is up.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hold: Option<HoldDto>,
+    },
+    /// Raised at the data storage service with the rule as datasource.
+    Alert {
+        severity: Severity,
+        identifier: String,
+        #[serde(default)]
+        note: Option<String>,
+    },
+    /// Posts a `RuleNotificationDto` to the url.
+    Notify { url: String, message: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HoldDto {
+    pub seconds: u32,
+    pub revert_to: Type,
+}
+
+/// Body of creating and updating a rule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuleRequestDto {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    pub actions: Vec<RuleAction>,
+    /// Time after firing in which the rule doesn't fire again.
+    #[serde(default = "default_cooldown_seconds")]
+    pub cooldown_seconds: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_cooldown_seconds() -> u32 {
+    300
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, IntoJsonResponse)]
+pub struct RuleResponseDto {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    pub actions: Vec<RuleAction>,
+    pub cooldown_seconds: u32,
+    pub last_fired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct RulesResponseDto {
+    pub rules: Vec<RuleResponseDto>,
+}
+
+impl From<Vec<RuleResponseDto>> for RulesResponseDto {
+    fn from(rules: Vec<RuleResponseDto>) -> Self {
+        Self { rules }
+    }
+}
+
+/// Sent to the url of notify actions.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuleNotificationDto {
+    pub rule_id: Uuid,
+    pub rule: String,
+    pub message: String,
+    pub fired_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationOutcome {
+    NotMet,
+    /// The condition held, but the rule fired too recently.
+    Cooldown,
+    Fired,
+    /// The rule fired and at least one action failed.
+    Failed,
+}
+
+/// State of a reading condition at the time of an evaluation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConditionResultDto {
+    pub device_id: Uuid,
+    pub channel: String,
+    pub satisfied: bool,
+    /// Latest reading of the channel.
+    pub value: Option<ConditionValue>,
+    /// Why the condition couldn't be checked, e.g. missing readings.
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActionResultDto {
+    pub kind: String,
+    pub succeeded: bool,
+    pub error: Option<String>,
+}
+
+/// A check of a rule, logged whether it fired or not.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuleEvaluationDto {
+    pub evaluated_at: DateTime<Utc>,
+    pub outcome: EvaluationOutcome,
+    pub conditions: Vec<ConditionResultDto>,
+    pub actions: Vec<ActionResultDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct RuleEvaluationsDto {
+    pub evaluations: Vec<RuleEvaluationDto>,
+}
+
+impl From<Vec<RuleEvaluationDto>> for RuleEvaluationsDto {
+    fn from(evaluations: Vec<RuleEvaluationDto>) -> Self {
+        Self { evaluations }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RuleEvaluationsQuery {
+    /// Number of evaluations returned, newest first, 100 if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Leaves out evaluations whose condition didn't hold.
+    #[serde(default)]
+    pub matched_only: bool,
+}
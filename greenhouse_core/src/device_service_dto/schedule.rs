@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::smart_device_dto::Type;
+
+/// When a schedule runs. Cron expressions are evaluated in the timezone of
+/// the device service, sunrise and sunset at its configured location.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ScheduleTrigger {
+    /// Standard five field expression like `0 6 * * *`, seconds may be
+    /// given as an additional first field.
+    Cron { expression: String },
+    Sunrise {
+        /// Minutes after sunrise, negative for before.
+        #[serde(default)]
+        offset_minutes: i32,
+    },
+    Sunset {
+        /// Minutes after sunset, negative for before.
+        #[serde(default)]
+        offset_minutes: i32,
+    },
+}
+
+/// What happens to runs that were due while the service was down.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Missed runs are logged and otherwise dropped.
+    #[default]
+    Skip,
+    /// The latest missed run is caught up once.
+    RunOnce,
+}
+
+/// Body of creating and updating a schedule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleRequestDto {
+    pub name: String,
+    pub device_id: Uuid,
+    pub trigger: ScheduleTrigger,
+    /// Value written to the device on every run.
+    pub data: Type,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, IntoJsonResponse)]
+pub struct ScheduleResponseDto {
+    pub id: Uuid,
+    pub name: String,
+    pub device_id: Uuid,
+    pub trigger: ScheduleTrigger,
+    pub data: Type,
+    pub enabled: bool,
+    pub missed_run_policy: MissedRunPolicy,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// `None` while disabled or if the trigger never fires again.
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct SchedulesResponseDto {
+    pub schedules: Vec<ScheduleResponseDto>,
+}
+
+impl From<Vec<ScheduleResponseDto>> for SchedulesResponseDto {
+    fn from(schedules: Vec<ScheduleResponseDto>) -> Self {
+        Self { schedules }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct NextRunsQuery {
+    /// Number of runs to preview, 5 if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct NextRunsDto {
+    pub runs: Vec<DateTime<Utc>>,
+}
+
+impl From<Vec<DateTime<Utc>>> for NextRunsDto {
+    fn from(runs: Vec<DateTime<Utc>>) -> Self {
+        Self { runs }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    Succeeded,
+    Failed,
+    /// The run was due while the service was down and skipped.
+    Missed,
+}
+
+/// A run of a schedule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleExecutionDto {
+    /// When the run was due.
+    pub scheduled_for: DateTime<Utc>,
+    pub executed_at: DateTime<Utc>,
+    pub status: ExecutionStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct ScheduleExecutionsDto {
+    pub executions: Vec<ScheduleExecutionDto>,
+}
+
+impl From<Vec<ScheduleExecutionDto>> for ScheduleExecutionsDto {
+    fn from(executions: Vec<ScheduleExecutionDto>) -> Self {
+        Self { executions }
+    }
+}
@@ -0,0 +1,9 @@
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+
+/// Token a device presents when it opens its reverse connection to
+/// device_service. Issuing a new one invalidates the previous token.
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct TunnelTokenResponseDto {
+    pub token: String,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::smart_device_dto::{Type, write::WriteStatus};
+
+/// Who a write is done for, recorded in the audit trail of the device.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WriteQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+}
+
+/// A write forwarded to a device.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WriteAuditDto {
+    pub actor: Option<String>,
+    pub written_at: DateTime<Utc>,
+    pub data: Type,
+    pub status: WriteStatus,
+    pub error: Option<String>,
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct WriteAuditsDto {
+    pub writes: Vec<WriteAuditDto>,
+}
+
+impl From<Vec<WriteAuditDto>> for WriteAuditsDto {
+    fn from(writes: Vec<WriteAuditDto>) -> Self {
+        Self { writes }
+    }
+}
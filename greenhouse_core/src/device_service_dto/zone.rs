@@ -0,0 +1,76 @@
+use greenhouse_macro::IntoJsonResponse;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::get_timeseries::GetTimeseriesDto;
+
+/// Level of a zone. Greenhouses hold compartments, compartments hold benches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneKind {
+    Greenhouse,
+    Compartment,
+    Bench,
+}
+
+impl ZoneKind {
+    /// Kind the parent of a zone of this kind has, `None` for top level zones.
+    pub fn parent(&self) -> Option<ZoneKind> {
+        match self {
+            ZoneKind::Greenhouse => None,
+            ZoneKind::Compartment => Some(ZoneKind::Greenhouse),
+            ZoneKind::Bench => Some(ZoneKind::Compartment),
+        }
+    }
+}
+
+/// Body of creating and updating a zone.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ZoneRequestDto {
+    pub name: String,
+    pub kind: ZoneKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct ZoneResponseDto {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: ZoneKind,
+    pub parent_id: Option<Uuid>,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, IntoJsonResponse)]
+pub struct ZonesResponseDto {
+    pub zones: Vec<ZoneResponseDto>,
+}
+
+impl From<Vec<ZoneResponseDto>> for ZonesResponseDto {
+    fn from(zones: Vec<ZoneResponseDto>) -> Self {
+        Self { zones }
+    }
+}
+
+/// Timeseries of one device of a zone.
+#[derive(Serialize, Deserialize)]
+pub struct DeviceTimeseriesDto {
+    pub device_id: Uuid,
+    pub name: String,
+    pub timeseries: GetTimeseriesDto,
+}
+
+/// Timeseries of every device in a zone and the zones below it.
+#[derive(Serialize, Deserialize, IntoJsonResponse)]
+pub struct ZoneTimeseriesDto {
+    pub devices: Vec<DeviceTimeseriesDto>,
+}
+
+impl From<Vec<DeviceTimeseriesDto>> for ZoneTimeseriesDto {
+    fn from(devices: Vec<DeviceTimeseriesDto>) -> Self {
+        Self { devices }
+    }
+}
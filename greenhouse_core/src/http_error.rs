@@ -55,6 +55,13 @@ pub struct ErrorResponseBody {
     context: Option<serde_json::Value>,
 }
 
+impl ErrorResponseBody {
+    /// Structured details of the error, e.g. field errors of a rejected update
+    pub fn context(&self) -> Option<&serde_json::Value> {
+        self.context.as_ref()
+    }
+}
+
 impl<E> axum::response::IntoResponse for HttpErrorResponse<E>
 where
     E: HttpErrorMapping + fmt::Display + fmt::Debug,
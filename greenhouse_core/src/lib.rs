@@ -10,6 +10,14 @@ pub mod scripting_service_dto;
 pub mod smart_device_dto;
 #[cfg(feature = "smart_device_interface")]
 pub mod smart_device_interface;
+#[cfg(any(
+    feature = "smart_device_dto",
+    feature = "device_service_dto",
+    feature = "auth_service_dto"
+))]
+pub use greenhouse_protocol::units;
+#[cfg(any(feature = "smart_device_dto", feature = "device_service_dto"))]
+pub use greenhouse_protocol::value;
 
 // HTTP error mapping system - enabled when axum is available
 #[cfg(feature = "error_handling")]
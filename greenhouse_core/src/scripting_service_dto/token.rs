@@ -5,3 +5,17 @@ use serde::{Deserialize, Serialize};
 pub struct TokenDto {
     pub token: String,
 }
+
+/// Body of generating a scripting key, binds the key to the datasource it is
+/// issued to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScriptingKeyRequestDto {
+    pub datasource_id: String,
+}
+
+/// Answer of checking a scripting key.
+#[derive(Serialize, Deserialize, Debug, Clone, IntoJsonResponse)]
+pub struct ScriptingKeyDto {
+    /// Datasource the key was issued to, `None` for keys issued without one.
+    pub datasource_id: Option<String>,
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::smart_device_dto::{
+    Type,
+    config::{TypeOption, ValidationErrors},
+};
+
+/// A named operation of a device beyond read and write, e.g. `tare` or
+/// `open_for`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActionDto {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<ActionParameterDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActionParameterDto {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub parameter_type: TypeOption,
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActionsResponseDto {
+    pub actions: Vec<ActionDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActionRequestDto {
+    #[serde(default)]
+    pub parameters: HashMap<String, Type>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ActionStatus {
+    Completed,
+    /// The device refused to run the action in its current state.
+    Rejected,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActionResponseDto {
+    pub status: ActionStatus,
+    /// Optional outcome of the action, e.g. the measured tare weight.
+    pub result: Option<Type>,
+    pub error: Option<String>,
+}
+
+impl ActionDto {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_parameter(self, name: impl Into<String>, parameter_type: TypeOption) -> Self {
+        self.push_parameter(name.into(), parameter_type, true)
+    }
+
+    pub fn with_optional_parameter(
+        self,
+        name: impl Into<String>,
+        parameter_type: TypeOption,
+    ) -> Self {
+        self.push_parameter(name.into(), parameter_type, false)
+    }
+
+    /// Checks the request against the declared parameters: required ones must
+    /// be present, every given one must be declared and of the declared type.
+    pub fn validate(&self, request: &ActionRequestDto) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for parameter in &self.parameters {
+            match request.parameters.get(&parameter.name) {
+                None if parameter.required => errors.add(&parameter.name, "is required"),
+                Some(value) if !parameter.parameter_type.accepts(value) => errors.add(
+                    &parameter.name,
+                    format!("expected {:?}", parameter.parameter_type),
+                ),
+                _ => {}
+            }
+        }
+        for name in request.parameters.keys() {
+            if !self.parameters.iter().any(|p| &p.name == name) {
+                errors.add(name, "is not a parameter of this action");
+            }
+        }
+        errors.into_result(())
+    }
+
+    fn push_parameter(mut self, name: String, parameter_type: TypeOption, required: bool) -> Self {
+        self.parameters.push(ActionParameterDto {
+            name,
+            parameter_type,
+            required,
+            description: None,
+        });
+        self
+    }
+}
+
+impl ActionResponseDto {
+    pub fn completed() -> Self {
+        Self::new(ActionStatus::Completed, None)
+    }
+
+    pub fn rejected(error: impl Into<String>) -> Self {
+        Self::new(ActionStatus::Rejected, Some(error.into()))
+    }
+
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self::new(ActionStatus::Failed, Some(error.into()))
+    }
+
+    pub fn with_result(mut self, result: Type) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    fn new(status: ActionStatus, error: Option<String>) -> Self {
+        Self {
+            status,
+            result: None,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_parameters() {
+        let action = ActionDto::new("open_for")
+            .with_parameter("seconds", TypeOption::Number)
+            .with_optional_parameter("reason", TypeOption::Text);
+
+        let valid = ActionRequestDto {
+            parameters: HashMap::from([(String::from("seconds"), Type::Number(30.0))]),
+        };
+        assert!(action.validate(&valid).is_ok());
+
+        let invalid = ActionRequestDto {
+            parameters: HashMap::from([
+                (String::from("reason"), Type::Number(1.0)),
+                (String::from("force"), Type::Boolean(true)),
+            ]),
+        };
+        let errors = action.validate(&invalid).unwrap_err();
+        let fields: Vec<_> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["seconds", "reason", "force"]);
+    }
+}
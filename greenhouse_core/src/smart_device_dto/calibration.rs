@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Channel name of a device whose read value is a single number or measurement.
+/// Nested values are addressed like the scraper names them, e.g. `air_temperature`.
+pub const ROOT_CHANNEL: &str = "value";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum CalibrationCurve {
+    /// `reference = raw + offset`
+    Offset(f64),
+    /// `reference = raw * gain`
+    Gain(f64),
+    /// Linear interpolation between measured points, extrapolated past the ends.
+    /// A single point acts as an offset.
+    Piecewise(Vec<CalibrationPoint>),
+    /// Coefficients in ascending order: `c0 + c1 * raw + c2 * raw^2 + ...`
+    Polynomial(Vec<f64>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CalibrationPoint {
+    pub raw: f64,
+    pub reference: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Calibration {
+    pub curve: CalibrationCurve,
+    pub calibrated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CalibrationsResponseDto {
+    pub channels: HashMap<String, Calibration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CalibrationRequestDto {
+    pub curve: CalibrationCurve,
+}
+
+/// Value read on a reference instrument while the device measured the same thing.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CalibrationReferenceRequestDto {
+    pub reference: f64,
+}
+
+impl CalibrationCurve {
+    pub fn apply(&self, raw: f64) -> f64 {
+        match self {
+            CalibrationCurve::Offset(offset) => raw + offset,
+            CalibrationCurve::Gain(gain) => raw * gain,
+            CalibrationCurve::Piecewise(points) => apply_piecewise(points, raw),
+            CalibrationCurve::Polynomial(coefficients) => coefficients
+                .iter()
+                .rev()
+                .fold(0.0, |acc, coefficient| acc * raw + coefficient),
+        }
+    }
+
+    /// Adds a reference point, turning any other curve into a piecewise one.
+    /// A point with the same raw value replaces the existing one.
+    pub fn with_point(self, point: CalibrationPoint) -> Self {
+        let mut points = match self {
+            CalibrationCurve::Piecewise(points) => points,
+            _ => Vec::new(),
+        };
+        points.retain(|p| p.raw != point.raw);
+        points.push(point);
+        points.sort_by(|a, b| a.raw.total_cmp(&b.raw));
+        CalibrationCurve::Piecewise(points)
+    }
+}
+
+fn apply_piecewise(points: &[CalibrationPoint], raw: f64) -> f64 {
+    match points {
+        [] => raw,
+        [point] => raw + point.reference - point.raw,
+        _ => {
+            // Segment containing raw, or the outermost one for extrapolation
+            let index = points
+                .windows(2)
+                .position(|segment| raw <= segment[1].raw)
+                .unwrap_or(points.len() - 2);
+            let (start, end) = (points[index], points[index + 1]);
+            if end.raw == start.raw {
+                return start.reference;
+            }
+            let slope = (end.reference - start.reference) / (end.raw - start.raw);
+            start.reference + (raw - start.raw) * slope
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(raw: f64, reference: f64) -> CalibrationPoint {
+        CalibrationPoint { raw, reference }
+    }
+
+    #[test]
+    fn applies_offset_and_gain() {
+        assert_eq!(CalibrationCurve::Offset(-1.5).apply(20.0), 18.5);
+        assert_eq!(CalibrationCurve::Gain(2.0).apply(20.0), 40.0);
+    }
+
+    #[test]
+    fn applies_polynomial() {
+        // 1 + 2x + 3x^2
+        let curve = CalibrationCurve::Polynomial(vec![1.0, 2.0, 3.0]);
+        assert_eq!(curve.apply(2.0), 17.0);
+        assert_eq!(CalibrationCurve::Polynomial(vec![]).apply(2.0), 0.0);
+    }
+
+    #[test]
+    fn interpolates_and_extrapolates_piecewise() {
+        let curve = CalibrationCurve::Piecewise(vec![
+            point(0.0, 0.0),
+            point(10.0, 20.0),
+            point(20.0, 30.0),
+        ]);
+        assert_eq!(curve.apply(5.0), 10.0);
+        assert_eq!(curve.apply(15.0), 25.0);
+        assert_eq!(curve.apply(-5.0), -10.0);
+        assert_eq!(curve.apply(30.0), 40.0);
+    }
+
+    #[test]
+    fn single_point_acts_as_offset() {
+        let curve = CalibrationCurve::Gain(3.0).with_point(point(20.0, 21.0));
+        assert_eq!(curve, CalibrationCurve::Piecewise(vec![point(20.0, 21.0)]));
+        assert_eq!(curve.apply(10.0), 11.0);
+    }
+
+    #[test]
+    fn replaces_point_with_same_raw_value() {
+        let curve = CalibrationCurve::Piecewise(vec![point(10.0, 11.0), point(0.0, 1.0)])
+            .with_point(point(10.0, 12.0));
+        assert_eq!(
+            curve,
+            CalibrationCurve::Piecewise(vec![point(0.0, 1.0), point(10.0, 12.0)])
+        );
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Certificate signing request of the device, its private key never leaves it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CertificateSigningRequestDto {
+    pub csr_pem: String,
+}
+
+/// Certificate issued to a device by device_service, which acts as the CA.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CertificateDto {
+    pub certificate_pem: String,
+    pub ca_certificate_pem: String,
+}
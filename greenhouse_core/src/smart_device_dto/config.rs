@@ -1,15 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+pub use crate::value::TypeOption;
+
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ConfigResponseDto<T> {
     pub mode: Mode,
     pub input_type: Option<TypeOption>,
     pub output_type: Option<TypeOption>,
     pub scripting_api: Option<ScriptingApi>,
+    /// Device specific settings, free-form for every device.
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
     pub additional_config: T,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum Mode {
     Input,
     Output,
@@ -18,22 +24,88 @@ pub enum Mode {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ConfigRequestDto<T> {
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
     pub additional_config: T,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ScriptingApi {
     pub url: String,
     pub token: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum TypeOption {
-    Number,
-    Boolean,
-    Object,
-    Measurement,
-    Stream,
-    Unknown,
+/// Field errors of a rejected config update, answered with 422.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// Path of the field inside `additional_config`, e.g. `min`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns `value` if no error was added.
+    pub fn into_result<V>(self, value: V) -> Result<V, ValidationErrors> {
+        if self.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect::<Vec<_>>();
+        write!(f, "{}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_field_errors() {
+        assert_eq!(ValidationErrors::new().into_result(1), Ok(1));
+
+        let mut errors = ValidationErrors::new();
+        errors.add("min", "must not be greater than max");
+        errors.add("max", "must be at most 1000");
+        let errors = errors.into_result(1).unwrap_err();
+        assert_eq!(
+            errors.to_string(),
+            "min: must not be greater than max, max: must be at most 1000"
+        );
+    }
 }
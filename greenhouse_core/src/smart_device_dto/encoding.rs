@@ -0,0 +1,137 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Encode(String),
+    Decode(String),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+/// Wire encoding of the device protocol bodies. JSON is always understood,
+/// devices advertising `Capability::Cbor` also speak CBOR. CBOR support is
+/// behind the `cbor` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Encoding {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Json => JSON_CONTENT_TYPE,
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => CBOR_CONTENT_TYPE,
+        }
+    }
+
+    /// Encoding of a body with the given `Content-Type`, `None` if unsupported.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if media_type.eq_ignore_ascii_case(JSON_CONTENT_TYPE) {
+            return Some(Encoding::Json);
+        }
+        #[cfg(feature = "cbor")]
+        if media_type.eq_ignore_ascii_case(CBOR_CONTENT_TYPE) {
+            return Some(Encoding::Cbor);
+        }
+        None
+    }
+
+    /// Picks the encoding the client prefers according to its `Accept` header,
+    /// honouring quality values. Falls back to JSON.
+    pub fn from_accept(accept: &str) -> Self {
+        let mut best = (Encoding::Json, 0.0);
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';');
+            let Some(encoding) = Encoding::from_content_type(params.next().unwrap_or_default())
+            else {
+                continue;
+            };
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > best.1 {
+                best = (encoding, quality);
+            }
+        }
+        best.0
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Json => serde_json::to_vec(value).map_err(|e| Error::Encode(e.to_string())),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)
+                    .map_err(|e| Error::Encode(e.to_string()))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Encoding::Json => {
+                serde_json::from_slice(bytes).map_err(|e| Error::Decode(e.to_string()))
+            }
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => {
+                ciborium::from_reader(bytes).map_err(|e| Error::Decode(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cbor"))]
+mod tests {
+    use super::*;
+    use crate::smart_device_dto::{Measurement, Type, read::ReadResponseDto};
+
+    #[test]
+    fn prefers_accepted_encoding() {
+        assert_eq!(Encoding::from_accept("application/cbor"), Encoding::Cbor);
+        assert_eq!(
+            Encoding::from_accept("application/cbor;q=0.5, application/json"),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept("text/html, application/cbor;q=0.9, */*;q=0.1"),
+            Encoding::Cbor
+        );
+        assert_eq!(Encoding::from_accept("*/*"), Encoding::Json);
+    }
+
+    #[test]
+    fn round_trips_read_response_as_cbor() {
+        let response = ReadResponseDto {
+            data: Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("°C"),
+            }),
+        };
+        let bytes = Encoding::Cbor.encode(&response).unwrap();
+        assert!(bytes.len() < Encoding::Json.encode(&response).unwrap().len());
+
+        let decoded: ReadResponseDto = Encoding::Cbor.decode(&bytes).unwrap();
+        assert!(matches!(decoded.data, Type::Measurement(m) if m.value == 21.5));
+    }
+}
@@ -3,3 +3,9 @@ pub const WRITE: &str = "/write";
 pub const STATUS: &str = "/status";
 pub const CONFIG: &str = "/config";
 pub const ACTIVATE: &str = "/activate";
+pub const CALIBRATION: &str = "/calibration";
+pub const OPENAPI: &str = "/openapi.json";
+pub const LOGS: &str = "/logs";
+pub const ACTIONS: &str = "/actions";
+pub const VERSION: &str = "/version";
+pub const CERTIFICATE: &str = "/certificate";
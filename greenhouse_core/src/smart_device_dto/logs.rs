@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LogRecordDto {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    pub target: String,
+    pub message: String,
+}
+
+/// Filters for `/logs`, both optional.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogsQuery {
+    /// Minimum level, e.g. `warn` returns warnings and errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<LogLevel>,
+    /// Only records logged after this point in time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LogsResponseDto {
+    pub records: Vec<LogRecordDto>,
+}
+
+/// Records a device forwards to the scripting api.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogBatchDto {
+    pub datasource_id: String,
+    pub records: Vec<LogRecordDto>,
+}
+
+impl LogsQuery {
+    pub fn matches(&self, record: &LogRecordDto) -> bool {
+        self.level.is_none_or(|level| record.level >= level)
+            && self.since.is_none_or(|since| record.timestamp > since)
+    }
+}
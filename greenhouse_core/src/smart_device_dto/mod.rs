@@ -1,26 +1,16 @@
-use std::collections::HashMap;
-
-use serde::{Deserialize, Serialize};
-
+pub mod action;
 pub mod activation;
+pub mod calibration;
+pub mod certificate;
 pub mod config;
+pub mod encoding;
 pub mod endpoints;
-pub mod read;
-pub mod status;
-pub mod write;
-
-#[derive(Serialize, Deserialize)]
-pub enum Type {
-    Number(f64),
-    Boolean(bool),
-    Object(HashMap<String, Type>),
-    Measurement(Measurement),
-    Stream,
-    None,
-}
+pub mod logs;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod tunnel;
+pub mod version;
 
-#[derive(Serialize, Deserialize)]
-pub struct Measurement {
-    pub value: f64,
-    pub unit: String,
-}
+pub use crate::value::{Measurement, Timestamped, Type};
+/// Shared with firmware through the no_std `greenhouse_protocol` crate.
+pub use greenhouse_protocol::{read, status, write};
@@ -0,0 +1,495 @@
+use utoipa::{
+    OpenApi, ToSchema,
+    openapi::{
+        Content, ContentBuilder, Ref, RefOr, Required, ResponseBuilder,
+        path::{
+            HttpMethod, OperationBuilder, ParameterBuilder, ParameterIn, PathItem, PathsBuilder,
+        },
+        request_body::RequestBodyBuilder,
+        schema::{KnownFormat, ObjectBuilder, SchemaFormat, Type as SchemaType},
+    },
+};
+
+use super::{
+    action::{ActionRequestDto, ActionResponseDto, ActionsResponseDto},
+    activation::ActivateRequestDto,
+    calibration::{
+        Calibration, CalibrationReferenceRequestDto, CalibrationRequestDto, CalibrationsResponseDto,
+    },
+    certificate::{CertificateDto, CertificateSigningRequestDto},
+    config::{ConfigRequestDto, ConfigResponseDto, ValidationErrors},
+    endpoints::{
+        ACTIONS, ACTIVATE, CALIBRATION, CERTIFICATE, CONFIG, LOGS, OPENAPI, READ, STATUS, VERSION,
+        WRITE,
+    },
+    logs::{LogLevel, LogsResponseDto},
+    read::ReadResponseDto,
+    status::DeviceStatusResponseDto,
+    version::VersionResponseDto,
+    write::{WriteRequestDto, WriteResponseDto},
+};
+
+/// The device specific part of the config is free-form, so the spec describes
+/// it with this placeholder.
+type AdditionalConfig = serde_json::Value;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Greenhouse smart device protocol",
+        description = "HTTP interface every smart device exposes to the device service. \
+            Devices only implement the endpoints matching their mode: input devices \
+            accept writes, output devices are read. Devices advertising the `cbor` \
+            capability also accept and return `application/cbor` bodies, devices advertising `tls` \
+            switch to HTTPS once device_service installed a certificate."
+    ),
+    components(schemas(
+        ReadResponseDto,
+        WriteRequestDto,
+        WriteResponseDto,
+        ConfigResponseDto<AdditionalConfig>,
+        ConfigRequestDto<AdditionalConfig>,
+        ValidationErrors,
+        DeviceStatusResponseDto,
+        ActivateRequestDto,
+        CalibrationsResponseDto,
+        CalibrationRequestDto,
+        CalibrationReferenceRequestDto,
+        Calibration,
+        LogsResponseDto,
+        ActionsResponseDto,
+        ActionRequestDto,
+        ActionResponseDto,
+        VersionResponseDto,
+        CertificateSigningRequestDto,
+        CertificateDto,
+    ))
+)]
+struct SmartDeviceApi;
+
+/// Builds the OpenAPI document of the smart device protocol from the DTOs.
+/// A published copy lives in `greenhouse_core/openapi/smart_device.json`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut api = SmartDeviceApi::openapi();
+    api.info.version = String::from(env!("CARGO_PKG_VERSION"));
+    api.paths = PathsBuilder::new()
+        .path(
+            READ,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("read"))
+                    .summary(Some("Read the current value of an output device"))
+                    .response("200", json_response::<ReadResponseDto>("Current value"))
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Value has an unsupported unit"),
+                    ),
+            ),
+        )
+        .path(
+            WRITE,
+            PathItem::new(
+                HttpMethod::Post,
+                OperationBuilder::new()
+                    .operation_id(Some("write"))
+                    .summary(Some("Write a value to an input device"))
+                    .request_body(Some(json_request::<WriteRequestDto>()))
+                    .response(
+                        "200",
+                        json_response::<WriteResponseDto>("Value applied or unchanged"),
+                    )
+                    .response("400", json_response::<WriteResponseDto>("Value rejected"))
+                    .response(
+                        "500",
+                        json_response::<WriteResponseDto>("Device failed to apply the value"),
+                    ),
+            ),
+        )
+        .path(
+            CONFIG,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("get_config"))
+                    .summary(Some("Get the device configuration"))
+                    .response(
+                        "200",
+                        json_response::<ConfigResponseDto<AdditionalConfig>>(
+                            "Device configuration",
+                        ),
+                    ),
+            ),
+        )
+        .path(
+            CONFIG,
+            PathItem::new(
+                HttpMethod::Post,
+                OperationBuilder::new()
+                    .operation_id(Some("update_config"))
+                    .summary(Some("Update the device specific configuration"))
+                    .request_body(Some(json_request::<ConfigRequestDto<AdditionalConfig>>()))
+                    .response("200", ResponseBuilder::new().description("Config updated"))
+                    .response(
+                        "422",
+                        json_response::<ValidationErrors>("Config rejected by the device"),
+                    )
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Config could not be stored"),
+                    ),
+            ),
+        )
+        .path(
+            STATUS,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("status"))
+                    .summary(Some("Get the device status"))
+                    .response(
+                        "200",
+                        json_response::<DeviceStatusResponseDto>("Device status"),
+                    ),
+            ),
+        )
+        .path(
+            ACTIVATE,
+            PathItem::new(
+                HttpMethod::Post,
+                OperationBuilder::new()
+                    .operation_id(Some("activate"))
+                    .summary(Some(
+                        "Hand the scripting api address and token to the device",
+                    ))
+                    .request_body(Some(json_request::<ActivateRequestDto>()))
+                    .response(
+                        "200",
+                        ResponseBuilder::new().description("Device activated"),
+                    )
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Activation could not be stored"),
+                    ),
+            ),
+        )
+        .path(
+            ACTIVATE,
+            PathItem::new(
+                HttpMethod::Delete,
+                OperationBuilder::new()
+                    .operation_id(Some("deactivate"))
+                    .summary(Some("Make the device forget the scripting api"))
+                    .response(
+                        "200",
+                        ResponseBuilder::new().description("Device deactivated"),
+                    )
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Deactivation could not be stored"),
+                    ),
+            ),
+        )
+        .path(
+            CALIBRATION,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("get_calibration"))
+                    .summary(Some("List the calibration of every channel"))
+                    .response(
+                        "200",
+                        json_response::<CalibrationsResponseDto>("Calibrations per channel"),
+                    ),
+            ),
+        )
+        .path(
+            format!("{CALIBRATION}/{{channel}}"),
+            PathItem::new(
+                HttpMethod::Put,
+                OperationBuilder::new()
+                    .operation_id(Some("update_calibration"))
+                    .summary(Some("Set the calibration curve of a channel"))
+                    .parameter(channel_parameter())
+                    .request_body(Some(json_request::<CalibrationRequestDto>()))
+                    .response("200", json_response::<Calibration>("Stored calibration")),
+            ),
+        )
+        .path(
+            format!("{CALIBRATION}/{{channel}}"),
+            PathItem::new(
+                HttpMethod::Delete,
+                OperationBuilder::new()
+                    .operation_id(Some("delete_calibration"))
+                    .summary(Some("Remove the calibration of a channel"))
+                    .parameter(channel_parameter())
+                    .response(
+                        "200",
+                        ResponseBuilder::new().description("Calibration removed"),
+                    )
+                    .response(
+                        "404",
+                        ResponseBuilder::new().description("Channel is not calibrated"),
+                    ),
+            ),
+        )
+        .path(
+            format!("{CALIBRATION}/{{channel}}/reference"),
+            PathItem::new(
+                HttpMethod::Post,
+                OperationBuilder::new()
+                    .operation_id(Some("add_calibration_reference"))
+                    .summary(Some(
+                        "Add the current raw reading and a reference value as calibration point",
+                    ))
+                    .parameter(channel_parameter())
+                    .request_body(Some(json_request::<CalibrationReferenceRequestDto>()))
+                    .response("200", json_response::<Calibration>("Updated calibration"))
+                    .response(
+                        "400",
+                        ResponseBuilder::new().description("Device has no read value"),
+                    )
+                    .response(
+                        "404",
+                        ResponseBuilder::new().description("Channel missing in the read value"),
+                    ),
+            ),
+        )
+        .path(
+            LOGS,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("logs"))
+                    .summary(Some("Recent log records buffered by the device"))
+                    .parameter(
+                        ParameterBuilder::new()
+                            .name("level")
+                            .parameter_in(ParameterIn::Query)
+                            .required(Required::False)
+                            .description(Some("Minimum level of the returned records"))
+                            .schema(Some(RefOr::Ref(Ref::from_schema_name(LogLevel::name())))),
+                    )
+                    .parameter(
+                        ParameterBuilder::new()
+                            .name("since")
+                            .parameter_in(ParameterIn::Query)
+                            .required(Required::False)
+                            .description(Some("Only records logged after this RFC 3339 timestamp"))
+                            .schema(Some(
+                                ObjectBuilder::new()
+                                    .schema_type(SchemaType::String)
+                                    .format(Some(SchemaFormat::KnownFormat(KnownFormat::DateTime))),
+                            )),
+                    )
+                    .response(
+                        "200",
+                        json_response::<LogsResponseDto>("Buffered log records"),
+                    ),
+            ),
+        )
+        .path(
+            ACTIONS,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("list_actions"))
+                    .summary(Some("List the named actions of the device"))
+                    .response(
+                        "200",
+                        json_response::<ActionsResponseDto>("Actions and their parameters"),
+                    ),
+            ),
+        )
+        .path(
+            format!("{ACTIONS}/{{name}}"),
+            PathItem::new(
+                HttpMethod::Post,
+                OperationBuilder::new()
+                    .operation_id(Some("invoke_action"))
+                    .summary(Some("Invoke a named action"))
+                    .parameter(
+                        ParameterBuilder::new()
+                            .name("name")
+                            .parameter_in(ParameterIn::Path)
+                            .required(Required::True)
+                            .description(Some("Action name as listed at `/actions`"))
+                            .schema(Some(ObjectBuilder::new().schema_type(SchemaType::String))),
+                    )
+                    .request_body(Some(json_request::<ActionRequestDto>()))
+                    .response(
+                        "200",
+                        json_response::<ActionResponseDto>("Action completed"),
+                    )
+                    .response("400", json_response::<ActionResponseDto>("Action rejected"))
+                    .response("404", ResponseBuilder::new().description("Unknown action"))
+                    .response(
+                        "422",
+                        json_response::<ValidationErrors>("Parameters do not match the action"),
+                    )
+                    .response("500", json_response::<ActionResponseDto>("Action failed")),
+            ),
+        )
+        .path(
+            VERSION,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("version"))
+                    .summary(Some(
+                        "Protocol version and capabilities, missing on protocol version 1",
+                    ))
+                    .response(
+                        "200",
+                        json_response::<VersionResponseDto>("Protocol version of the device"),
+                    ),
+            ),
+        )
+        .path(
+            CERTIFICATE,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("certificate_request"))
+                    .summary(Some("Certificate signing request for the key of the device"))
+                    .response(
+                        "200",
+                        json_response::<CertificateSigningRequestDto>("Signing request"),
+                    )
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Key could not be created"),
+                    ),
+            ),
+        )
+        .path(
+            CERTIFICATE,
+            PathItem::new(
+                HttpMethod::Put,
+                OperationBuilder::new()
+                    .operation_id(Some("install_certificate"))
+                    .summary(Some(
+                        "Install a certificate issued by device_service, the device restarts on HTTPS",
+                    ))
+                    .request_body(Some(json_request::<CertificateDto>()))
+                    .response(
+                        "200",
+                        ResponseBuilder::new().description("Certificate installed"),
+                    )
+                    .response(
+                        "400",
+                        ResponseBuilder::new()
+                            .description("Certificate does not match the key of the device"),
+                    )
+                    .response(
+                        "403",
+                        ResponseBuilder::new().description(
+                            "Certificate is not issued by the CA pinned at the first install",
+                        ),
+                    )
+                    .response(
+                        "500",
+                        ResponseBuilder::new().description("Certificate could not be stored"),
+                    ),
+            ),
+        )
+        .path(
+            OPENAPI,
+            PathItem::new(
+                HttpMethod::Get,
+                OperationBuilder::new()
+                    .operation_id(Some("openapi"))
+                    .summary(Some("This document"))
+                    .response(
+                        "200",
+                        ResponseBuilder::new()
+                            .description("OpenAPI document")
+                            .content(
+                                "application/json",
+                                ContentBuilder::new()
+                                    .schema(Some(
+                                        ObjectBuilder::new().schema_type(SchemaType::Object),
+                                    ))
+                                    .build(),
+                            ),
+                    ),
+            ),
+        )
+        .build();
+    api
+}
+
+fn json_content<S: ToSchema>() -> Content {
+    ContentBuilder::new()
+        .schema(Some(RefOr::Ref(Ref::from_schema_name(S::name()))))
+        .build()
+}
+
+fn json_request<S: ToSchema>() -> utoipa::openapi::request_body::RequestBody {
+    RequestBodyBuilder::new()
+        .content("application/json", json_content::<S>())
+        .required(Some(Required::True))
+        .build()
+}
+
+fn json_response<S: ToSchema>(description: &str) -> ResponseBuilder {
+    ResponseBuilder::new()
+        .description(description)
+        .content("application/json", json_content::<S>())
+}
+
+fn channel_parameter() -> ParameterBuilder {
+    ParameterBuilder::new()
+        .name("channel")
+        .parameter_in(ParameterIn::Path)
+        .required(Required::True)
+        .description(Some("Channel name, e.g. `value` or `air_temperature`"))
+        .schema(Some(ObjectBuilder::new().schema_type(SchemaType::String)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi/smart_device.json");
+
+    /// Run with `UPDATE_OPENAPI=1` to rewrite the published spec after changing a DTO.
+    #[test]
+    fn published_spec_matches_dtos() {
+        let generated = serde_json::to_value(openapi()).unwrap();
+        if std::env::var("UPDATE_OPENAPI").is_ok() {
+            let mut json = serde_json::to_string_pretty(&generated).unwrap();
+            json.push('\n');
+            std::fs::write(SPEC_PATH, json).unwrap();
+        }
+
+        let published: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(SPEC_PATH).unwrap()).unwrap();
+        assert_eq!(
+            published, generated,
+            "openapi/smart_device.json is outdated, rerun the test with UPDATE_OPENAPI=1"
+        );
+    }
+
+    #[test]
+    fn describes_externally_tagged_type() {
+        let spec = serde_json::to_value(openapi()).unwrap();
+        let variants = spec["components"]["schemas"]["Type"]["oneOf"]
+            .as_array()
+            .unwrap();
+
+        let tagged = |tag: &str| {
+            variants
+                .iter()
+                .any(|variant| variant["properties"].get(tag).is_some())
+        };
+        assert!(tagged("Number"));
+        assert!(tagged("Measurement"));
+        assert!(tagged("Timestamped"));
+        assert!(
+            variants
+                .iter()
+                .any(|variant| variant["enum"] == serde_json::json!(["Stream"]))
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the tunnel token when a device connects to device_service.
+pub const TUNNEL_TOKEN_HEADER: &str = "authorization";
+
+/// A request device_service sends through the reverse connection of a device
+/// that can't be reached inbound. Frames are CBOR encoded binary messages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TunnelRequestDto {
+    /// Correlates the response, unique per connection.
+    pub id: u64,
+    pub method: String,
+    /// Path and query relative to the device root, e.g. `/read`.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+    #[serde(default)]
+    pub body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TunnelResponseDto {
+    pub id: u64,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub body: Vec<u8>,
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the smart device protocol spoken by this crate. Bump it when a
+/// DTO changes in a way older peers can't read.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Devices built before the handshake existed don't serve `/version` and are
+/// treated as this version.
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Read,
+    Write,
+    /// Writes are answered with a `WriteResponseDto` and honour idempotency keys.
+    WriteAcknowledgement,
+    /// Config updates may be rejected with field errors.
+    ConfigValidation,
+    Calibration,
+    Logs,
+    Actions,
+    OpenApi,
+    /// Bodies may be sent and requested as CBOR through `Content-Type` and `Accept`.
+    Cbor,
+    /// Serves HTTPS once device_service installed a certificate at `/certificate`.
+    Tls,
+    /// Forgets its scripting api on `DELETE /activate`.
+    Deactivation,
+    /// A capability introduced by a newer protocol version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VersionResponseDto {
+    pub protocol_version: u32,
+    /// Version of the greenhouse_core crate the device was built with.
+    pub sdk_version: String,
+    pub capabilities: Vec<Capability>,
+}
+
+impl VersionResponseDto {
+    /// What a device without `/version` is assumed to speak.
+    pub fn legacy() -> Self {
+        Self {
+            protocol_version: LEGACY_PROTOCOL_VERSION,
+            sdk_version: String::from("unknown"),
+            capabilities: vec![Capability::Read, Capability::Write],
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_device_dto::{Type, read::ReadResponseDto};
+
+    #[test]
+    fn ignores_unknown_capabilities() {
+        let version: VersionResponseDto = serde_json::from_str(
+            r#"{"protocol_version": 3, "sdk_version": "0.3.0", "capabilities": ["read", "streaming"]}"#,
+        )
+        .unwrap();
+
+        assert!(version.supports(Capability::Read));
+        assert_eq!(version.capabilities[1], Capability::Unknown);
+    }
+
+    #[test]
+    fn reads_legacy_read_response() {
+        // Version 1 values are a subset of today's, so they decode unchanged
+        let response: ReadResponseDto = serde_json::from_str(
+            r#"{"data": {"Object": {"temp": {"Measurement": {"value": 21.5, "unit": "°C"}}}}}"#,
+        )
+        .unwrap();
+
+        let Type::Object(values) = response.data else {
+            panic!("expected an object");
+        };
+        assert!(matches!(values["temp"], Type::Measurement(_)));
+    }
+}
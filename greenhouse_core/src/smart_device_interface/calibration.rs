@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::smart_device_dto::{
+    Type,
+    calibration::{Calibration, ROOT_CHANNEL},
+};
+
+/// Applies the calibration of every channel to the matching numbers and
+/// measurements in `value`. Channels are named like the scraper names metrics.
+pub(crate) fn apply_calibration(value: &mut Type, calibration: &HashMap<String, Calibration>) {
+    if !calibration.is_empty() {
+        apply_calibration_for_channel(value, None, calibration);
+    }
+}
+
+/// Returns the uncalibrated number of a channel, if the channel exists.
+pub(crate) fn channel_value(value: &Type, channel: &str) -> Option<f64> {
+    find_channel_value(value, None, channel)
+}
+
+fn apply_calibration_for_channel(
+    value: &mut Type,
+    channel: Option<&str>,
+    calibration: &HashMap<String, Calibration>,
+) {
+    match value {
+        Type::Number(number) => {
+            if let Some(calibration) = calibration.get(channel.unwrap_or(ROOT_CHANNEL)) {
+                *number = calibration.curve.apply(*number);
+            }
+        }
+        Type::Measurement(measurement) => {
+            if let Some(calibration) = calibration.get(channel.unwrap_or(ROOT_CHANNEL)) {
+                measurement.value = calibration.curve.apply(measurement.value);
+            }
+        }
+        Type::Timestamped(timestamped) => {
+            apply_calibration_for_channel(&mut timestamped.value, channel, calibration)
+        }
+        Type::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                let next = child_channel(channel, &index.to_string());
+                apply_calibration_for_channel(value, Some(&next), calibration);
+            }
+        }
+        Type::Object(values) => {
+            for (key, value) in values.iter_mut() {
+                let next = child_channel(channel, key);
+                apply_calibration_for_channel(value, Some(&next), calibration);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn find_channel_value(value: &Type, channel: Option<&str>, wanted: &str) -> Option<f64> {
+    match value {
+        Type::Number(number) if channel.unwrap_or(ROOT_CHANNEL) == wanted => Some(*number),
+        Type::Measurement(measurement) if channel.unwrap_or(ROOT_CHANNEL) == wanted => {
+            Some(measurement.value)
+        }
+        Type::Timestamped(timestamped) => find_channel_value(&timestamped.value, channel, wanted),
+        Type::Array(values) => values.iter().enumerate().find_map(|(index, value)| {
+            find_channel_value(
+                value,
+                Some(&child_channel(channel, &index.to_string())),
+                wanted,
+            )
+        }),
+        Type::Object(values) => values.iter().find_map(|(key, value)| {
+            find_channel_value(value, Some(&child_channel(channel, key)), wanted)
+        }),
+        _ => None,
+    }
+}
+
+fn child_channel(channel: Option<&str>, key: &str) -> String {
+    match channel {
+        Some(channel) => format!("{channel}_{key}"),
+        None => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_device_dto::{Measurement, calibration::CalibrationCurve};
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+
+    fn calibration(channel: &str, curve: CalibrationCurve) -> HashMap<String, Calibration> {
+        HashMap::from([(
+            channel.to_string(),
+            Calibration {
+                curve,
+                calibrated_at: Utc::now(),
+            },
+        )])
+    }
+
+    #[test]
+    fn calibrates_root_value() {
+        let mut value = Type::Number(10.0);
+        apply_calibration(
+            &mut value,
+            &calibration(ROOT_CHANNEL, CalibrationCurve::Offset(0.5)),
+        );
+        assert_eq!(value, Type::Number(10.5));
+    }
+
+    #[test]
+    fn calibrates_nested_channel_only() {
+        let mut value = Type::Object(BTreeMap::from([
+            (
+                String::from("air"),
+                Type::Object(BTreeMap::from([(
+                    String::from("temperature"),
+                    Type::Measurement(Measurement {
+                        value: 20.0,
+                        unit: String::from("°C"),
+                    }),
+                )])),
+            ),
+            (String::from("soil"), Type::Number(20.0)),
+        ]));
+        apply_calibration(
+            &mut value,
+            &calibration("air_temperature", CalibrationCurve::Gain(2.0)),
+        );
+
+        assert_eq!(channel_value(&value, "air_temperature"), Some(40.0));
+        assert_eq!(channel_value(&value, "soil"), Some(20.0));
+        assert_eq!(channel_value(&value, "missing"), None);
+    }
+}
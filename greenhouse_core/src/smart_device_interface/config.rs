@@ -1,12 +1,14 @@
-use crate::smart_device_dto::config::TypeOption;
+use std::{collections::HashMap, sync::Arc};
 
-use super::{Error, Result};
+use crate::smart_device_dto::{calibration::Calibration, config::TypeOption};
+
+use super::{Error, Result, state::StateHandle};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 // Default config file path for backward compatibility
 pub(crate) const DEFAULT_CONFIG_FILE_NAME: &str = "./config/config.json";
 
-pub fn update_config_file<T>(config: &Config<T>) -> Result<()>
+pub fn update_config_file<T, S>(config: &Config<T, S>) -> Result<()>
 where
     T: Serialize + Clone + Default,
 {
@@ -20,7 +22,7 @@ where
     read_config_file_with_path(DEFAULT_CONFIG_FILE_NAME)
 }
 
-pub fn update_config_file_with_path<T>(config: &Config<T>, config_path: &str) -> Result<()>
+pub fn update_config_file_with_path<T, S>(config: &Config<T, S>, config_path: &str) -> Result<()>
 where
     T: Serialize + Clone + Default,
 {
@@ -45,19 +47,10 @@ pub enum Mode {
     Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
-pub enum TypeOptionDto {
-    Number,
-    Boolean,
-    Object,
-    Measurement,
-    Stream,
-    #[default]
-    Unknown,
-}
-
+/// Config handed to the handlers. `S` is the state type attached with
+/// `DeviceBuilder::with_state`, `()` for devices without state.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct Config<T>
+pub struct Config<T, S = ()>
 where
     T: Clone + Default,
 {
@@ -65,6 +58,33 @@ where
     pub datasource_id: String,
     pub additional_config: T,
     pub scripting_api: Option<ScriptingApi>,
+    /// Calibration per read channel, managed through the calibration endpoints
+    #[serde(default)]
+    pub calibration: HashMap<String, Calibration>,
+    /// Reverse connection for devices device_service can't reach inbound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tunnel: Option<TunnelConfig>,
+    /// State store of the device, not part of the file
+    #[serde(skip)]
+    pub state: Arc<StateHandle<S>>,
+}
+
+impl<T> Config<T>
+where
+    T: Clone + Default,
+{
+    /// The same config handing `state` to the handlers.
+    pub(crate) fn with_state<S>(self, state: Arc<StateHandle<S>>) -> Config<T, S> {
+        Config {
+            port: self.port,
+            datasource_id: self.datasource_id,
+            additional_config: self.additional_config,
+            scripting_api: self.scripting_api,
+            calibration: self.calibration,
+            tunnel: self.tunnel,
+            state,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -72,3 +92,12 @@ pub struct ScriptingApi {
     pub url: String,
     pub token: String,
 }
+
+/// Reverse connection to device_service, opened by `tunnel::spawn_tunnel`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TunnelConfig {
+    /// WebSocket address of the tunnel endpoint, e.g. `wss://greenhouse.example/tunnel`.
+    pub url: String,
+    /// Token issued by device_service for this device.
+    pub token: String,
+}
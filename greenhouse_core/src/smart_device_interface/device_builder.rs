@@ -3,11 +3,19 @@ use super::config::{
     Config, DEFAULT_CONFIG_FILE_NAME, read_config_file_with_path, update_config_file_with_path,
 };
 use crate::smart_device_dto::Type;
+use crate::smart_device_dto::action::{ActionDto, ActionRequestDto, ActionResponseDto};
 use crate::smart_device_dto::config::TypeOption;
-use crate::smart_device_dto::{config::ConfigRequestDto, status::DeviceStatusResponseDto};
+use crate::smart_device_dto::write::WriteResponseDto;
+use crate::smart_device_dto::{
+    config::{ConfigRequestDto, ValidationErrors},
+    status::DeviceStatusResponseDto,
+};
 use crate::smart_device_interface::config::Mode;
-use axum::http::StatusCode;
+use crate::smart_device_interface::state::{DeviceState, StateHandle, state_path_for_config};
+use crate::smart_device_interface::tls::TlsIdentity;
+use crate::smart_device_interface::write_cache::WriteCache;
 use futures::future::BoxFuture;
+use futures::lock::Mutex;
 use serde::{Serialize, de::DeserializeOwned};
 use std::future::Future;
 use std::sync::{Arc, RwLock};
@@ -16,99 +24,176 @@ use std::sync::{Arc, RwLock};
 pub trait ReadFuture: Future<Output = Type> + Send + 'static {}
 impl<T> ReadFuture for T where T: Future<Output = Type> + Send + 'static {}
 
-pub trait WriteFuture: Future<Output = StatusCode> + Send + 'static {}
-impl<T> WriteFuture for T where T: Future<Output = StatusCode> + Send + 'static {}
+pub trait WriteFuture: Future<Output = WriteResponseDto> + Send + 'static {}
+impl<T> WriteFuture for T where T: Future<Output = WriteResponseDto> + Send + 'static {}
 
 pub trait StatusFuture: Future<Output = DeviceStatusResponseDto> + Send + 'static {}
 impl<T> StatusFuture for T where T: Future<Output = DeviceStatusResponseDto> + Send + 'static {}
 
-pub trait ConfigFuture<C>: Future<Output = Config<C>> + Send + 'static
+pub trait ActionFuture: Future<Output = ActionResponseDto> + Send + 'static {}
+impl<T> ActionFuture for T where T: Future<Output = ActionResponseDto> + Send + 'static {}
+
+pub trait ConfigFuture<C, S = ()>:
+    Future<Output: ConfigInterceptorResult<C, S>> + Send + 'static
 where
     C: Clone + Default,
 {
 }
-impl<T, C> ConfigFuture<C> for T
+impl<T, C, S> ConfigFuture<C, S> for T
 where
-    T: Future<Output = Config<C>> + Send + 'static,
+    T: Future<Output: ConfigInterceptorResult<C, S>> + Send + 'static,
     C: Clone + Default,
 {
 }
 
+/// Output of a config interceptor. Interceptors that validate the update return
+/// `Result<Config<T, S>, ValidationErrors>` to reject it with field errors.
+pub trait ConfigInterceptorResult<T, S = ()>
+where
+    T: Clone + Default,
+{
+    fn into_config_result(self) -> std::result::Result<Config<T, S>, ValidationErrors>;
+}
+
+impl<T, S> ConfigInterceptorResult<T, S> for Config<T, S>
+where
+    T: Clone + Default,
+{
+    fn into_config_result(self) -> std::result::Result<Config<T, S>, ValidationErrors> {
+        Ok(self)
+    }
+}
+
+impl<T, S> ConfigInterceptorResult<T, S> for std::result::Result<Config<T, S>, ValidationErrors>
+where
+    T: Clone + Default,
+{
+    fn into_config_result(self) -> std::result::Result<Config<T, S>, ValidationErrors> {
+        self
+    }
+}
+
 // Trait aliases for repetitive function signature patterns
-pub trait ReadHandlerFn<T, RF>: Fn(Arc<Config<T>>) -> RF + Send + Sync + 'static
+pub trait ReadHandlerFn<T, RF, S = ()>:
+    Fn(Arc<Config<T, S>>) -> RF + Send + Sync + 'static
+where
+    T: Clone + Default,
+{
+}
+impl<F, T, RF, S> ReadHandlerFn<T, RF, S> for F
+where
+    F: Fn(Arc<Config<T, S>>) -> RF + Send + Sync + 'static,
+    T: Clone + Default,
+{
+}
+
+pub trait WriteHandlerFn<T, WF, S = ()>:
+    Fn(Type, Arc<Config<T, S>>) -> WF + Send + Sync + 'static
 where
     T: Clone + Default,
 {
 }
-impl<F, T, RF> ReadHandlerFn<T, RF> for F
+impl<F, T, WF, S> WriteHandlerFn<T, WF, S> for F
 where
-    F: Fn(Arc<Config<T>>) -> RF + Send + Sync + 'static,
+    F: Fn(Type, Arc<Config<T, S>>) -> WF + Send + Sync + 'static,
     T: Clone + Default,
 {
 }
 
-pub trait WriteHandlerFn<T, WF>: Fn(Type, Arc<Config<T>>) -> WF + Send + Sync + 'static
+pub trait StatusHandlerFn<T, SF, S = ()>:
+    Fn(Arc<Config<T, S>>) -> SF + Send + Sync + 'static
 where
     T: Clone + Default,
 {
 }
-impl<F, T, WF> WriteHandlerFn<T, WF> for F
+impl<F, T, SF, S> StatusHandlerFn<T, SF, S> for F
 where
-    F: Fn(Type, Arc<Config<T>>) -> WF + Send + Sync + 'static,
+    F: Fn(Arc<Config<T, S>>) -> SF + Send + Sync + 'static,
     T: Clone + Default,
 {
 }
 
-pub trait StatusHandlerFn<T, SF>: Fn(Arc<Config<T>>) -> SF + Send + Sync + 'static
+pub trait ConfigHandlerFn<T, CIF, S = ()>:
+    Fn(ConfigRequestDto<T>, Arc<Config<T, S>>) -> CIF + Send + Sync + 'static
 where
     T: Clone + Default,
 {
 }
-impl<F, T, SF> StatusHandlerFn<T, SF> for F
+impl<F, T, CIF, S> ConfigHandlerFn<T, CIF, S> for F
 where
-    F: Fn(Arc<Config<T>>) -> SF + Send + Sync + 'static,
+    F: Fn(ConfigRequestDto<T>, Arc<Config<T, S>>) -> CIF + Send + Sync + 'static,
     T: Clone + Default,
 {
 }
 
-pub trait ConfigHandlerFn<T, CIF>:
-    Fn(ConfigRequestDto<T>, Arc<Config<T>>) -> CIF + Send + Sync + 'static
+pub trait ActionHandlerFn<T, AF, S = ()>:
+    Fn(ActionRequestDto, Arc<Config<T, S>>) -> AF + Send + Sync + 'static
 where
     T: Clone + Default,
 {
 }
-impl<F, T, CIF> ConfigHandlerFn<T, CIF> for F
+impl<F, T, AF, S> ActionHandlerFn<T, AF, S> for F
 where
-    F: Fn(ConfigRequestDto<T>, Arc<Config<T>>) -> CIF + Send + Sync + 'static,
+    F: Fn(ActionRequestDto, Arc<Config<T, S>>) -> AF + Send + Sync + 'static,
     T: Clone + Default,
 {
 }
 
-type ReadHandler<T> = Option<Arc<dyn Fn(Arc<Config<T>>) -> BoxFuture<'static, Type> + Send + Sync>>;
-type WriteHandler<T> =
-    Option<Arc<dyn Fn(Type, Arc<Config<T>>) -> BoxFuture<'static, StatusCode> + Send + Sync>>;
-type StatusHandler<T> =
-    Arc<dyn Fn(Arc<Config<T>>) -> BoxFuture<'static, DeviceStatusResponseDto> + Send + Sync>;
-type ConfigInterceptorHandler<T> =
-    Arc<dyn Fn(ConfigRequestDto<T>, Arc<Config<T>>) -> BoxFuture<'static, Config<T>> + Send + Sync>;
+type ReadHandler<T, S> =
+    Option<Arc<dyn Fn(Arc<Config<T, S>>) -> BoxFuture<'static, Type> + Send + Sync>>;
+type WriteHandler<T, S> = Option<
+    Arc<dyn Fn(Type, Arc<Config<T, S>>) -> BoxFuture<'static, WriteResponseDto> + Send + Sync>,
+>;
+type StatusHandler<T, S> =
+    Arc<dyn Fn(Arc<Config<T, S>>) -> BoxFuture<'static, DeviceStatusResponseDto> + Send + Sync>;
+type ConfigInterceptorHandler<T, S> = Arc<
+    dyn Fn(
+            ConfigRequestDto<T>,
+            Arc<Config<T, S>>,
+        ) -> BoxFuture<'static, std::result::Result<Config<T, S>, ValidationErrors>>
+        + Send
+        + Sync,
+>;
+type ActionHandler<T, S> = Arc<
+    dyn Fn(ActionRequestDto, Arc<Config<T, S>>) -> BoxFuture<'static, ActionResponseDto>
+        + Send
+        + Sync,
+>;
 
+/// An action registered through `DeviceBuilder::with_action`.
 #[derive(Clone)]
-pub struct DeviceBuilder<T>
+pub(crate) struct DeviceAction<T, S>
 where
     T: Clone + Default,
 {
-    pub read_handler: ReadHandler<T>,
-    pub write_handler: WriteHandler<T>,
-    pub status_handler: StatusHandler<T>,
-    pub config_interceptor_handler: ConfigInterceptorHandler<T>,
-    pub config: Arc<RwLock<Arc<Config<T>>>>,
+    pub(crate) action: ActionDto,
+    pub(crate) handler: ActionHandler<T, S>,
+}
+
+#[derive(Clone)]
+pub struct DeviceBuilder<T, S = ()>
+where
+    T: Clone + Default,
+{
+    pub read_handler: ReadHandler<T, S>,
+    pub write_handler: WriteHandler<T, S>,
+    pub status_handler: StatusHandler<T, S>,
+    pub config_interceptor_handler: ConfigInterceptorHandler<T, S>,
+    pub config: Arc<RwLock<Arc<Config<T, S>>>>,
     pub config_path: String,
     pub mode: Mode,
+    pub(crate) write_cache: Arc<Mutex<WriteCache>>,
+    /// Serializes the read-modify-write cycles of the config file.
+    pub(crate) config_lock: Arc<Mutex<()>>,
+    pub(crate) state: Arc<StateHandle<S>>,
+    pub(crate) actions: Arc<Vec<DeviceAction<T, S>>>,
+    pub(crate) tls: Arc<TlsIdentity>,
 }
 
-impl<T> DeviceBuilder<T>
+impl<T, S> DeviceBuilder<T, S>
 where
     T: Clone + Default + DeserializeOwned + Serialize,
+    S: DeviceState,
 {
     pub fn new_hybrid_device<RH, RF, WH, WF, SH, SF, CIH, CIF>(
         read_handler: RH,
@@ -119,14 +204,14 @@ where
         output_type: TypeOption,
     ) -> Result<Self>
     where
-        RH: ReadHandlerFn<T, RF>,
+        RH: ReadHandlerFn<T, RF, S>,
         RF: ReadFuture,
-        WH: WriteHandlerFn<T, WF>,
+        WH: WriteHandlerFn<T, WF, S>,
         WF: WriteFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         Self::new_hybrid_device_with_config_path(
             read_handler,
@@ -149,39 +234,45 @@ where
         output_type: TypeOption,
     ) -> Result<Self>
     where
-        RH: ReadHandlerFn<T, RF>,
+        RH: ReadHandlerFn<T, RF, S>,
         RF: ReadFuture,
-        WH: WriteHandlerFn<T, WF>,
+        WH: WriteHandlerFn<T, WF, S>,
         WF: WriteFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         let config = read_config_file_with_path(config_path)?;
 
+        let state = Arc::default();
         Ok(DeviceBuilder {
-            read_handler: Some(Arc::new(move |cfg: Arc<Config<T>>| {
+            read_handler: Some(Arc::new(move |cfg: Arc<Config<T, S>>| {
                 let fut = read_handler(cfg);
                 Box::pin(fut)
             })),
-            write_handler: Some(Arc::new(move |data: Type, cfg: Arc<Config<T>>| {
+            write_handler: Some(Arc::new(move |data: Type, cfg: Arc<Config<T, S>>| {
                 let fut = write_handler(data, cfg);
                 Box::pin(fut)
             })),
-            status_handler: Arc::new(move |cfg: Arc<Config<T>>| {
+            status_handler: Arc::new(move |cfg: Arc<Config<T, S>>| {
                 let fut = status_handler(cfg);
                 Box::pin(fut)
             }),
             config_interceptor_handler: Arc::new(
-                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T>>| {
+                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T, S>>| {
                     let fut = config_interceptor_handler(req, cfg);
-                    Box::pin(fut)
+                    Box::pin(async move { fut.await.into_config_result() })
                 },
             ),
-            config: Arc::new(RwLock::new(Arc::new(config))),
+            config: Arc::new(RwLock::new(Arc::new(config.with_state(Arc::clone(&state))))),
             config_path: config_path.to_string(),
             mode: Mode::InputOutput(input_type, output_type),
+            write_cache: Arc::new(Mutex::new(WriteCache::default())),
+            config_lock: Arc::default(),
+            state,
+            actions: Arc::default(),
+            tls: Arc::new(TlsIdentity::for_config(config_path)),
         })
     }
 
@@ -192,12 +283,12 @@ where
         output_type: TypeOption,
     ) -> Result<Self>
     where
-        RH: ReadHandlerFn<T, RF>,
+        RH: ReadHandlerFn<T, RF, S>,
         RF: ReadFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         Self::new_output_device_with_config_path(
             read_handler,
@@ -216,12 +307,12 @@ where
         output_type: TypeOption,
     ) -> Result<Self>
     where
-        RH: ReadHandlerFn<T, RF>,
+        RH: ReadHandlerFn<T, RF, S>,
         RF: ReadFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         let config = match read_config_file_with_path(config_path) {
             Ok(config) => config,
@@ -231,25 +322,31 @@ where
                 default_config
             }
         };
+        let state = Arc::default();
         Ok(DeviceBuilder {
-            read_handler: Some(Arc::new(move |cfg: Arc<Config<T>>| {
+            read_handler: Some(Arc::new(move |cfg: Arc<Config<T, S>>| {
                 let fut = read_handler(cfg);
                 Box::pin(fut)
             })),
             write_handler: None,
-            status_handler: Arc::new(move |cfg: Arc<Config<T>>| {
+            status_handler: Arc::new(move |cfg: Arc<Config<T, S>>| {
                 let fut = status_handler(cfg);
                 Box::pin(fut)
             }),
             config_interceptor_handler: Arc::new(
-                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T>>| {
+                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T, S>>| {
                     let fut = config_interceptor_handler(req, cfg);
-                    Box::pin(fut)
+                    Box::pin(async move { fut.await.into_config_result() })
                 },
             ),
-            config: Arc::new(RwLock::new(Arc::new(config))),
+            config: Arc::new(RwLock::new(Arc::new(config.with_state(Arc::clone(&state))))),
             config_path: config_path.to_string(),
             mode: Mode::Output(output_type),
+            write_cache: Arc::new(Mutex::new(WriteCache::default())),
+            config_lock: Arc::default(),
+            state,
+            actions: Arc::default(),
+            tls: Arc::new(TlsIdentity::for_config(config_path)),
         })
     }
 
@@ -260,12 +357,12 @@ where
         input_type: TypeOption,
     ) -> Result<Self>
     where
-        WH: WriteHandlerFn<T, WF>,
+        WH: WriteHandlerFn<T, WF, S>,
         WF: WriteFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         Self::new_input_device_with_config_path(
             write_handler,
@@ -284,12 +381,12 @@ where
         input_type: TypeOption,
     ) -> Result<Self>
     where
-        WH: WriteHandlerFn<T, WF>,
+        WH: WriteHandlerFn<T, WF, S>,
         WF: WriteFuture,
-        SH: StatusHandlerFn<T, SF>,
+        SH: StatusHandlerFn<T, SF, S>,
         SF: StatusFuture,
-        CIH: ConfigHandlerFn<T, CIF>,
-        CIF: ConfigFuture<T>,
+        CIH: ConfigHandlerFn<T, CIF, S>,
+        CIF: ConfigFuture<T, S>,
     {
         let config = match read_config_file_with_path(config_path) {
             Ok(config) => config,
@@ -299,25 +396,85 @@ where
                 default_config
             }
         };
+        let state = Arc::default();
         Ok(DeviceBuilder {
             read_handler: None,
-            write_handler: Some(Arc::new(move |data: Type, cfg: Arc<Config<T>>| {
+            write_handler: Some(Arc::new(move |data: Type, cfg: Arc<Config<T, S>>| {
                 let fut = write_handler(data, cfg);
                 Box::pin(fut)
             })),
-            status_handler: Arc::new(move |cfg: Arc<Config<T>>| {
+            status_handler: Arc::new(move |cfg: Arc<Config<T, S>>| {
                 let fut = status_handler(cfg);
                 Box::pin(fut)
             }),
             config_interceptor_handler: Arc::new(
-                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T>>| {
+                move |req: ConfigRequestDto<T>, cfg: Arc<Config<T, S>>| {
                     let fut = config_interceptor_handler(req, cfg);
-                    Box::pin(fut)
+                    Box::pin(async move { fut.await.into_config_result() })
                 },
             ),
-            config: Arc::new(RwLock::new(Arc::new(config))),
+            config: Arc::new(RwLock::new(Arc::new(config.with_state(Arc::clone(&state))))),
             config_path: config_path.to_string(),
             mode: Mode::Input(input_type),
+            write_cache: Arc::new(Mutex::new(WriteCache::default())),
+            config_lock: Arc::default(),
+            state,
+            actions: Arc::default(),
+            tls: Arc::new(TlsIdentity::for_config(config_path)),
         })
     }
+
+    /// Persists the state handed to the handlers as `config.state` next to the
+    /// config file and restores the last stored state.
+    pub fn with_state(mut self) -> Result<Self> {
+        let handle = StateHandle::<S>::open(state_path_for_config(&self.config_path))?;
+        self.state = Arc::new(handle);
+        let config = self
+            .config
+            .read()
+            .map(|c| c.as_ref().clone())
+            .unwrap_or_default();
+        self.set_config(config);
+        Ok(self)
+    }
+}
+
+impl<T, S> DeviceBuilder<T, S>
+where
+    T: Clone + Default,
+    S: Clone,
+{
+    /// Registers a named action listed at `/actions` and invoked at
+    /// `/actions/{name}`. Parameters are checked against the declared ones
+    /// before the handler runs; registering a name twice replaces the action.
+    pub fn with_action<AH, AF>(mut self, action: ActionDto, handler: AH) -> Self
+    where
+        AH: ActionHandlerFn<T, AF, S>,
+        AF: ActionFuture,
+    {
+        let actions = Arc::make_mut(&mut self.actions);
+        actions.retain(|registered| registered.action.name != action.name);
+        actions.push(DeviceAction {
+            action,
+            handler: Arc::new(move |req: ActionRequestDto, cfg: Arc<Config<T, S>>| {
+                let fut = handler(req, cfg);
+                Box::pin(fut)
+            }),
+        });
+        self
+    }
+
+    pub(crate) fn action(&self, name: &str) -> Option<&DeviceAction<T, S>> {
+        self.actions
+            .iter()
+            .find(|registered| registered.action.name == name)
+    }
+
+    /// Replaces the config handed to the handlers, keeping the attached state.
+    pub(crate) fn set_config(&self, mut config: Config<T, S>) {
+        config.state = self.state.clone();
+        if let Ok(mut guard) = self.config.write() {
+            *guard = Arc::new(config);
+        }
+    }
 }
@@ -1,8 +1,13 @@
 use crate::data_storage_service_dto::alert_dto::alert::Severity;
 use crate::data_storage_service_dto::alert_dto::post_create_alert::CreateAlertDto;
+use crate::smart_device_dto::logs::{LogBatchDto, LogRecordDto};
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::config::Config;
+use super::device_builder::DeviceBuilder;
+use super::logs::logs_from;
+use super::state::DeviceState;
 use super::{Error, Result};
 
 pub struct AlertCreation {
@@ -12,9 +17,10 @@ pub struct AlertCreation {
     pub note: Option<String>,
 }
 
-pub async fn trigger_alert<T>(config: Arc<Config<T>>, alert: AlertCreation) -> Result<()>
+pub async fn trigger_alert<T, S>(config: Arc<Config<T, S>>, alert: AlertCreation) -> Result<()>
 where
     T: Clone + Default,
+    S: DeviceState,
 {
     if let Some(scripting_api) = &config.scripting_api {
         let client = reqwest::Client::new();
@@ -43,3 +49,62 @@ where
     }
     Err(Error::ScriptingApiNotConfigured)
 }
+
+pub async fn ship_logs<T, S>(config: Arc<Config<T, S>>, records: Vec<LogRecordDto>) -> Result<()>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    if let Some(scripting_api) = &config.scripting_api {
+        let batch = LogBatchDto {
+            datasource_id: config.datasource_id.clone(),
+            records,
+        };
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/logs", scripting_api.url))
+            .json(&batch)
+            .header("Access-Control-Allow-Credentials", "true")
+            .header("Cookie", format!("auth-token={}", scripting_api.token))
+            .send()
+            .await
+            .map_err(Error::Request)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Request(response.error_for_status().unwrap_err()));
+        }
+        return Ok(());
+    }
+    Err(Error::ScriptingApiNotConfigured)
+}
+
+/// Forwards the records buffered by `LogLayer` to the scripting api every
+/// `interval`. Undelivered records are retried while they are still buffered.
+/// Meant to be spawned next to the device router.
+pub async fn forward_logs<T, S>(device_service: DeviceBuilder<T, S>, interval: Duration)
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let mut sequence = 0;
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let (records, next_sequence) = logs_from(sequence);
+        if records.is_empty() {
+            continue;
+        }
+        let config = device_service
+            .config
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        if config.scripting_api.is_none() {
+            sequence = next_sequence;
+            continue;
+        }
+        if ship_logs(config, records).await.is_ok() {
+            sequence = next_sequence;
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::smart_device_dto::encoding::Encoding;
+
+tokio::task_local! {
+    /// Encoding the client of the current request prefers for responses.
+    static ACCEPT: Encoding;
+}
+
+/// Lets every handler speak CBOR: remembers the encoding the client's `Accept`
+/// header prefers, so `Encoded` responses are serialized in it directly.
+pub(crate) async fn negotiate_encoding(request: Request, next: Next) -> Response {
+    let accept = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(Encoding::from_accept)
+        .unwrap_or_default();
+
+    ACCEPT.scope(accept, next.run(request)).await
+}
+
+/// Like `Json`, but decodes request bodies in the encoding of their
+/// `Content-Type` and encodes responses in the one the client accepts,
+/// without going through JSON first.
+#[derive(Debug)]
+pub(crate) struct Encoded<T>(pub T);
+
+impl<T, S> FromRequest<S> for Encoded<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if encoding_of(request.headers()) != Some(Encoding::Cbor) {
+            let Json(value) = Json::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Encoded(value));
+        }
+
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Encoding::Cbor
+            .decode(&bytes)
+            .map(Encoded)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())
+    }
+}
+
+impl<T> IntoResponse for Encoded<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let encoding = ACCEPT.try_with(|accept| *accept).unwrap_or_default();
+        if encoding == Encoding::Json {
+            return Json(self.0).into_response();
+        }
+
+        match encoding.encode(&self.0) {
+            Ok(bytes) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(encoding.content_type()),
+                )],
+                bytes,
+            )
+                .into_response(),
+            Err(e) => {
+                tracing::error!("Could not encode {:?} response: {e}", encoding);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+fn encoding_of(headers: &HeaderMap) -> Option<Encoding> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(Encoding::from_content_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_device_dto::{Type, read::ReadResponseDto, write::WriteRequestDto};
+    use axum::{Router, body::Body, body::to_bytes, middleware, routing::post};
+    use tower::ServiceExt;
+
+    async fn echo(Encoded(request): Encoded<WriteRequestDto>) -> Encoded<ReadResponseDto> {
+        Encoded(ReadResponseDto { data: request.data })
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/write", post(echo))
+            .layer(middleware::from_fn(negotiate_encoding))
+    }
+
+    #[tokio::test]
+    async fn speaks_cbor_when_asked() {
+        let body = Encoding::Cbor
+            .encode(&WriteRequestDto {
+                data: Type::Number(4.0),
+                idempotency_key: None,
+            })
+            .unwrap();
+
+        let response = router()
+            .oneshot(
+                Request::post("/write")
+                    .header(header::CONTENT_TYPE, "application/cbor")
+                    .header(header::ACCEPT, "application/cbor")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(encoding_of(response.headers()), Some(Encoding::Cbor));
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response: ReadResponseDto = Encoding::Cbor.decode(&bytes).unwrap();
+        assert!(matches!(response.data, Type::Number(n) if n == 4.0));
+    }
+
+    #[tokio::test]
+    async fn answers_json_by_default() {
+        let response = router()
+            .oneshot(
+                Request::post("/write")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"data": {"Number": 4.0}}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(encoding_of(response.headers()), Some(Encoding::Json));
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response: ReadResponseDto = serde_json::from_slice(&bytes).unwrap();
+        assert!(matches!(response.data, Type::Number(n) if n == 4.0));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_cbor() {
+        let response = router()
+            .oneshot(
+                Request::post("/write")
+                    .header(header::CONTENT_TYPE, "application/cbor")
+                    .body(Body::from(vec![0xff]))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,3 +1,7 @@
+use axum::http::StatusCode;
+
+use crate::http_error::HttpErrorMapping;
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
@@ -5,6 +9,11 @@ pub enum Error {
     IllFormattedConfig,
     MissingConfig,
     ScriptingApiNotConfigured,
+    IllFormattedState,
+    StateStorage,
+    Certificate,
+    UntrustedCertificate,
+    CertificateStorage,
     Request(reqwest::Error),
 }
 
@@ -16,4 +25,31 @@ impl core::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+impl HttpErrorMapping for Error {
+    fn to_status_code(&self) -> StatusCode {
+        match self {
+            Error::Certificate => StatusCode::BAD_REQUEST,
+            Error::UntrustedCertificate => StatusCode::FORBIDDEN,
+            Error::Request(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn to_error_message(&self) -> String {
+        match self {
+            Error::IllFormattedConfig => String::from("Config could not be serialized"),
+            Error::MissingConfig => String::from("Config could not be stored"),
+            Error::ScriptingApiNotConfigured => String::from("Scripting api not configured"),
+            Error::IllFormattedState => String::from("State could not be serialized"),
+            Error::StateStorage => String::from("State could not be stored"),
+            Error::Certificate => String::from("Invalid certificate"),
+            Error::UntrustedCertificate => {
+                String::from("Certificate is not issued by the pinned CA")
+            }
+            Error::CertificateStorage => String::from("Certificate could not be stored"),
+            Error::Request(e) => format!("Request failed: {e}"),
+        }
+    }
+}
 // endregion: --- Error Boilerplate
@@ -1,76 +1,147 @@
 use std::sync::Arc;
 
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
+    http_error::HttpErrorResponse,
     smart_device_dto::{
         Type,
+        action::{ActionRequestDto, ActionResponseDto, ActionStatus, ActionsResponseDto},
         activation::ActivateRequestDto,
+        calibration::{
+            Calibration, CalibrationCurve, CalibrationPoint, CalibrationReferenceRequestDto,
+            CalibrationRequestDto, CalibrationsResponseDto,
+        },
+        certificate::{CertificateDto, CertificateSigningRequestDto},
         config::{ConfigRequestDto, ConfigResponseDto, TypeOption},
+        logs::{LogsQuery, LogsResponseDto},
+        openapi,
         read::ReadResponseDto,
         status::DeviceStatusResponseDto,
-        write::WriteRequestDto,
+        version::{Capability, PROTOCOL_VERSION, VersionResponseDto},
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
     },
     smart_device_interface::config::{Config, Mode, ScriptingApi},
 };
 
 use super::{
+    Error,
+    calibration::{apply_calibration, channel_value},
     config::{read_config_file_with_path, update_config_file_with_path},
     device_builder::DeviceBuilder,
+    encoding::Encoded,
+    logs::recent_logs,
+    state::DeviceState,
 };
 
-pub(crate) async fn write_device_handler<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-    Json(payload): Json<WriteRequestDto>,
-) -> StatusCode
+pub(crate) async fn write_device_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Encoded(payload): Encoded<WriteRequestDto>,
+) -> (StatusCode, Encoded<WriteResponseDto>)
 where
     T: Clone + Default + DeserializeOwned,
+    S: DeviceState,
 {
     let config = device_service
         .config
         .read()
         .map(|c| c.clone())
-        .unwrap_or_else(|_| Arc::new(Config::<T>::default()));
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
+
+    let Some(handler) = device_service.write_handler else {
+        return write_response(WriteResponseDto::failed("Device does not accept writes"));
+    };
+
+    let Some(idempotency_key) = payload.idempotency_key else {
+        return write_response(handler(payload.data, config).await);
+    };
 
-    match device_service.write_handler {
-        Some(handler) => handler(payload.data, config).await,
-        None => StatusCode::INTERNAL_SERVER_ERROR,
+    // Writes sharing a key are serialized so a retry racing the original
+    // request still sees its cached acknowledgement. The cache itself is only
+    // locked briefly, so other writes don't wait for this one.
+    let key_lock = device_service
+        .write_cache
+        .lock()
+        .await
+        .key_lock(&idempotency_key);
+    let _key_guard = key_lock.lock().await;
+    let cached = device_service
+        .write_cache
+        .lock()
+        .await
+        .get(&idempotency_key);
+    if let Some(response) = cached {
+        return write_response(response);
+    }
+
+    let mut response = handler(payload.data, config).await;
+    response.idempotency_key = Some(idempotency_key.clone());
+    // Failures may be transient, so a retry is allowed to try again
+    if response.status != WriteStatus::Failed {
+        device_service
+            .write_cache
+            .lock()
+            .await
+            .insert(idempotency_key, response.clone());
     }
+    write_response(response)
 }
 
-pub(crate) async fn read_device_handler<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-) -> Json<ReadResponseDto>
+fn write_response(response: WriteResponseDto) -> (StatusCode, Encoded<WriteResponseDto>) {
+    let status = match response.status {
+        WriteStatus::Applied | WriteStatus::Unchanged => StatusCode::OK,
+        WriteStatus::Rejected => StatusCode::BAD_REQUEST,
+        WriteStatus::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Encoded(response))
+}
+
+pub(crate) async fn read_device_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<ReadResponseDto>
 where
     T: Clone + Default + DeserializeOwned,
+    S: DeviceState,
 {
     let config = device_service
         .config
         .read()
         .map(|c| c.clone())
-        .unwrap_or_else(|_| Arc::new(Config::<T>::default()));
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
 
     match device_service.read_handler {
-        None => Json(ReadResponseDto { data: Type::None }),
+        None => Encoded(ReadResponseDto { data: Type::None }),
         Some(handler) => {
-            let data = handler(config).await;
-            Json(ReadResponseDto { data })
+            let mut data = handler(config.clone()).await;
+            apply_calibration(&mut data, &config.calibration);
+            // A value in an unknown unit can't be converted, so it isn't handed out
+            for e in data.normalize_units() {
+                tracing::warn!("Dropped a value from the read: {e}");
+            }
+            Encoded(ReadResponseDto { data })
         }
     }
 }
 
-pub(crate) async fn get_config_handler<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-) -> Json<Option<ConfigResponseDto<T>>>
+pub(crate) async fn get_config_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<Option<ConfigResponseDto<T>>>
 where
     T: DeserializeOwned + Clone + Default,
+    S: DeviceState,
 {
+    // Held so a config read before a concurrent update can't replace it afterwards
+    let _config_guard = device_service.config_lock.lock().await;
     match read_config_file_with_path(&device_service.config_path) {
         Ok(config) => {
-            if let Ok(mut guard) = device_service.config.write() {
-                *guard = Arc::new(config.clone());
-            }
+            device_service.set_config(config.clone().with_state(device_service.state.clone()));
             let mut input_type: Option<TypeOption> = None;
             let mut output_type: Option<TypeOption> = None;
             let mode: crate::smart_device_dto::config::Mode;
@@ -106,67 +177,74 @@ where
                 additional_config: config.additional_config,
             };
 
-            Json(Some(config_dto))
+            Encoded(Some(config_dto))
         }
-        Err(_) => Json(None),
+        Err(_) => Encoded(None),
     }
 }
 
-pub(crate) async fn status_device_handler<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-) -> Json<DeviceStatusResponseDto>
+pub(crate) async fn status_device_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<DeviceStatusResponseDto>
 where
     T: Clone + Default + DeserializeOwned,
+    S: DeviceState,
 {
     let config = device_service
         .config
         .read()
         .map(|c| c.clone())
-        .unwrap_or_else(|_| Arc::new(Config::<T>::default()));
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
 
-    Json((device_service.status_handler)(config).await)
+    Encoded((device_service.status_handler)(config).await)
 }
 
-pub(crate) async fn config_update_handler<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-    Json(config): Json<ConfigRequestDto<T>>,
-) -> StatusCode
+pub(crate) async fn config_update_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Encoded(config): Encoded<ConfigRequestDto<T>>,
+) -> Result<StatusCode, Response>
 where
     T: Serialize + Clone + Default,
+    S: DeviceState,
 {
-    let config = (device_service.config_interceptor_handler)(config, {
-        device_service
-            .config
-            .read()
-            .map(|c| c.clone())
-            .unwrap_or_else(|_| Arc::new(Config::<T>::default()))
-    })
-    .await;
+    let _config_guard = device_service.config_lock.lock().await;
+    let old_config = device_service
+        .config
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
+    let mut config = (device_service.config_interceptor_handler)(config, old_config.clone())
+        .await
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response())?;
+    // Calibration is only changed through its own endpoints, the tunnel only
+    // in the local config file since the device may be unreachable without it
+    config.calibration = old_config.calibration.clone();
+    config.tunnel = old_config.tunnel.clone();
 
-    if update_config_file_with_path(&config, &device_service.config_path).is_ok() {
-        if let Ok(mut guard) = device_service.config.write() {
-            *guard = Arc::new(config);
-        }
-        StatusCode::OK
-    } else {
-        StatusCode::INTERNAL_SERVER_ERROR
-    }
+    update_config_file_with_path(&config, &device_service.config_path)
+        .map_err(|e| HttpErrorResponse::new(e).into_response())?;
+    device_service.set_config(config);
+    Ok(StatusCode::OK)
 }
 
-pub(crate) async fn activate_device<T>(
-    State(device_service): State<DeviceBuilder<T>>,
-    Json(config): Json<ActivateRequestDto>,
-) -> StatusCode
+pub(crate) async fn activate_device<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Encoded(config): Encoded<ActivateRequestDto>,
+) -> Result<StatusCode, HttpErrorResponse<Error>>
 where
     T: Clone + Default + Serialize + DeserializeOwned,
+    S: DeviceState,
 {
-    let mut base_config: Config<T> = device_service
+    let _config_guard = device_service.config_lock.lock().await;
+    let mut base_config: Config<T, S> = device_service
         .config
         .read()
         .ok()
         .map(|c| (*c).as_ref().clone())
         .unwrap_or_else(|| {
-            read_config_file_with_path(&device_service.config_path).unwrap_or_default()
+            read_config_file_with_path(&device_service.config_path)
+                .unwrap_or_default()
+                .with_state(device_service.state.clone())
         });
 
     base_config.scripting_api = Some(ScriptingApi {
@@ -174,12 +252,281 @@ where
         token: config.token,
     });
 
-    if update_config_file_with_path(&base_config, &device_service.config_path).is_ok() {
-        if let Ok(mut guard) = device_service.config.write() {
-            *guard = Arc::new(base_config);
+    update_config_file_with_path(&base_config, &device_service.config_path)?;
+    device_service.set_config(base_config);
+    Ok(StatusCode::OK)
+}
+
+/// Forgets the scripting api, e.g. because the device was deleted from
+/// device_service.
+pub(crate) async fn deactivate_device<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Result<StatusCode, HttpErrorResponse<Error>>
+where
+    T: Clone + Default + Serialize + DeserializeOwned,
+    S: DeviceState,
+{
+    let _config_guard = device_service.config_lock.lock().await;
+    let mut base_config: Config<T, S> = device_service
+        .config
+        .read()
+        .ok()
+        .map(|c| (*c).as_ref().clone())
+        .unwrap_or_else(|| {
+            read_config_file_with_path(&device_service.config_path)
+                .unwrap_or_default()
+                .with_state(device_service.state.clone())
+        });
+
+    base_config.scripting_api = None;
+
+    update_config_file_with_path(&base_config, &device_service.config_path)?;
+    device_service.set_config(base_config);
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn certificate_request_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Result<Encoded<CertificateSigningRequestDto>, StatusCode>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let datasource_id = device_service
+        .config
+        .read()
+        .map(|c| c.datasource_id.clone())
+        .unwrap_or_default();
+    match device_service.tls.signing_request(&datasource_id) {
+        Ok(csr_pem) => Ok(Encoded(CertificateSigningRequestDto { csr_pem })),
+        Err(e) => {
+            tracing::error!("Could not create certificate signing request: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
-        StatusCode::OK
-    } else {
-        StatusCode::INTERNAL_SERVER_ERROR
     }
 }
+
+pub(crate) async fn install_certificate_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Encoded(certificate): Encoded<CertificateDto>,
+) -> StatusCode
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    match device_service.tls.install(&certificate) {
+        Ok(()) => StatusCode::OK,
+        Err(Error::CertificateStorage) => StatusCode::INTERNAL_SERVER_ERROR,
+        Err(Error::UntrustedCertificate) => StatusCode::FORBIDDEN,
+        Err(_) => StatusCode::BAD_REQUEST,
+    }
+}
+
+pub(crate) async fn logs_handler(Query(query): Query<LogsQuery>) -> Encoded<LogsResponseDto> {
+    Encoded(LogsResponseDto {
+        records: recent_logs(&query),
+    })
+}
+
+pub(crate) async fn version_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<VersionResponseDto>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let mut capabilities = Vec::new();
+    if device_service.read_handler.is_some() {
+        capabilities.extend([Capability::Read, Capability::Calibration]);
+    }
+    if device_service.write_handler.is_some() {
+        capabilities.extend([Capability::Write, Capability::WriteAcknowledgement]);
+    }
+    capabilities.extend([
+        Capability::ConfigValidation,
+        Capability::Logs,
+        Capability::Actions,
+        Capability::OpenApi,
+        Capability::Cbor,
+        Capability::Deactivation,
+    ]);
+    if device_service.tls.is_serving() {
+        capabilities.push(Capability::Tls);
+    }
+    Encoded(VersionResponseDto {
+        protocol_version: PROTOCOL_VERSION,
+        sdk_version: String::from(env!("CARGO_PKG_VERSION")),
+        capabilities,
+    })
+}
+
+pub(crate) async fn openapi_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi::openapi())
+}
+
+pub(crate) async fn get_calibration_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<CalibrationsResponseDto>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let config = device_service
+        .config
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
+
+    Encoded(CalibrationsResponseDto {
+        channels: config.calibration.clone(),
+    })
+}
+
+pub(crate) async fn update_calibration_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Path(channel): Path<String>,
+    Encoded(request): Encoded<CalibrationRequestDto>,
+) -> Result<Encoded<Calibration>, StatusCode>
+where
+    T: Clone + Default + Serialize,
+    S: DeviceState,
+{
+    let calibration = Calibration {
+        curve: request.curve,
+        calibrated_at: Utc::now(),
+    };
+    let _config_guard = device_service.config_lock.lock().await;
+    let mut config = current_config(&device_service);
+    config.calibration.insert(channel, calibration.clone());
+    store_config(&device_service, config)?;
+    Ok(Encoded(calibration))
+}
+
+pub(crate) async fn delete_calibration_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Path(channel): Path<String>,
+) -> StatusCode
+where
+    T: Clone + Default + Serialize,
+    S: DeviceState,
+{
+    let _config_guard = device_service.config_lock.lock().await;
+    let mut config = current_config(&device_service);
+    if config.calibration.remove(&channel).is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+    match store_config(&device_service, config) {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
+    }
+}
+
+/// Reads the current raw value of the channel and adds it together with the
+/// reference reading as a point of the channel's piecewise calibration.
+pub(crate) async fn calibration_reference_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Path(channel): Path<String>,
+    Encoded(request): Encoded<CalibrationReferenceRequestDto>,
+) -> Result<Encoded<Calibration>, StatusCode>
+where
+    T: Clone + Default + Serialize,
+    S: DeviceState,
+{
+    let Some(read_handler) = &device_service.read_handler else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    let _config_guard = device_service.config_lock.lock().await;
+    let mut config = current_config(&device_service);
+    let raw = channel_value(&read_handler(Arc::new(config.clone())).await, &channel)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let curve = config
+        .calibration
+        .remove(&channel)
+        .map(|calibration| calibration.curve)
+        .unwrap_or(CalibrationCurve::Piecewise(Vec::new()))
+        .with_point(CalibrationPoint {
+            raw,
+            reference: request.reference,
+        });
+    let calibration = Calibration {
+        curve,
+        calibrated_at: Utc::now(),
+    };
+    config.calibration.insert(channel, calibration.clone());
+    store_config(&device_service, config)?;
+    Ok(Encoded(calibration))
+}
+
+pub(crate) async fn list_actions_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+) -> Encoded<ActionsResponseDto>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    Encoded(ActionsResponseDto {
+        actions: device_service
+            .actions
+            .iter()
+            .map(|registered| registered.action.clone())
+            .collect(),
+    })
+}
+
+pub(crate) async fn invoke_action_handler<T, S>(
+    State(device_service): State<DeviceBuilder<T, S>>,
+    Path(name): Path<String>,
+    Encoded(request): Encoded<ActionRequestDto>,
+) -> Response
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let Some(registered) = device_service.action(&name) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if let Err(errors) = registered.action.validate(&request) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response();
+    }
+
+    let config = device_service
+        .config
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_else(|_| Arc::new(Config::<T, S>::default()));
+    let response: ActionResponseDto = (registered.handler)(request, config).await;
+    let status = match response.status {
+        ActionStatus::Completed => StatusCode::OK,
+        ActionStatus::Rejected => StatusCode::BAD_REQUEST,
+        ActionStatus::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Encoded(response)).into_response()
+}
+
+/// Callers hold `config_lock` until the changed config is stored.
+fn current_config<T, S>(device_service: &DeviceBuilder<T, S>) -> Config<T, S>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    device_service
+        .config
+        .read()
+        .map(|c| c.as_ref().clone())
+        .unwrap_or_default()
+}
+
+fn store_config<T, S>(
+    device_service: &DeviceBuilder<T, S>,
+    config: Config<T, S>,
+) -> Result<(), StatusCode>
+where
+    T: Clone + Default + Serialize,
+    S: DeviceState,
+{
+    update_config_file_with_path(&config, &device_service.config_path)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    device_service.set_config(config);
+    Ok(())
+}
@@ -1,30 +1,47 @@
 use axum::{
-    Router,
+    Router, middleware,
     routing::{get, post},
 };
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
-    smart_device_dto::endpoints::{ACTIVATE, CONFIG, STATUS, WRITE},
-    smart_device_interface::handler::activate_device,
+    smart_device_dto::endpoints::{
+        ACTIONS, ACTIVATE, CERTIFICATE, CONFIG, LOGS, OPENAPI, STATUS, VERSION, WRITE,
+    },
+    smart_device_interface::handler::{activate_device, deactivate_device},
 };
 
 use super::{
     device_builder::DeviceBuilder,
+    encoding::negotiate_encoding,
     handler::{
-        config_update_handler, get_config_handler, status_device_handler, write_device_handler,
+        certificate_request_handler, config_update_handler, get_config_handler,
+        install_certificate_handler, invoke_action_handler, list_actions_handler, logs_handler,
+        openapi_handler, status_device_handler, version_handler, write_device_handler,
     },
+    state::DeviceState,
 };
 
-pub fn init_input_router<T>(device_service: DeviceBuilder<T>) -> Router
+pub fn init_input_router<T, S>(device_service: DeviceBuilder<T, S>) -> Router
 where
     T: Clone + Default + Serialize + DeserializeOwned + Send + Sync + 'static,
+    S: DeviceState,
 {
     Router::new()
         .route(WRITE, post(write_device_handler))
         .route(CONFIG, post(config_update_handler))
         .route(CONFIG, get(get_config_handler))
         .route(STATUS, get(status_device_handler))
-        .route(ACTIVATE, post(activate_device))
+        .route(ACTIVATE, post(activate_device).delete(deactivate_device))
+        .route(ACTIONS, get(list_actions_handler))
+        .route(&format!("{ACTIONS}/{{name}}"), post(invoke_action_handler))
+        .route(
+            CERTIFICATE,
+            get(certificate_request_handler).put(install_certificate_handler),
+        )
+        .route(LOGS, get(logs_handler))
+        .route(VERSION, get(version_handler))
+        .route(OPENAPI, get(openapi_handler))
+        .layer(middleware::from_fn(negotiate_encoding))
         .with_state(device_service)
 }
@@ -0,0 +1,185 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use chrono::Utc;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::smart_device_dto::logs::{LogLevel, LogRecordDto, LogsQuery};
+
+/// Records kept for `/logs` and forwarding, the oldest are dropped first.
+const LOG_BUFFER_CAPACITY: usize = 1000;
+
+/// Crates whose events are not buffered, mostly the HTTP stack shipping the
+/// buffered records, which would otherwise log about itself on every shipment.
+const IGNORED_TARGETS: [&str; 7] = [
+    "reqwest",
+    "hyper",
+    "hyper_util",
+    "h2",
+    "rustls",
+    "tungstenite",
+    "tokio_tungstenite",
+];
+
+static LOG_BUFFER: LazyLock<Mutex<LogBuffer>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct LogBuffer {
+    records: VecDeque<(u64, LogRecordDto)>,
+    next_sequence: u64,
+}
+
+/// Tracing layer buffering recent log records of the device, served at
+/// `/logs`. Register it next to the usual fmt layer:
+///
+/// ```ignore
+/// tracing_subscriber::registry()
+///     .with(LogLayer)
+///     .with(tracing_subscriber::fmt::layer())
+///     .init();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogLayer;
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if is_ignored(event.metadata().target()) {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        push(LogRecordDto {
+            timestamp: Utc::now(),
+            level: level(event.metadata().level()),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+fn is_ignored(target: &str) -> bool {
+    let crate_name = target.split("::").next().unwrap_or(target);
+    IGNORED_TARGETS.contains(&crate_name)
+}
+
+fn level(level: &Level) -> LogLevel {
+    match *level {
+        Level::TRACE => LogLevel::Trace,
+        Level::DEBUG => LogLevel::Debug,
+        Level::INFO => LogLevel::Info,
+        Level::WARN => LogLevel::Warn,
+        Level::ERROR => LogLevel::Error,
+    }
+}
+
+/// Joins the message with the remaining fields as `key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.message.is_empty() {
+            self.message.push(' ');
+        }
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.message, "{}={value:?}", field.name());
+        }
+    }
+}
+
+fn push(record: LogRecordDto) {
+    let mut buffer = LOG_BUFFER.lock().unwrap_or_else(PoisonError::into_inner);
+    if buffer.records.len() == LOG_BUFFER_CAPACITY {
+        buffer.records.pop_front();
+    }
+    let sequence = buffer.next_sequence;
+    buffer.next_sequence += 1;
+    buffer.records.push_back((sequence, record));
+}
+
+pub(crate) fn recent_logs(query: &LogsQuery) -> Vec<LogRecordDto> {
+    let buffer = LOG_BUFFER.lock().unwrap_or_else(PoisonError::into_inner);
+    buffer
+        .records
+        .iter()
+        .filter(|(_, record)| query.matches(record))
+        .map(|(_, record)| record.clone())
+        .collect()
+}
+
+/// Records logged from `sequence` on, together with the sequence to continue from.
+pub(crate) fn logs_from(sequence: u64) -> (Vec<LogRecordDto>, u64) {
+    let buffer = LOG_BUFFER.lock().unwrap_or_else(PoisonError::into_inner);
+    let records = buffer
+        .records
+        .iter()
+        .filter(|(record_sequence, _)| *record_sequence >= sequence)
+        .map(|(_, record)| record.clone())
+        .collect();
+    (records, buffer.next_sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn buffers_and_filters_events() {
+        let subscriber = tracing_subscriber::registry().with(LogLayer);
+        let (_, start) = logs_from(u64::MAX);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "buffer_test", "pump started");
+            tracing::warn!(target: "buffer_test", pressure = 3, "pressure low");
+        });
+
+        let (records, _) = logs_from(start);
+        let records: Vec<_> = records
+            .into_iter()
+            .filter(|record| record.target == "buffer_test")
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].message, "pressure low pressure=3");
+
+        let warnings = recent_logs(&LogsQuery {
+            level: Some(LogLevel::Warn),
+            since: Some(records[0].timestamp - chrono::Duration::seconds(1)),
+        });
+        assert!(warnings.iter().all(|record| record.level >= LogLevel::Warn));
+        assert!(
+            warnings
+                .iter()
+                .any(|record| record.message == "pressure low pressure=3")
+        );
+    }
+
+    #[test]
+    fn ignores_http_stack_events() {
+        let subscriber = tracing_subscriber::registry().with(LogLayer);
+        let (_, start) = logs_from(u64::MAX);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "hyper_util::client::legacy::pool", "reuse idle connection");
+            tracing::debug!(target: "reqwest::connect", "starting new connection");
+            tracing::info!(target: "hyperion", "kept");
+        });
+
+        let (records, _) = logs_from(start);
+        assert!(records.iter().all(|record| !is_ignored(&record.target)));
+        assert!(records.iter().any(|record| record.target == "hyperion"));
+    }
+}
@@ -1,10 +1,17 @@
+mod calibration;
 pub mod config;
 pub mod device_builder;
 pub mod device_service;
+mod encoding;
 mod error;
 mod handler;
 pub mod hybrid_device;
 pub mod input_device;
+pub mod logs;
 pub mod output_device;
+pub mod state;
+pub mod tls;
+pub mod tunnel;
+mod write_cache;
 
 pub use self::error::{Error, Result};
@@ -1,30 +1,58 @@
 use axum::{
-    Router,
-    routing::{get, post},
+    Router, middleware,
+    routing::{get, post, put},
 };
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
-    smart_device_dto::endpoints::{ACTIVATE, CONFIG, READ, STATUS},
-    smart_device_interface::handler::activate_device,
+    smart_device_dto::endpoints::{
+        ACTIONS, ACTIVATE, CALIBRATION, CERTIFICATE, CONFIG, LOGS, OPENAPI, READ, STATUS, VERSION,
+    },
+    smart_device_interface::handler::{activate_device, deactivate_device},
 };
 
 use super::{
     device_builder::DeviceBuilder,
+    encoding::negotiate_encoding,
     handler::{
-        config_update_handler, get_config_handler, read_device_handler, status_device_handler,
+        calibration_reference_handler, certificate_request_handler, config_update_handler,
+        delete_calibration_handler, get_calibration_handler, get_config_handler,
+        install_certificate_handler, invoke_action_handler, list_actions_handler, logs_handler,
+        openapi_handler, read_device_handler, status_device_handler, update_calibration_handler,
+        version_handler,
     },
+    state::DeviceState,
 };
 
-pub fn init_output_router<T>(device_service: DeviceBuilder<T>) -> Router
+pub fn init_output_router<T, S>(device_service: DeviceBuilder<T, S>) -> Router
 where
     T: Clone + Default + Serialize + DeserializeOwned + Send + Sync + 'static,
+    S: DeviceState,
 {
     Router::new()
         .route(READ, get(read_device_handler))
         .route(CONFIG, post(config_update_handler))
         .route(CONFIG, get(get_config_handler))
-        .route(ACTIVATE, post(activate_device))
+        .route(ACTIVATE, post(activate_device).delete(deactivate_device))
+        .route(ACTIONS, get(list_actions_handler))
+        .route(&format!("{ACTIONS}/{{name}}"), post(invoke_action_handler))
+        .route(
+            CERTIFICATE,
+            get(certificate_request_handler).put(install_certificate_handler),
+        )
+        .route(LOGS, get(logs_handler))
+        .route(VERSION, get(version_handler))
+        .route(OPENAPI, get(openapi_handler))
         .route(STATUS, get(status_device_handler))
+        .route(CALIBRATION, get(get_calibration_handler))
+        .route(
+            &format!("{CALIBRATION}/{{channel}}"),
+            put(update_calibration_handler).delete(delete_calibration_handler),
+        )
+        .route(
+            &format!("{CALIBRATION}/{{channel}}/reference"),
+            post(calibration_reference_handler),
+        )
+        .layer(middleware::from_fn(negotiate_encoding))
         .with_state(device_service)
 }
@@ -0,0 +1,174 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{Error, Result};
+
+/// Bounds of a state type attached with `DeviceBuilder::with_state`.
+pub trait DeviceState:
+    Serialize + DeserializeOwned + Clone + Default + Send + Sync + 'static
+{
+}
+impl<S> DeviceState for S where
+    S: Serialize + DeserializeOwned + Clone + Default + Send + Sync + 'static
+{
+}
+
+/// Runtime state of a device, e.g. the last applied setpoint or a counter,
+/// persisted next to the config file so it survives restarts.
+#[derive(Debug)]
+pub struct StateHandle<S> {
+    state: Mutex<S>,
+    /// Serializes updates, so the state on disk is always the visible one.
+    writer: futures::lock::Mutex<()>,
+    /// `None` until the store is attached, updates then stay in memory.
+    path: Option<PathBuf>,
+}
+
+impl<S> Default for StateHandle<S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(S::default()),
+            writer: futures::lock::Mutex::new(()),
+            path: None,
+        }
+    }
+}
+
+impl<S> StateHandle<S>
+where
+    S: DeviceState,
+{
+    /// Restores the state stored at `path`, or starts from `S::default()` if
+    /// nothing was stored yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let state = match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).map_err(|_| Error::IllFormattedState)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => S::default(),
+            Err(_) => return Err(Error::StateStorage),
+        };
+        Ok(Self {
+            state: Mutex::new(state),
+            writer: futures::lock::Mutex::new(()),
+            path: Some(path),
+        })
+    }
+
+    pub fn get(&self) -> S {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Applies `update` to a copy of the state and persists it before it
+    /// becomes visible. If persisting fails the previous state is kept.
+    pub async fn update<R, F>(&self, update: F) -> Result<R>
+    where
+        F: FnOnce(&mut S) -> R + Send,
+        R: Send,
+    {
+        let _writer = self.writer.lock().await;
+        let mut next = self.get();
+        let result = update(&mut next);
+        if let Some(path) = &self.path {
+            let json = serde_json::to_vec(&next).map_err(|_| Error::IllFormattedState)?;
+            let path = path.clone();
+            // Syncing to disk blocks, so it must not stall the runtime
+            tokio::task::spawn_blocking(move || persist(&json, &path))
+                .await
+                .map_err(|_| Error::StateStorage)??;
+        }
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = next;
+        Ok(result)
+    }
+
+    pub async fn set(&self, state: S) -> Result<()> {
+        self.update(|current| *current = state).await
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+/// Writes to a temporary file and renames it over the old state, so a crash
+/// leaves either the old or the new state on disk, never a partial one.
+fn persist(json: &[u8], path: &Path) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+
+    let mut file = File::create(&tmp_path).map_err(|_| Error::StateStorage)?;
+    file.write_all(json).map_err(|_| Error::StateStorage)?;
+    file.sync_all().map_err(|_| Error::StateStorage)?;
+    std::fs::rename(&tmp_path, path).map_err(|_| Error::StateStorage)
+}
+
+/// The state file belonging to a config file, e.g. `config.state.json` for
+/// `config.json`.
+pub(crate) fn state_path_for_config(config_path: &str) -> PathBuf {
+    Path::new(config_path).with_extension("state.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+    struct Counter {
+        count: u32,
+    }
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("{}.state.json", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn restores_persisted_state() {
+        let path = temp_path();
+        let handle = StateHandle::<Counter>::open(&path).unwrap();
+        assert_eq!(handle.get(), Counter::default());
+
+        let count = handle
+            .update(|state| {
+                state.count += 2;
+                state.count
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let restored = StateHandle::<Counter>::open(&path).unwrap();
+        assert_eq!(restored.get(), Counter { count: 2 });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_previous_state_if_persisting_fails() {
+        let path = std::env::temp_dir()
+            .join(uuid::Uuid::new_v4().to_string())
+            .join("state.json");
+        let handle = StateHandle::<Counter>::open(&path).unwrap();
+
+        assert!(handle.update(|state| state.count = 5).await.is_err());
+        assert_eq!(handle.get(), Counter::default());
+    }
+
+    #[tokio::test]
+    async fn keeps_unattached_state_in_memory() {
+        let handle = StateHandle::<Counter>::default();
+
+        handle.set(Counter { count: 3 }).await.unwrap();
+        assert_eq!(handle.get(), Counter { count: 3 });
+        assert!(handle.path().is_none());
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::Router;
+use axum_server::{Handle, tls_rustls::RustlsConfig};
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair};
+use rustls::{
+    ServerConfig,
+    pki_types::{CertificateDer, PrivateKeyDer, UnixTime, pem::PemObject},
+};
+use tokio::{net::TcpListener, sync::Notify};
+
+use crate::smart_device_dto::certificate::CertificateDto;
+
+use super::{Error, Result, device_builder::DeviceBuilder, state::DeviceState};
+
+/// How long open connections may finish when the server restarts with a new
+/// certificate.
+const RESTART_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Key and certificate of the device, stored next to the config file. The key
+/// is created on the first signing request and never leaves the device.
+pub(crate) struct TlsIdentity {
+    key_path: PathBuf,
+    certificate_path: PathBuf,
+    /// CA pinned by the first install, later certificates must be issued by
+    /// it. Deleting the file allows installing one from another CA.
+    ca_path: PathBuf,
+    /// Set by `serve`, devices served otherwise can't switch to HTTPS.
+    serving: AtomicBool,
+    installed: Notify,
+}
+
+impl TlsIdentity {
+    pub(crate) fn for_config(config_path: &str) -> Self {
+        let config_path = Path::new(config_path);
+        Self {
+            key_path: config_path.with_extension("key.pem"),
+            certificate_path: config_path.with_extension("cert.pem"),
+            ca_path: config_path.with_extension("ca.pem"),
+            serving: AtomicBool::new(false),
+            installed: Notify::new(),
+        }
+    }
+
+    pub(crate) fn is_serving(&self) -> bool {
+        self.serving.load(Ordering::Relaxed)
+    }
+
+    /// PEM encoded signing request for the device key. The subject is only a
+    /// hint, device_service decides the names the certificate is issued for.
+    pub(crate) fn signing_request(&self, datasource_id: &str) -> Result<String> {
+        let key = self.key_pair()?;
+        let mut params = CertificateParams::default();
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, datasource_id);
+        params
+            .serialize_request(&key)
+            .and_then(|request| request.pem())
+            .map_err(|_| Error::Certificate)
+    }
+
+    /// Stores the certificate if it belongs to the device key and is issued by
+    /// the pinned CA, then restarts `serve` on HTTPS. The first install pins
+    /// the CA it comes with.
+    pub(crate) fn install(&self, certificate: &CertificateDto) -> Result<()> {
+        let key = std::fs::read(&self.key_path).map_err(|_| Error::CertificateStorage)?;
+        let pinned_ca = match std::fs::read_to_string(&self.ca_path) {
+            Ok(ca) => Some(ca),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(_) => return Err(Error::CertificateStorage),
+        };
+        let ca = pinned_ca
+            .as_deref()
+            .unwrap_or(&certificate.ca_certificate_pem);
+        verify_issued_by(&certificate.certificate_pem, ca)?;
+
+        let chain = format!("{}\n{}", certificate.certificate_pem.trim_end(), ca);
+        server_config(chain.as_bytes(), &key)?;
+        if pinned_ca.is_none() {
+            std::fs::write(&self.ca_path, ca).map_err(|_| Error::CertificateStorage)?;
+        }
+        std::fs::write(&self.certificate_path, chain).map_err(|_| Error::CertificateStorage)?;
+        self.installed.notify_one();
+        Ok(())
+    }
+
+    /// Server config for the installed certificate, `None` until one is installed.
+    fn server_config(&self) -> Result<Option<ServerConfig>> {
+        let (Ok(chain), Ok(key)) = (
+            std::fs::read(&self.certificate_path),
+            std::fs::read(&self.key_path),
+        ) else {
+            return Ok(None);
+        };
+        server_config(&chain, &key).map(Some)
+    }
+
+    fn key_pair(&self) -> Result<KeyPair> {
+        match std::fs::read_to_string(&self.key_path) {
+            Ok(pem) => KeyPair::from_pem(&pem).map_err(|_| Error::Certificate),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = KeyPair::generate().map_err(|_| Error::Certificate)?;
+                write_private(&self.key_path, &key.serialize_pem())?;
+                Ok(key)
+            }
+            Err(_) => Err(Error::CertificateStorage),
+        }
+    }
+}
+
+/// Fails with `UntrustedCertificate` unless `certificate_pem` is a server
+/// certificate issued by `ca_pem`.
+fn verify_issued_by(certificate_pem: &str, ca_pem: &str) -> Result<()> {
+    let certificate = CertificateDer::from_pem_slice(certificate_pem.as_bytes())
+        .map_err(|_| Error::Certificate)?;
+    let ca = CertificateDer::from_pem_slice(ca_pem.as_bytes()).map_err(|_| Error::Certificate)?;
+    let anchor = webpki::anchor_from_trusted_cert(&ca).map_err(|_| Error::Certificate)?;
+    webpki::EndEntityCert::try_from(&certificate)
+        .map_err(|_| Error::Certificate)?
+        .verify_for_usage(
+            webpki::ALL_VERIFICATION_ALGS,
+            &[anchor],
+            &[],
+            UnixTime::now(),
+            webpki::KeyUsage::server_auth(),
+            None,
+            None,
+        )
+        .map(|_| ())
+        .map_err(|_| Error::UntrustedCertificate)
+}
+
+fn server_config(chain: &[u8], key: &[u8]) -> Result<ServerConfig> {
+    let chain = CertificateDer::pem_slice_iter(chain)
+        .collect::<core::result::Result<Vec<_>, _>>()
+        .map_err(|_| Error::Certificate)?;
+    let key = PrivateKeyDer::from_pem_slice(key).map_err(|_| Error::Certificate)?;
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|_| Error::Certificate)?
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .map_err(|_| Error::Certificate)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|_| Error::CertificateStorage)
+}
+
+/// Serves the device router, over plain HTTP until device_service installed a
+/// certificate and over HTTPS from then on. Use it instead of `axum::serve` so
+/// the device advertises `Capability::Tls`.
+pub async fn serve<T, S>(
+    device_service: &DeviceBuilder<T, S>,
+    router: Router,
+    address: SocketAddr,
+) -> std::io::Result<()>
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let identity = device_service.tls.clone();
+    identity.serving.store(true, Ordering::Relaxed);
+
+    loop {
+        let handle = Handle::new();
+        let restart = handle.clone();
+        let installed = identity.clone();
+        tokio::spawn(async move {
+            installed.installed.notified().await;
+            restart.graceful_shutdown(Some(RESTART_GRACE_PERIOD));
+        });
+
+        // Tokio sets SO_REUSEADDR, so the port can be bound again right away
+        let listener = TcpListener::bind(address).await?.into_std()?;
+        let service = router.clone().into_make_service();
+        match identity.server_config() {
+            Ok(Some(config)) => {
+                tracing::info!("Serving HTTPS on {address}");
+                axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(Arc::new(config)))
+                    .handle(handle)
+                    .serve(service)
+                    .await?
+            }
+            result => {
+                if result.is_err() {
+                    tracing::error!("Installed certificate is unusable, serving plain HTTP");
+                }
+                tracing::info!("Serving HTTP on {address}");
+                axum_server::from_tcp(listener)
+                    .handle(handle)
+                    .serve(service)
+                    .await?
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{BasicConstraints, Certificate, CertificateSigningRequestParams, IsCa};
+
+    fn ca() -> (Certificate, KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        (params.self_signed(&key).unwrap(), key)
+    }
+
+    fn issue(identity: &TlsIdentity, (ca, ca_key): &(Certificate, KeyPair)) -> CertificateDto {
+        let request = identity.signing_request("device").unwrap();
+        let certificate = CertificateSigningRequestParams::from_pem(&request)
+            .unwrap()
+            .signed_by(ca, ca_key)
+            .unwrap();
+        CertificateDto {
+            certificate_pem: certificate.pem(),
+            ca_certificate_pem: ca.pem(),
+        }
+    }
+
+    fn temp_identity() -> (TlsIdentity, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("greenhouse-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let identity = TlsIdentity::for_config(dir.join("config.json").to_str().unwrap());
+        (identity, dir)
+    }
+
+    #[test]
+    fn installs_certificate_for_own_key() {
+        let (identity, dir) = temp_identity();
+        assert!(identity.server_config().unwrap().is_none());
+
+        let ca = ca();
+        identity.install(&issue(&identity, &ca)).unwrap();
+        assert!(identity.server_config().unwrap().is_some());
+
+        // A certificate for another key is refused
+        let foreign = CertificateDto {
+            certificate_pem: ca.0.pem(),
+            ca_certificate_pem: ca.0.pem(),
+        };
+        assert!(identity.install(&foreign).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_accepts_certificates_of_the_pinned_ca() {
+        let (identity, dir) = temp_identity();
+        let pinned = ca();
+        identity.install(&issue(&identity, &pinned)).unwrap();
+
+        // Renewals by the pinned CA are accepted
+        identity.install(&issue(&identity, &pinned)).unwrap();
+
+        let other = issue(&identity, &ca());
+        assert!(matches!(
+            identity.install(&other),
+            Err(Error::UntrustedCertificate)
+        ));
+        // Claiming the pinned CA doesn't help either
+        let forged = CertificateDto {
+            ca_certificate_pem: pinned.0.pem(),
+            ..other
+        };
+        assert!(matches!(
+            identity.install(&forged),
+            Err(Error::UntrustedCertificate)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
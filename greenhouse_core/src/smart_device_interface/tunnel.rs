@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderValue, Request, header},
+};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{Message, client::IntoClientRequest};
+use tower::ServiceExt;
+
+use crate::smart_device_dto::{
+    encoding::Encoding,
+    tunnel::{TUNNEL_TOKEN_HEADER, TunnelRequestDto, TunnelResponseDto},
+};
+
+use super::{config::TunnelConfig, device_builder::DeviceBuilder, state::DeviceState};
+
+/// Largest response body sent back through the tunnel.
+const BODY_LIMIT: usize = 2 * 1024 * 1024;
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Keeps the reverse connection configured in `config.tunnel` open and answers
+/// the requests device_service sends through it with `router`. Does nothing if
+/// no tunnel is configured. Reconnects with backoff when the connection drops.
+pub fn spawn_tunnel<T, S>(device_service: &DeviceBuilder<T, S>, router: Router)
+where
+    T: Clone + Default,
+    S: DeviceState,
+{
+    let Some(tunnel) = device_service
+        .config
+        .read()
+        .ok()
+        .and_then(|config| config.tunnel.clone())
+    else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut delay = Duration::from_secs(1);
+        loop {
+            match run_tunnel(&tunnel, &router).await {
+                Ok(()) => {
+                    tracing::info!("Tunnel to {} closed", tunnel.url);
+                    delay = Duration::from_secs(1);
+                }
+                Err(e) => tracing::warn!("Tunnel to {} failed: {e}", tunnel.url),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+}
+
+async fn run_tunnel(
+    tunnel: &TunnelConfig,
+    router: &Router,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let mut request = tunnel.url.as_str().into_client_request()?;
+    if let Ok(token) = HeaderValue::from_str(&format!("Bearer {}", tunnel.token)) {
+        request.headers_mut().insert(TUNNEL_TOKEN_HEADER, token);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+    tracing::info!("Tunnel to {} connected", tunnel.url);
+    let (mut sink, mut stream) = socket.split();
+
+    // Requests are answered concurrently, responses are funneled to the sink
+    let (responses, mut pending) = mpsc::channel::<TunnelResponseDto>(16);
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Binary(bytes))) => {
+                    let request: TunnelRequestDto = match Encoding::Cbor.decode(&bytes) {
+                        Ok(request) => request,
+                        Err(e) => {
+                            tracing::warn!("Ignoring malformed tunnel request: {e}");
+                            continue;
+                        }
+                    };
+                    let router = router.clone();
+                    let responses = responses.clone();
+                    tokio::spawn(async move {
+                        let _ = responses.send(dispatch(router, request).await).await;
+                    });
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+            },
+            Some(response) = pending.recv() => {
+                match Encoding::Cbor.encode(&response) {
+                    Ok(bytes) => sink.send(Message::Binary(bytes.into())).await?,
+                    Err(e) => tracing::error!("Could not encode tunnel response: {e}"),
+                }
+            }
+            _ = ping.tick() => sink.send(Message::Ping(Default::default())).await?,
+        }
+    }
+}
+
+/// Runs a tunneled request through the device router as if it came in over HTTP.
+async fn dispatch(router: Router, request: TunnelRequestDto) -> TunnelResponseDto {
+    let id = request.id;
+    let mut builder = Request::builder()
+        .method(request.method.as_str())
+        .uri(request.path.as_str());
+    if let Some(content_type) = &request.content_type {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(accept) = &request.accept {
+        builder = builder.header(header::ACCEPT, accept);
+    }
+    let Ok(http_request) = builder.body(Body::from(request.body)) else {
+        return TunnelResponseDto {
+            id,
+            status: 400,
+            content_type: None,
+            body: Vec::new(),
+        };
+    };
+
+    let Ok(response) = router.oneshot(http_request).await;
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(String::from);
+    match to_bytes(response.into_body(), BODY_LIMIT).await {
+        Ok(body) => TunnelResponseDto {
+            id,
+            status,
+            content_type,
+            body: body.to_vec(),
+        },
+        Err(_) => TunnelResponseDto {
+            id,
+            status: 500,
+            content_type: None,
+            body: Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    #[tokio::test]
+    async fn dispatches_through_router() {
+        let router = Router::new().route("/status", get(|| async { "running" }));
+        let response = dispatch(
+            router,
+            TunnelRequestDto {
+                id: 7,
+                method: String::from("GET"),
+                path: String::from("/status"),
+                content_type: None,
+                accept: None,
+                body: Vec::new(),
+            },
+        )
+        .await;
+
+        assert_eq!(response.id, 7);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"running");
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use futures::lock::Mutex;
+
+use crate::smart_device_dto::write::WriteResponseDto;
+
+// Number of acknowledged writes remembered for de-duplicating retries
+const WRITE_CACHE_CAPACITY: usize = 64;
+
+#[derive(Default)]
+pub(crate) struct WriteCache {
+    entries: VecDeque<(String, WriteResponseDto)>,
+    /// Locks of the keys currently being written, so only writes sharing a
+    /// key wait for each other.
+    locks: HashMap<String, Arc<Mutex<()>>>,
+}
+
+impl WriteCache {
+    pub(crate) fn key_lock(&mut self, idempotency_key: &str) -> Arc<Mutex<()>> {
+        // Locks nobody holds a handle to anymore are no longer needed
+        self.locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        self.locks
+            .entry(idempotency_key.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub(crate) fn get(&self, idempotency_key: &str) -> Option<WriteResponseDto> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == idempotency_key)
+            .map(|(_, response)| response.clone())
+    }
+
+    pub(crate) fn insert(&mut self, idempotency_key: String, response: WriteResponseDto) {
+        if self.entries.len() >= WRITE_CACHE_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((idempotency_key, response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_device_dto::Type;
+
+    #[test]
+    fn returns_cached_response() {
+        let mut cache = WriteCache::default();
+        cache.insert(
+            String::from("key"),
+            WriteResponseDto::applied(Type::Number(1.0)),
+        );
+
+        let response = cache.get("key").unwrap();
+        assert_eq!(response.applied, Some(Type::Number(1.0)));
+        assert!(cache.get("other").is_none());
+    }
+
+    #[test]
+    fn evicts_oldest_entry() {
+        let mut cache = WriteCache::default();
+        for i in 0..=WRITE_CACHE_CAPACITY {
+            cache.insert(
+                i.to_string(),
+                WriteResponseDto::applied(Type::Boolean(true)),
+            );
+        }
+
+        assert!(cache.get("0").is_none());
+        assert!(cache.get(&WRITE_CACHE_CAPACITY.to_string()).is_some());
+    }
+
+    #[test]
+    fn shares_locks_per_key_only() {
+        let mut cache = WriteCache::default();
+        let lock = cache.key_lock("key");
+
+        assert!(Arc::ptr_eq(&lock, &cache.key_lock("key")));
+        assert!(!Arc::ptr_eq(&lock, &cache.key_lock("other")));
+
+        drop(lock);
+        cache.key_lock("other");
+        assert!(!cache.locks.contains_key("key"));
+    }
+}
@@ -0,0 +1,60 @@
+//! JSON over fixed buffers through `serde-json-core`, for firmware that keeps
+//! request and response bodies off the heap.
+
+use serde::{Serialize, de::DeserializeOwned};
+
+pub use serde_json_core::{de::Error as DeError, ser::Error as SerError};
+
+/// Serializes `value` into `buffer`, returning the number of bytes written.
+pub fn to_slice<T: Serialize>(value: &T, buffer: &mut [u8]) -> Result<usize, SerError> {
+    serde_json_core::to_slice(value, buffer)
+}
+
+/// Deserializes a complete JSON body, e.g. a `WriteRequestDto`.
+pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> Result<T, DeError> {
+    serde_json_core::from_slice(body).map(|(value, _)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Measurement, Type,
+        read::ReadResponseDto,
+        status::{DeviceStatusDto, DeviceStatusResponseDto},
+        write::WriteRequestDto,
+    };
+    use alloc::string::String;
+
+    #[test]
+    fn writes_responses_into_fixed_buffer() {
+        let mut buffer = [0u8; 128];
+        let response = ReadResponseDto {
+            data: Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("°C"),
+            }),
+        };
+        let len = to_slice(&response, &mut buffer).unwrap();
+        let expected = serde_json::to_vec(&response).unwrap();
+        assert_eq!(&buffer[..len], expected.as_slice());
+
+        let status = DeviceStatusResponseDto {
+            status: DeviceStatusDto::Online,
+            datasource_id: String::from("7a224a14"),
+        };
+        let len = to_slice(&status, &mut buffer).unwrap();
+        assert_eq!(
+            &buffer[..len],
+            br#"{"status":"Online","datasource_id":"7a224a14"}"#
+        );
+    }
+
+    #[test]
+    fn reads_write_requests() {
+        let request: WriteRequestDto =
+            from_slice(br#"{"data":{"Boolean":true},"idempotency_key":"k1"}"#).unwrap();
+        assert!(matches!(request.data, Type::Boolean(true)));
+        assert_eq!(request.idempotency_key.as_deref(), Some("k1"));
+    }
+}
@@ -0,0 +1,19 @@
+//! Smart device protocol DTOs without std, so firmware (ESP32, RP2040, ...)
+//! and the backend share one definition. Only `alloc` is required;
+//! greenhouse_core re-exports everything from here.
+// The OpenAPI derives need std, firmware builds leave that feature off
+#![cfg_attr(not(feature = "openapi"), no_std)]
+
+extern crate alloc;
+#[cfg(all(test, not(feature = "openapi")))]
+extern crate std;
+
+#[cfg(feature = "json-core")]
+pub mod json_core;
+pub mod read;
+pub mod status;
+pub mod units;
+pub mod value;
+pub mod write;
+
+pub use value::{Measurement, Timestamped, Type, TypeOption};
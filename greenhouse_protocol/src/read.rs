@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use super::Type;
+use crate::Type;
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ReadResponseDto {
     pub data: Type,
 }
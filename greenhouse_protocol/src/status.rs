@@ -1,12 +1,16 @@
+use alloc::string::String;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum DeviceStatusDto {
     Online,
     Panic,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DeviceStatusResponseDto {
     pub status: DeviceStatusDto,
     pub datasource_id: String,
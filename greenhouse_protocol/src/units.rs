@@ -0,0 +1,481 @@
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownUnit(String),
+    IncompatibleUnits(Unit, Unit),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        match self {
+            Error::UnknownUnit(unit) => write!(fmt, "Unknown unit: {unit}"),
+            Error::IncompatibleUnits(from, to) => write!(
+                fmt,
+                "Cannot convert {} ({:?}) to {} ({:?})",
+                from.symbol(),
+                from.quantity(),
+                to.symbol(),
+                to.quantity()
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantity {
+    Temperature,
+    Humidity,
+    Pressure,
+    Light,
+    ElectricalConductivity,
+    Ph,
+    Flow,
+    Energy,
+    /// Share of a whole, e.g. soil moisture or a valve position.
+    Ratio,
+    /// Concentration of a gas like CO2.
+    Concentration,
+    Power,
+    Voltage,
+    Current,
+}
+
+impl Quantity {
+    /// The unit every value of this quantity is converted through.
+    pub fn base_unit(&self) -> Unit {
+        match self {
+            Quantity::Temperature => Unit::Celsius,
+            Quantity::Humidity => Unit::RelativeHumidity,
+            Quantity::Pressure => Unit::Pascal,
+            Quantity::Light => Unit::Lux,
+            Quantity::ElectricalConductivity => Unit::MicrosiemensPerCentimeter,
+            Quantity::Ph => Unit::Ph,
+            Quantity::Flow => Unit::LitersPerMinute,
+            Quantity::Energy => Unit::WattHour,
+            Quantity::Ratio => Unit::Percent,
+            Quantity::Concentration => Unit::PartsPerMillion,
+            Quantity::Power => Unit::Watt,
+            Quantity::Voltage => Unit::Volt,
+            Quantity::Current => Unit::Ampere,
+        }
+    }
+}
+
+/// Canonical units known to the system. Serialized as their symbol.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+    RelativeHumidity,
+    Pascal,
+    Hectopascal,
+    Kilopascal,
+    Millibar,
+    Bar,
+    Psi,
+    Lux,
+    Kilolux,
+    FootCandle,
+    MicrosiemensPerCentimeter,
+    MillisiemensPerCentimeter,
+    DecisiemensPerMeter,
+    SiemensPerMeter,
+    Ph,
+    MillilitersPerMinute,
+    LitersPerMinute,
+    LitersPerHour,
+    CubicMetersPerHour,
+    GallonsPerMinute,
+    Joule,
+    Kilojoule,
+    Megajoule,
+    WattHour,
+    KilowattHour,
+    Percent,
+    PartsPerMillion,
+    PartsPerBillion,
+    Watt,
+    Kilowatt,
+    Volt,
+    Millivolt,
+    Ampere,
+    Milliampere,
+}
+
+pub const UNITS: &[Unit] = &[
+    Unit::Celsius,
+    Unit::Fahrenheit,
+    Unit::Kelvin,
+    Unit::RelativeHumidity,
+    Unit::Pascal,
+    Unit::Hectopascal,
+    Unit::Kilopascal,
+    Unit::Millibar,
+    Unit::Bar,
+    Unit::Psi,
+    Unit::Lux,
+    Unit::Kilolux,
+    Unit::FootCandle,
+    Unit::MicrosiemensPerCentimeter,
+    Unit::MillisiemensPerCentimeter,
+    Unit::DecisiemensPerMeter,
+    Unit::SiemensPerMeter,
+    Unit::Ph,
+    Unit::MillilitersPerMinute,
+    Unit::LitersPerMinute,
+    Unit::LitersPerHour,
+    Unit::CubicMetersPerHour,
+    Unit::GallonsPerMinute,
+    Unit::Joule,
+    Unit::Kilojoule,
+    Unit::Megajoule,
+    Unit::WattHour,
+    Unit::KilowattHour,
+    Unit::Percent,
+    Unit::PartsPerMillion,
+    Unit::PartsPerBillion,
+    Unit::Watt,
+    Unit::Kilowatt,
+    Unit::Volt,
+    Unit::Millivolt,
+    Unit::Ampere,
+    Unit::Milliampere,
+];
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Kelvin => "K",
+            Unit::RelativeHumidity => "%RH",
+            Unit::Pascal => "Pa",
+            Unit::Hectopascal => "hPa",
+            Unit::Kilopascal => "kPa",
+            Unit::Millibar => "mbar",
+            Unit::Bar => "bar",
+            Unit::Psi => "psi",
+            Unit::Lux => "lx",
+            Unit::Kilolux => "klx",
+            Unit::FootCandle => "fc",
+            Unit::MicrosiemensPerCentimeter => "µS/cm",
+            Unit::MillisiemensPerCentimeter => "mS/cm",
+            Unit::DecisiemensPerMeter => "dS/m",
+            Unit::SiemensPerMeter => "S/m",
+            Unit::Ph => "pH",
+            Unit::MillilitersPerMinute => "mL/min",
+            Unit::LitersPerMinute => "L/min",
+            Unit::LitersPerHour => "L/h",
+            Unit::CubicMetersPerHour => "m³/h",
+            Unit::GallonsPerMinute => "gal/min",
+            Unit::Joule => "J",
+            Unit::Kilojoule => "kJ",
+            Unit::Megajoule => "MJ",
+            Unit::WattHour => "Wh",
+            Unit::KilowattHour => "kWh",
+            Unit::Percent => "%",
+            Unit::PartsPerMillion => "ppm",
+            Unit::PartsPerBillion => "ppb",
+            Unit::Watt => "W",
+            Unit::Kilowatt => "kW",
+            Unit::Volt => "V",
+            Unit::Millivolt => "mV",
+            Unit::Ampere => "A",
+            Unit::Milliampere => "mA",
+        }
+    }
+
+    /// Alternative spellings accepted when parsing, compared in lowercase.
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Unit::Celsius => &["c", "celsius", "degc", "deg c", "℃"],
+            Unit::Fahrenheit => &["f", "fahrenheit", "degf", "deg f", "℉"],
+            Unit::Kelvin => &["kelvin"],
+            Unit::RelativeHumidity => &["rh", "% rh"],
+            Unit::Pascal => &["pascal"],
+            Unit::Hectopascal => &["hectopascal"],
+            Unit::Kilopascal => &["kilopascal"],
+            Unit::Millibar => &["mb", "millibar"],
+            Unit::Bar => &[],
+            Unit::Psi => &[],
+            Unit::Lux => &["lux"],
+            Unit::Kilolux => &["kilolux"],
+            Unit::FootCandle => &["foot-candle", "footcandle"],
+            Unit::MicrosiemensPerCentimeter => &["us/cm", "μs/cm"],
+            Unit::MillisiemensPerCentimeter => &[],
+            Unit::DecisiemensPerMeter => &[],
+            Unit::SiemensPerMeter => &[],
+            Unit::Ph => &[],
+            Unit::MillilitersPerMinute => &["ml/min"],
+            Unit::LitersPerMinute => &["l/min", "lpm"],
+            Unit::LitersPerHour => &["l/h", "lph"],
+            Unit::CubicMetersPerHour => &["m3/h"],
+            Unit::GallonsPerMinute => &["gpm"],
+            Unit::Joule => &["joule"],
+            Unit::Kilojoule => &[],
+            Unit::Megajoule => &[],
+            Unit::WattHour => &[],
+            Unit::KilowattHour => &[],
+            Unit::Percent => &["percent"],
+            Unit::PartsPerMillion => &[],
+            Unit::PartsPerBillion => &[],
+            Unit::Watt => &["watt"],
+            Unit::Kilowatt => &["kilowatt"],
+            Unit::Volt => &["volt"],
+            Unit::Millivolt => &["millivolt"],
+            Unit::Ampere => &["amp", "ampere"],
+            Unit::Milliampere => &["milliamp", "milliampere"],
+        }
+    }
+
+    pub fn quantity(&self) -> Quantity {
+        match self {
+            Unit::Celsius | Unit::Fahrenheit | Unit::Kelvin => Quantity::Temperature,
+            Unit::RelativeHumidity => Quantity::Humidity,
+            Unit::Pascal
+            | Unit::Hectopascal
+            | Unit::Kilopascal
+            | Unit::Millibar
+            | Unit::Bar
+            | Unit::Psi => Quantity::Pressure,
+            Unit::Lux | Unit::Kilolux | Unit::FootCandle => Quantity::Light,
+            Unit::MicrosiemensPerCentimeter
+            | Unit::MillisiemensPerCentimeter
+            | Unit::DecisiemensPerMeter
+            | Unit::SiemensPerMeter => Quantity::ElectricalConductivity,
+            Unit::Ph => Quantity::Ph,
+            Unit::MillilitersPerMinute
+            | Unit::LitersPerMinute
+            | Unit::LitersPerHour
+            | Unit::CubicMetersPerHour
+            | Unit::GallonsPerMinute => Quantity::Flow,
+            Unit::Joule
+            | Unit::Kilojoule
+            | Unit::Megajoule
+            | Unit::WattHour
+            | Unit::KilowattHour => Quantity::Energy,
+            Unit::Percent => Quantity::Ratio,
+            Unit::PartsPerMillion | Unit::PartsPerBillion => Quantity::Concentration,
+            Unit::Watt | Unit::Kilowatt => Quantity::Power,
+            Unit::Volt | Unit::Millivolt => Quantity::Voltage,
+            Unit::Ampere | Unit::Milliampere => Quantity::Current,
+        }
+    }
+
+    // (scale, offset) so that `base = value * scale + offset`
+    fn base_factors(&self) -> (f64, f64) {
+        match self {
+            Unit::Celsius => (1.0, 0.0),
+            Unit::Fahrenheit => (5.0 / 9.0, -160.0 / 9.0),
+            Unit::Kelvin => (1.0, -273.15),
+            Unit::RelativeHumidity => (1.0, 0.0),
+            Unit::Pascal => (1.0, 0.0),
+            Unit::Hectopascal => (100.0, 0.0),
+            Unit::Kilopascal => (1_000.0, 0.0),
+            Unit::Millibar => (100.0, 0.0),
+            Unit::Bar => (100_000.0, 0.0),
+            Unit::Psi => (6_894.757_293_168, 0.0),
+            Unit::Lux => (1.0, 0.0),
+            Unit::Kilolux => (1_000.0, 0.0),
+            Unit::FootCandle => (10.763_910_416_709_722, 0.0),
+            Unit::MicrosiemensPerCentimeter => (1.0, 0.0),
+            Unit::MillisiemensPerCentimeter => (1_000.0, 0.0),
+            Unit::DecisiemensPerMeter => (1_000.0, 0.0),
+            Unit::SiemensPerMeter => (10_000.0, 0.0),
+            Unit::Ph => (1.0, 0.0),
+            Unit::MillilitersPerMinute => (0.001, 0.0),
+            Unit::LitersPerMinute => (1.0, 0.0),
+            Unit::LitersPerHour => (1.0 / 60.0, 0.0),
+            Unit::CubicMetersPerHour => (1_000.0 / 60.0, 0.0),
+            Unit::GallonsPerMinute => (3.785_411_784, 0.0),
+            Unit::Joule => (1.0 / 3_600.0, 0.0),
+            Unit::Kilojoule => (1.0 / 3.6, 0.0),
+            Unit::Megajoule => (1_000.0 / 3.6, 0.0),
+            Unit::WattHour => (1.0, 0.0),
+            Unit::KilowattHour => (1_000.0, 0.0),
+            Unit::Percent => (1.0, 0.0),
+            Unit::PartsPerMillion => (1.0, 0.0),
+            Unit::PartsPerBillion => (0.001, 0.0),
+            Unit::Watt => (1.0, 0.0),
+            Unit::Kilowatt => (1_000.0, 0.0),
+            Unit::Volt => (1.0, 0.0),
+            Unit::Millivolt => (0.001, 0.0),
+            Unit::Ampere => (1.0, 0.0),
+            Unit::Milliampere => (0.001, 0.0),
+        }
+    }
+
+    /// Converts `value` given in this unit into `target`.
+    pub fn convert(&self, value: f64, target: Unit) -> Result<f64> {
+        if self.quantity() != target.quantity() {
+            return Err(Error::IncompatibleUnits(*self, target));
+        }
+        if *self == target {
+            return Ok(value);
+        }
+        let (scale, offset) = self.base_factors();
+        let base = value * scale + offset;
+        let (scale, offset) = target.base_factors();
+        Ok((base - offset) / scale)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized = s.trim().to_lowercase();
+        UNITS
+            .iter()
+            .find(|unit| {
+                unit.symbol().to_lowercase() == normalized
+                    || unit.aliases().contains(&normalized.as_str())
+            })
+            .copied()
+            .ok_or_else(|| Error::UnknownUnit(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Unit {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Unit> for String {
+    fn from(unit: Unit) -> Self {
+        unit.symbol().to_string()
+    }
+}
+
+impl core::fmt::Display for Unit {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{}", self.symbol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn parses_aliases_to_one_unit() {
+        for alias in ["C", "°C", "celsius", "degC", "℃"] {
+            assert_eq!(alias.parse::<Unit>().unwrap(), Unit::Celsius);
+        }
+        assert_eq!(
+            "uS/cm".parse::<Unit>().unwrap(),
+            Unit::MicrosiemensPerCentimeter
+        );
+        assert_eq!("m3/h".parse::<Unit>().unwrap(), Unit::CubicMetersPerHour);
+        assert_eq!(
+            "furlong".parse::<Unit>(),
+            Err(Error::UnknownUnit(String::from("furlong")))
+        );
+    }
+
+    #[test]
+    fn keeps_plain_percentages_apart_from_humidity() {
+        assert_eq!("%".parse::<Unit>().unwrap(), Unit::Percent);
+        assert_eq!("%RH".parse::<Unit>().unwrap(), Unit::RelativeHumidity);
+        assert_eq!(
+            Unit::Percent.convert(40.0, Unit::RelativeHumidity),
+            Err(Error::IncompatibleUnits(
+                Unit::Percent,
+                Unit::RelativeHumidity
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_common_greenhouse_units() {
+        assert_eq!("ppm".parse::<Unit>().unwrap(), Unit::PartsPerMillion);
+        assert_eq!("W".parse::<Unit>().unwrap(), Unit::Watt);
+        assert_eq!("V".parse::<Unit>().unwrap(), Unit::Volt);
+        assert_eq!("A".parse::<Unit>().unwrap(), Unit::Ampere);
+        assert_eq!("mA".parse::<Unit>().unwrap(), Unit::Milliampere);
+    }
+
+    #[test]
+    fn every_symbol_round_trips() {
+        for unit in UNITS {
+            assert_eq!(unit.symbol().parse::<Unit>().unwrap(), *unit);
+        }
+    }
+
+    #[test]
+    fn converts_temperature() {
+        assert_close(
+            Unit::Celsius.convert(100.0, Unit::Fahrenheit).unwrap(),
+            212.0,
+        );
+        assert_close(
+            Unit::Fahrenheit.convert(32.0, Unit::Kelvin).unwrap(),
+            273.15,
+        );
+        assert_close(Unit::Kelvin.convert(0.0, Unit::Celsius).unwrap(), -273.15);
+    }
+
+    #[test]
+    fn converts_linear_quantities() {
+        assert_close(Unit::Bar.convert(1.0, Unit::Kilopascal).unwrap(), 100.0);
+        assert_close(
+            Unit::MillisiemensPerCentimeter
+                .convert(1.5, Unit::MicrosiemensPerCentimeter)
+                .unwrap(),
+            1_500.0,
+        );
+        assert_close(
+            Unit::LitersPerHour
+                .convert(120.0, Unit::LitersPerMinute)
+                .unwrap(),
+            2.0,
+        );
+        assert_close(
+            Unit::KilowattHour.convert(1.0, Unit::Megajoule).unwrap(),
+            3.6,
+        );
+        assert_close(
+            Unit::PartsPerBillion
+                .convert(400_000.0, Unit::PartsPerMillion)
+                .unwrap(),
+            400.0,
+        );
+        assert_close(Unit::Milliampere.convert(20.0, Unit::Ampere).unwrap(), 0.02);
+    }
+
+    #[test]
+    fn rejects_incompatible_units() {
+        assert_eq!(
+            Unit::Celsius.convert(1.0, Unit::Lux),
+            Err(Error::IncompatibleUnits(Unit::Celsius, Unit::Lux))
+        );
+    }
+
+    #[test]
+    fn serializes_as_symbol() {
+        assert_eq!(serde_json::to_string(&Unit::Celsius).unwrap(), r#""°C""#);
+        assert_eq!(
+            serde_json::from_str::<Unit>(r#""celsius""#).unwrap(),
+            Unit::Celsius
+        );
+    }
+}
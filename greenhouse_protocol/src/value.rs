@@ -0,0 +1,234 @@
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::units::{self, Unit};
+
+/// A value produced or consumed by a smart device. Shared by the device
+/// protocol and the timeseries API so both speak the same shape.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Type {
+    Number(f64),
+    Boolean(bool),
+    /// Free text state, e.g. "OPEN" or "CLOSED".
+    Text(String),
+    /// One of the options declared by `TypeOption::Enum`.
+    Enum(String),
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
+    Array(Vec<Type>),
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
+    Object(BTreeMap<String, Type>),
+    Measurement(Measurement),
+    /// A value carrying the time it was measured at on the device.
+    Timestamped(Timestamped),
+    Stream,
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Measurement {
+    pub value: f64,
+    pub unit: String,
+}
+
+impl Type {
+    /// Rewrites the unit of every nested measurement to its canonical symbol,
+    /// so "C", "°C" and "celsius" end up as the same unit. Measurements in a
+    /// unit missing from the registry are replaced by `Type::None`, so one bad
+    /// channel doesn't hide the others, and their errors are returned.
+    pub fn normalize_units(&mut self) -> Vec<units::Error> {
+        let mut errors = Vec::new();
+        self.normalize_units_into(&mut errors);
+        errors
+    }
+
+    fn normalize_units_into(&mut self, errors: &mut Vec<units::Error>) {
+        match self {
+            Type::Measurement(measurement) => match measurement.unit() {
+                Ok(unit) => measurement.unit = unit.symbol().to_string(),
+                Err(e) => {
+                    errors.push(e);
+                    *self = Type::None;
+                }
+            },
+            Type::Array(values) => values
+                .iter_mut()
+                .for_each(|value| value.normalize_units_into(errors)),
+            Type::Object(values) => values
+                .values_mut()
+                .for_each(|value| value.normalize_units_into(errors)),
+            Type::Timestamped(timestamped) => timestamped.value.normalize_units_into(errors),
+            _ => {}
+        }
+    }
+}
+
+impl Measurement {
+    pub fn unit(&self) -> units::Result<Unit> {
+        self.unit.parse()
+    }
+
+    pub fn convert_to(&self, target: Unit) -> units::Result<Measurement> {
+        Ok(Measurement {
+            value: self.unit()?.convert(self.value, target)?,
+            unit: target.symbol().to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Timestamped {
+    pub timestamp: DateTime<Utc>,
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
+    pub value: Box<Type>,
+}
+
+/// Declares which `Type` a device reads or accepts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TypeOption {
+    Number,
+    Boolean,
+    Text,
+    Enum(Vec<String>),
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
+    Array(Box<TypeOption>),
+    Object,
+    Measurement,
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
+    Timestamped(Box<TypeOption>),
+    Stream,
+    Unknown,
+}
+
+impl TypeOption {
+    /// Checks whether `value` has the shape declared by this option.
+    /// `Unknown` accepts every value.
+    pub fn accepts(&self, value: &Type) -> bool {
+        match (self, value) {
+            (TypeOption::Unknown, _) => true,
+            (TypeOption::Number, Type::Number(_)) => true,
+            (TypeOption::Boolean, Type::Boolean(_)) => true,
+            (TypeOption::Text, Type::Text(_)) => true,
+            (TypeOption::Enum(options), Type::Enum(option)) => options.contains(option),
+            (TypeOption::Array(option), Type::Array(values)) => {
+                values.iter().all(|value| option.accepts(value))
+            }
+            (TypeOption::Object, Type::Object(_)) => true,
+            (TypeOption::Measurement, Type::Measurement(_)) => true,
+            (TypeOption::Timestamped(option), Type::Timestamped(timestamped)) => {
+                option.accepts(&timestamped.value)
+            }
+            (TypeOption::Stream, Type::Stream) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn accepts_declared_enum_option() {
+        let option = TypeOption::Enum(vec![String::from("OPEN"), String::from("CLOSED")]);
+
+        assert!(option.accepts(&Type::Enum(String::from("OPEN"))));
+        assert!(!option.accepts(&Type::Enum(String::from("HALF"))));
+        assert!(!option.accepts(&Type::Text(String::from("OPEN"))));
+    }
+
+    #[test]
+    fn accepts_nested_values() {
+        let option =
+            TypeOption::Timestamped(Box::new(TypeOption::Array(Box::new(TypeOption::Number))));
+        let value = Type::Timestamped(Timestamped {
+            timestamp: Utc::now(),
+            value: Box::new(Type::Array(vec![Type::Number(1.0), Type::Number(2.0)])),
+        });
+
+        assert!(option.accepts(&value));
+        assert!(
+            !TypeOption::Array(Box::new(TypeOption::Boolean))
+                .accepts(&Type::Array(vec![Type::Number(1.0)]))
+        );
+    }
+
+    #[test]
+    fn normalizes_nested_units() {
+        let mut value = Type::Object(BTreeMap::from([(
+            String::from("air"),
+            Type::Array(vec![Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("celsius"),
+            })]),
+        )]));
+        assert_eq!(value.normalize_units(), Vec::new());
+
+        let Type::Object(values) = value else {
+            panic!("expected object")
+        };
+        assert_eq!(
+            values["air"],
+            Type::Array(vec![Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("°C"),
+            })])
+        );
+    }
+
+    #[test]
+    fn drops_only_measurements_in_unknown_units() {
+        let mut value = Type::Object(BTreeMap::from([
+            (
+                String::from("air"),
+                Type::Measurement(Measurement {
+                    value: 21.5,
+                    unit: String::from("C"),
+                }),
+            ),
+            (
+                String::from("soil"),
+                Type::Measurement(Measurement {
+                    value: 3.0,
+                    unit: String::from("furlong"),
+                }),
+            ),
+        ]));
+
+        assert_eq!(
+            value.normalize_units(),
+            vec![units::Error::UnknownUnit(String::from("furlong"))]
+        );
+        let Type::Object(values) = value else {
+            panic!("expected object")
+        };
+        assert_eq!(
+            values["air"],
+            Type::Measurement(Measurement {
+                value: 21.5,
+                unit: String::from("°C"),
+            })
+        );
+        assert_eq!(values["soil"], Type::None);
+    }
+
+    #[test]
+    fn keeps_externally_tagged_encoding() {
+        let json = serde_json::to_string(&Type::Text(String::from("OPEN"))).unwrap();
+        assert_eq!(json, r#"{"Text":"OPEN"}"#);
+
+        let json = serde_json::to_string(&TypeOption::Enum(vec![String::from("OPEN")])).unwrap();
+        assert_eq!(json, r#"{"Enum":["OPEN"]}"#);
+    }
+}
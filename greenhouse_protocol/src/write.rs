@@ -0,0 +1,80 @@
+use alloc::string::String;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Type;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WriteRequestDto {
+    pub data: Type,
+    /// Retried writes carrying the same key are answered from the device's
+    /// cache instead of being applied a second time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum WriteStatus {
+    /// The value (or a corrected version of it) was applied.
+    Applied,
+    /// The device was already in the requested state.
+    Unchanged,
+    /// The value was refused, e.g. wrong type or out of range.
+    Rejected,
+    /// The device tried to apply the value but failed.
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WriteResponseDto {
+    pub status: WriteStatus,
+    /// The value the device actually applied, which may differ from the
+    /// requested one (e.g. after clamping).
+    pub applied: Option<Type>,
+    /// The state of the device after the write.
+    pub state: Option<Type>,
+    pub error: Option<String>,
+    pub idempotency_key: Option<String>,
+}
+
+impl WriteResponseDto {
+    pub fn applied(applied: Type) -> Self {
+        Self::new(WriteStatus::Applied, Some(applied), None)
+    }
+
+    pub fn unchanged(state: Type) -> Self {
+        let mut response = Self::new(WriteStatus::Unchanged, None, None);
+        response.state = Some(state);
+        response
+    }
+
+    pub fn rejected(error: impl Into<String>) -> Self {
+        Self::new(WriteStatus::Rejected, None, Some(error.into()))
+    }
+
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self::new(WriteStatus::Failed, None, Some(error.into()))
+    }
+
+    pub fn with_state(mut self, state: Type) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, WriteStatus::Applied | WriteStatus::Unchanged)
+    }
+
+    fn new(status: WriteStatus, applied: Option<Type>, error: Option<String>) -> Self {
+        Self {
+            status,
+            applied,
+            state: None,
+            error,
+            idempotency_key: None,
+        }
+    }
+}
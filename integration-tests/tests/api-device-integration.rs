@@ -1,8 +1,25 @@
-use greenhouse_core::device_service_dto::{
-    get_device::{DeviceResponseDto, DevicesResponseDto},
-    post_device::PostDeviceDtoRequest,
-    put_device::PutDeviceDtoRequest,
+use chrono::{TimeDelta, Utc};
+use greenhouse_core::{
+    device_service_dto::{
+        availability::{Availability, AvailabilityHistoryDto},
+        get_device::{AssetDto, DeviceResponseDto, DevicesResponseDto},
+        get_timeseries::{GetTimeseriesDto, Measurement, Type},
+        operations::OperationsDto,
+        post_device::PostDeviceDtoRequest,
+        put_device::PutDeviceDtoRequest,
+        readings::{IngestReadingsDto, ReadingDto},
+        write::WriteAuditsDto,
+    },
+    smart_device_dto::{
+        config::{ConfigRequestDto, TypeOption},
+        status::{DeviceStatusDto, DeviceStatusResponseDto},
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
+    },
+    smart_device_interface::{
+        config::Config, device_builder::DeviceBuilder, input_device::init_input_router, tls,
+    },
 };
+use std::{net::SocketAddr, sync::Arc};
 use test_helper::TestContext;
 mod test_helper;
 
@@ -20,6 +37,11 @@ async fn test_create_device_entry() {
         name: String::from("testDevice"),
         description: String::from("test Description"),
         scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
     };
     let response = client
         .post("http://localhost:3000/api/device")
@@ -60,6 +82,11 @@ async fn test_create_and_get_device_entry() {
         name: String::from("TestDevice2"),
         description: String::from("Second test device"),
         scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
     };
 
     let response = client
@@ -119,6 +146,11 @@ async fn test_create_and_update_device_entry() {
         name: String::from("OriginalDevice"),
         description: String::from("Original description"),
         scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
     };
 
     let response = client
@@ -144,6 +176,11 @@ async fn test_create_and_update_device_entry() {
         name: String::from("UpdatedDevice"),
         description: String::from("Updated description"),
         scraping: true,
+        scrape_interval: Some(60),
+        scrape_timeout: Some(10),
+        zone_id: None,
+        tags: None,
+        asset: None,
     };
 
     let response = client
@@ -170,6 +207,26 @@ async fn test_create_and_update_device_entry() {
     assert_eq!(updated_device.description, "Updated description");
     assert_eq!(updated_device.address, "10.0.0.2:4000");
     assert!(!updated_device.canscript);
+    assert_eq!(created_device.scrape_interval, 5);
+    assert_eq!(updated_device.scrape_interval, 60);
+    assert_eq!(updated_device.scrape_timeout, 10);
+
+    // The timeout may not outlast the interval
+    let response = client
+        .put(format!(
+            "http://localhost:3000/api/device/{}",
+            created_device.id
+        ))
+        .json(&PutDeviceDtoRequest {
+            scrape_timeout: Some(120),
+            ..put_entry
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
 
     context.stop().await;
 }
@@ -217,6 +274,11 @@ async fn test_status_for_offline_device_entry() {
         name: String::from("OfflineDevice"),
         description: String::from("Device that can't be reached"),
         scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
     };
 
     let response = client
@@ -254,6 +316,154 @@ async fn test_status_for_offline_device_entry() {
         response.text().await.unwrap()
     );
 
+    // Availability is only tracked by the scraper, which isn't running in tests
+    assert_eq!(created_device.availability, Availability::Unknown);
+    assert_eq!(created_device.last_seen, None);
+    let response = client
+        .get(format!(
+            "http://localhost:3000/api/device/{}/availability",
+            created_device.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to get availability history with error: {}",
+        response.text().await.unwrap()
+    );
+    let history: AvailabilityHistoryDto = response.json().await.unwrap();
+    assert!(history.changes.is_empty());
+
+    context.stop().await;
+}
+
+#[tokio::test]
+async fn test_delete_and_restore_device() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+
+    let client = reqwest::Client::new();
+
+    let post_entry = PostDeviceDtoRequest {
+        address: String::from("10.0.2.1:8000"),
+        can_script: true,
+        name: String::from("RetiredDevice"),
+        description: String::from("Device that gets decommissioned"),
+        scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
+    };
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&post_entry)
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to create device entry with error: {}",
+        response.text().await.unwrap()
+    );
+    let created_device: DeviceResponseDto = response.json().await.unwrap();
+    let device_url = format!("http://localhost:3000/api/device/{}", created_device.id);
+
+    let get_status = |url: String| {
+        let client = client.clone();
+        let token = token.clone();
+        async move {
+            client
+                .get(url)
+                .header("Access-Control-Allow-Credentials", "true")
+                .header("Cookie", format!("auth-token={token}"))
+                .send()
+                .await
+                .unwrap()
+                .status()
+        }
+    };
+    let deleted_devices = || {
+        let client = client.clone();
+        let token = token.clone();
+        async move {
+            client
+                .get("http://localhost:3000/api/device?deleted=true")
+                .header("Access-Control-Allow-Credentials", "true")
+                .header("Cookie", format!("auth-token={token}"))
+                .send()
+                .await
+                .unwrap()
+                .json::<DevicesResponseDto>()
+                .await
+                .unwrap()
+                .devices
+        }
+    };
+
+    // Soft delete keeps the device restorable
+    let response = client
+        .delete(&device_url)
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(
+        get_status(device_url.clone()).await,
+        reqwest::StatusCode::NOT_FOUND
+    );
+    let deleted = deleted_devices().await;
+    assert!(
+        deleted
+            .iter()
+            .any(|device| device.id == created_device.id && device.deleted_at.is_some())
+    );
+
+    let response = client
+        .post(format!("{device_url}/restore"))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to restore device with error: {}",
+        response.text().await.unwrap()
+    );
+    let restored: DeviceResponseDto = response.json().await.unwrap();
+    assert_eq!(restored.deleted_at, None);
+    assert!(get_status(device_url.clone()).await.is_success());
+
+    // Purging removes it for good
+    let response = client
+        .delete(format!("{device_url}?purge=true"))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(
+        get_status(device_url.clone()).await,
+        reqwest::StatusCode::NOT_FOUND
+    );
+    assert!(
+        !deleted_devices()
+            .await
+            .iter()
+            .any(|device| device.id == created_device.id)
+    );
+
     context.stop().await;
 }
 
@@ -273,6 +483,11 @@ async fn test_get_all_devices() {
             name: String::from("Device1"),
             description: String::from("First device"),
             scraping: true,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: None,
+            tags: Vec::new(),
+            asset: AssetDto::default(),
         },
         PostDeviceDtoRequest {
             address: String::from("10.0.1.2:8000"),
@@ -280,6 +495,11 @@ async fn test_get_all_devices() {
             name: String::from("Device2"),
             description: String::from("Second device"),
             scraping: true,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: None,
+            tags: Vec::new(),
+            asset: AssetDto::default(),
         },
     ];
 
@@ -339,6 +559,11 @@ async fn test_get_device_config() {
         name: String::from("ConfigTestDevice"),
         description: String::from("Device for config testing"),
         scraping: true,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
     };
 
     let response = client
@@ -379,3 +604,255 @@ async fn test_get_device_config() {
 
     context.stop().await;
 }
+
+#[tokio::test]
+async fn test_ingest_and_query_readings() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+
+    let client = reqwest::Client::new();
+
+    let post_entry = PostDeviceDtoRequest {
+        address: String::from("192.168.999.999:8080"),
+        can_script: false,
+        name: String::from("ReadingsTestDevice"),
+        description: String::from("Device for readings testing"),
+        scraping: false,
+        scrape_interval: None,
+        scrape_timeout: None,
+        zone_id: None,
+        tags: Vec::new(),
+        asset: AssetDto::default(),
+    };
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&post_entry)
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let device: DeviceResponseDto = response.json().await.unwrap();
+
+    // Readings are pushed to device_service directly
+    let now = Utc::now();
+    let reading = |minutes_ago, value| ReadingDto {
+        timestamp: Some(now - TimeDelta::minutes(minutes_ago)),
+        data: Type::Object(
+            [(
+                String::from("temperature"),
+                Type::Measurement(Measurement {
+                    value,
+                    unit: String::from("°C"),
+                }),
+            )]
+            .into(),
+        ),
+    };
+    let response = client
+        .post(format!("http://localhost:3003/{}/readings", device.id))
+        .json(&IngestReadingsDto {
+            readings: vec![reading(10, 20.0), reading(5, 100.0)],
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    // A reading in an unknown unit rejects the whole batch
+    let mut unknown_unit = reading(3, 30.0);
+    unknown_unit.data = Type::Object(
+        [(
+            String::from("temperature"),
+            Type::Measurement(Measurement {
+                value: 30.0,
+                unit: String::from("furlong"),
+            }),
+        )]
+        .into(),
+    );
+    let response = client
+        .post(format!("http://localhost:3003/{}/readings", device.id))
+        .json(&IngestReadingsDto {
+            readings: vec![reading(4, 25.0), unknown_unit],
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .get(format!(
+            "http://localhost:3000/api/device/{}/options",
+            device.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let options: OperationsDto = response.json().await.unwrap();
+    assert_eq!(options.operations, vec![String::from("temperature")]);
+
+    let response = client
+        .get(format!(
+            "http://localhost:3000/api/device/{}/timeseries",
+            device.id
+        ))
+        .query(&[
+            ("start", (now - TimeDelta::hours(1)).to_rfc3339()),
+            ("end", now.to_rfc3339()),
+            ("sub_property", String::from("temperature")),
+            ("unit", String::from("°F")),
+        ])
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to query timeseries with error: {}",
+        response.text().await.unwrap()
+    );
+    let timeseries: GetTimeseriesDto = response.json().await.unwrap();
+    let values: Vec<_> = timeseries
+        .timeseries
+        .into_iter()
+        .map(|entry| entry.value)
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            Type::Measurement(Measurement {
+                value: 68.0,
+                unit: String::from("°F")
+            }),
+            Type::Measurement(Measurement {
+                value: 212.0,
+                unit: String::from("°F")
+            }),
+        ]
+    );
+
+    context.stop().await;
+}
+
+/// Input device accepting numbers, served in-process on `port`.
+fn spawn_number_device(port: u16) {
+    let dir = std::env::temp_dir().join(format!("write-test-device-{port}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("config.json");
+    let device = DeviceBuilder::<()>::new_input_device_with_config_path(
+        |data: Type, _: Arc<Config<()>>| async move { WriteResponseDto::applied(data) },
+        |config: Arc<Config<()>>| async move {
+            DeviceStatusResponseDto {
+                status: DeviceStatusDto::Online,
+                datasource_id: config.datasource_id.clone(),
+            }
+        },
+        |_: ConfigRequestDto<()>, config: Arc<Config<()>>| async move { (*config).clone() },
+        config_path.to_str().unwrap(),
+        TypeOption::Number,
+    )
+    .unwrap();
+    let router = init_input_router(device.clone());
+    tokio::spawn(async move {
+        tls::serve(&device, router, SocketAddr::from(([127, 0, 0, 1], port)))
+            .await
+            .unwrap();
+    });
+}
+
+#[tokio::test]
+async fn test_write_device() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+    spawn_number_device(3010);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&PostDeviceDtoRequest {
+            address: String::from("http://localhost:3010"),
+            can_script: false,
+            name: String::from("WriteTestDevice"),
+            description: String::from("Device for write testing"),
+            scraping: false,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: None,
+            tags: Vec::new(),
+            asset: AssetDto::default(),
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let device: DeviceResponseDto = response.json().await.unwrap();
+
+    let response = client
+        .post(format!(
+            "http://localhost:3000/api/device/{}/write",
+            device.id
+        ))
+        .json(&WriteRequestDto {
+            data: Type::Number(21.0),
+            idempotency_key: None,
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to write device with error: {}",
+        response.text().await.unwrap()
+    );
+    let written: WriteResponseDto = response.json().await.unwrap();
+    assert_eq!(written.status, WriteStatus::Applied);
+
+    // The device declares numbers as its input
+    let response = client
+        .post(format!(
+            "http://localhost:3000/api/device/{}/write",
+            device.id
+        ))
+        .json(&WriteRequestDto {
+            data: Type::Text(String::from("warm")),
+            idempotency_key: None,
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .get(format!(
+            "http://localhost:3000/api/device/{}/writes",
+            device.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let audits: WriteAuditsDto = response.json().await.unwrap();
+    // Rejected writes are recorded too, newest first
+    assert_eq!(audits.writes.len(), 2);
+    assert_eq!(audits.writes[0].data, Type::Text(String::from("warm")));
+    assert_eq!(audits.writes[0].status, WriteStatus::Rejected);
+    assert_eq!(
+        audits.writes[1].actor.as_deref(),
+        Some(test_helper::TEST_USERNAME)
+    );
+    assert_eq!(audits.writes[1].data, Type::Number(21.0));
+
+    context.stop().await;
+}
@@ -0,0 +1,169 @@
+use greenhouse_core::{
+    device_service_dto::{
+        get_device::{AssetDto, DeviceResponseDto},
+        post_device::PostDeviceDtoRequest,
+        rule::{
+            ConditionValue, HoldDto, Operator, ReadingCondition, RuleAction, RuleCondition,
+            RuleEvaluationsDto, RuleRequestDto, RuleResponseDto, RulesResponseDto,
+        },
+    },
+    smart_device_dto::Type,
+};
+use test_helper::TestContext;
+use uuid::Uuid;
+mod test_helper;
+
+fn moisture_rule(sensor_id: Uuid, valve_id: Uuid) -> RuleRequestDto {
+    RuleRequestDto {
+        name: String::from("Water when dry"),
+        enabled: true,
+        condition: RuleCondition::All {
+            conditions: vec![RuleCondition::Reading(ReadingCondition {
+                device_id: sensor_id,
+                channel: String::from("moisture"),
+                operator: Operator::Lt,
+                value: ConditionValue::Number(30.0),
+                for_seconds: 600,
+            })],
+        },
+        actions: vec![RuleAction::Write {
+            device_id: valve_id,
+            data: Type::Boolean(true),
+            hold: Some(HoldDto {
+                seconds: 120,
+                revert_to: Type::Boolean(false),
+            }),
+        }],
+        cooldown_seconds: 900,
+    }
+}
+
+async fn post_rule(
+    client: &reqwest::Client,
+    token: &str,
+    rule: &RuleRequestDto,
+) -> reqwest::Response {
+    client
+        .post("http://localhost:3000/api/rule")
+        .json(rule)
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rules() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&PostDeviceDtoRequest {
+            address: String::from("192.168.999.999:8080"),
+            can_script: false,
+            name: String::from("RuleTestDevice"),
+            description: String::from("Device for rule testing"),
+            scraping: false,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: None,
+            tags: Vec::new(),
+            asset: AssetDto::default(),
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let device: DeviceResponseDto = response.json().await.unwrap();
+    let device_id: Uuid = device.id.parse().unwrap();
+
+    // Unknown devices are rejected
+    let response = post_rule(&client, &token, &moisture_rule(Uuid::new_v4(), device_id)).await;
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Texts can't be ordered and a rule needs actions
+    let mut invalid = moisture_rule(device_id, device_id);
+    invalid.condition = RuleCondition::Reading(ReadingCondition {
+        device_id,
+        channel: String::new(),
+        operator: Operator::Gt,
+        value: ConditionValue::Text(String::from("wet")),
+        for_seconds: 0,
+    });
+    invalid.actions.clear();
+    let response = post_rule(&client, &token, &invalid).await;
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = post_rule(&client, &token, &moisture_rule(device_id, device_id)).await;
+    assert!(
+        response.status().is_success(),
+        "Failed to create rule with error: {}",
+        response.text().await.unwrap()
+    );
+    let rule: RuleResponseDto = response.json().await.unwrap();
+    assert!(rule.enabled);
+    assert!(rule.last_fired_at.is_none());
+    assert_eq!(rule.cooldown_seconds, 900);
+    assert_eq!(
+        rule.condition,
+        moisture_rule(device_id, device_id).condition
+    );
+
+    let disabled: RuleResponseDto = client
+        .post(format!(
+            "http://localhost:3000/api/rule/{}/disable",
+            rule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!disabled.enabled);
+
+    let rules: RulesResponseDto = client
+        .get("http://localhost:3000/api/rule")
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(rules.rules.iter().any(|r| r.id == rule.id && !r.enabled));
+
+    let evaluations: RuleEvaluationsDto = client
+        .get(format!(
+            "http://localhost:3000/api/rule/{}/evaluations?limit=10",
+            rule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(evaluations.evaluations.is_empty());
+
+    let response = client
+        .delete(format!("http://localhost:3000/api/rule/{}", rule.id))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    context.stop().await;
+}
@@ -0,0 +1,212 @@
+use greenhouse_core::{
+    device_service_dto::{
+        get_device::{AssetDto, DeviceResponseDto},
+        post_device::PostDeviceDtoRequest,
+        schedule::{
+            MissedRunPolicy, NextRunsDto, ScheduleExecutionsDto, ScheduleRequestDto,
+            ScheduleResponseDto, ScheduleTrigger, SchedulesResponseDto,
+        },
+    },
+    smart_device_dto::Type,
+};
+use test_helper::TestContext;
+use uuid::Uuid;
+mod test_helper;
+
+async fn post_schedule(
+    client: &reqwest::Client,
+    token: &str,
+    device_id: Uuid,
+    trigger: ScheduleTrigger,
+) -> reqwest::Response {
+    client
+        .post("http://localhost:3000/api/schedule")
+        .json(&ScheduleRequestDto {
+            name: String::from("Lights on"),
+            device_id,
+            trigger,
+            data: Type::Boolean(true),
+            enabled: true,
+            missed_run_policy: MissedRunPolicy::RunOnce,
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_schedules() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&PostDeviceDtoRequest {
+            address: String::from("192.168.999.999:8080"),
+            can_script: false,
+            name: String::from("ScheduleTestDevice"),
+            description: String::from("Device for schedule testing"),
+            scraping: false,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: None,
+            tags: Vec::new(),
+            asset: AssetDto::default(),
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let device: DeviceResponseDto = response.json().await.unwrap();
+    let device_id: Uuid = device.id.parse().unwrap();
+
+    let response = post_schedule(
+        &client,
+        &token,
+        device_id,
+        ScheduleTrigger::Cron {
+            expression: String::from("not a cron expression"),
+        },
+    )
+    .await;
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = post_schedule(
+        &client,
+        &token,
+        Uuid::new_v4(),
+        ScheduleTrigger::Cron {
+            expression: String::from("0 6 * * *"),
+        },
+    )
+    .await;
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = post_schedule(
+        &client,
+        &token,
+        device_id,
+        ScheduleTrigger::Cron {
+            expression: String::from("0 6 * * *"),
+        },
+    )
+    .await;
+    assert!(
+        response.status().is_success(),
+        "Failed to create schedule with error: {}",
+        response.text().await.unwrap()
+    );
+    let schedule: ScheduleResponseDto = response.json().await.unwrap();
+    assert!(schedule.enabled);
+    assert!(schedule.last_run_at.is_none());
+    let next_run_at = schedule.next_run_at.expect("enabled schedule runs next");
+
+    let next: NextRunsDto = client
+        .get(format!(
+            "http://localhost:3000/api/schedule/{}/next?count=3",
+            schedule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(next.runs.len(), 3);
+    assert_eq!(next.runs[0], next_run_at);
+    assert_eq!((next.runs[1] - next.runs[0]).num_hours(), 24);
+
+    let disabled: ScheduleResponseDto = client
+        .post(format!(
+            "http://localhost:3000/api/schedule/{}/disable",
+            schedule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!disabled.enabled);
+    assert!(disabled.next_run_at.is_none());
+
+    let response = client
+        .put(format!(
+            "http://localhost:3000/api/schedule/{}",
+            schedule.id
+        ))
+        .json(&ScheduleRequestDto {
+            name: String::from("Shades down"),
+            device_id,
+            trigger: ScheduleTrigger::Sunset { offset_minutes: 30 },
+            data: Type::Boolean(false),
+            enabled: true,
+            missed_run_policy: MissedRunPolicy::Skip,
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to update schedule with error: {}",
+        response.text().await.unwrap()
+    );
+    let updated: ScheduleResponseDto = response.json().await.unwrap();
+    assert_eq!(
+        updated.trigger,
+        ScheduleTrigger::Sunset { offset_minutes: 30 }
+    );
+    assert!(updated.next_run_at.is_some());
+
+    let schedules: SchedulesResponseDto = client
+        .get("http://localhost:3000/api/schedule")
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(schedules.schedules.iter().any(|s| s.id == schedule.id));
+
+    let executions: ScheduleExecutionsDto = client
+        .get(format!(
+            "http://localhost:3000/api/schedule/{}/executions",
+            schedule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(executions.executions.is_empty());
+
+    let response = client
+        .delete(format!(
+            "http://localhost:3000/api/schedule/{}",
+            schedule.id
+        ))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    context.stop().await;
+}
@@ -0,0 +1,110 @@
+use chrono::Utc;
+use greenhouse_core::{
+    scripting_service_dto::token::{ScriptingKeyDto, ScriptingKeyRequestDto, TokenDto},
+    smart_device_dto::logs::{LogBatchDto, LogLevel, LogRecordDto},
+};
+use test_helper::TestContext;
+mod test_helper;
+
+#[tokio::test]
+async fn test_revoked_scripting_key_is_refused() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let client = reqwest::Client::new();
+
+    // Generate key
+    let response = client
+        .post("http://localhost:3004/token")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success(), "Failed to generate key");
+    let key: TokenDto = response.json().await.unwrap();
+
+    let check = |key: &TokenDto| {
+        client
+            .post("http://localhost:3004/token/check")
+            .json(key)
+            .send()
+    };
+    let response = check(&key).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // Revoke key
+    let response = client
+        .delete("http://localhost:3004/token")
+        .json(&key)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success(), "Failed to revoke key");
+
+    let response = check(&key).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = check(&TokenDto {
+        token: uuid::Uuid::new_v4().to_string(),
+    })
+    .await
+    .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    context.stop().await;
+}
+
+#[tokio::test]
+async fn test_logs_are_attributed_to_the_datasource_of_the_key() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let client = reqwest::Client::new();
+    let generate = |datasource_id: Option<&str>| {
+        let request = client.post("http://localhost:3004/token");
+        match datasource_id {
+            Some(datasource_id) => request.json(&ScriptingKeyRequestDto {
+                datasource_id: String::from(datasource_id),
+            }),
+            None => request,
+        }
+        .send()
+    };
+    let ship = |key: &TokenDto| {
+        client
+            .post("http://localhost:3100/logs")
+            .json(&LogBatchDto {
+                datasource_id: uuid::Uuid::new_v4().to_string(),
+                records: vec![LogRecordDto {
+                    timestamp: Utc::now(),
+                    level: LogLevel::Info,
+                    target: String::from("test"),
+                    message: String::from("hello"),
+                }],
+            })
+            .header("Cookie", format!("auth-token={}", key.token))
+            .send()
+    };
+
+    // Keys report the datasource they were issued to
+    let device_id = uuid::Uuid::new_v4().to_string();
+    let bound: TokenDto = generate(Some(&device_id))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let response = client
+        .post("http://localhost:3004/token/check")
+        .json(&bound)
+        .send()
+        .await
+        .unwrap();
+    let checked: ScriptingKeyDto = response.json().await.unwrap();
+    assert_eq!(checked.datasource_id, Some(device_id));
+    let response = ship(&bound).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // Logs can't be attributed to keys without a datasource
+    let unbound: TokenDto = generate(None).await.unwrap().json().await.unwrap();
+    let response = ship(&unbound).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    context.stop().await;
+}
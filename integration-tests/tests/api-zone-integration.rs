@@ -0,0 +1,182 @@
+use chrono::NaiveDate;
+use greenhouse_core::device_service_dto::{
+    get_device::{AssetDto, DeviceResponseDto, DevicesResponseDto},
+    post_device::PostDeviceDtoRequest,
+    zone::{ZoneKind, ZoneRequestDto, ZoneResponseDto, ZonesResponseDto},
+};
+use test_helper::TestContext;
+use uuid::Uuid;
+mod test_helper;
+
+async fn create_zone(
+    client: &reqwest::Client,
+    token: &str,
+    name: &str,
+    kind: ZoneKind,
+    parent_id: Option<Uuid>,
+) -> reqwest::Response {
+    client
+        .post("http://localhost:3000/api/zone")
+        .json(&ZoneRequestDto {
+            name: String::from(name),
+            kind,
+            parent_id,
+            description: String::new(),
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_zones_and_tags() {
+    let mut context = TestContext::new();
+    context.start_all_services().await;
+    let token = test_helper::admin_login().await;
+    let client = reqwest::Client::new();
+
+    let greenhouse: ZoneResponseDto =
+        create_zone(&client, &token, "House 1", ZoneKind::Greenhouse, None)
+            .await
+            .json()
+            .await
+            .unwrap();
+    let compartment: ZoneResponseDto = create_zone(
+        &client,
+        &token,
+        "Compartment A",
+        ZoneKind::Compartment,
+        Some(greenhouse.id),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    let bench: ZoneResponseDto = create_zone(
+        &client,
+        &token,
+        "Bench 3",
+        ZoneKind::Bench,
+        Some(compartment.id),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+
+    // A bench can't lie directly in a greenhouse
+    let response = create_zone(
+        &client,
+        &token,
+        "Bench 4",
+        ZoneKind::Bench,
+        Some(greenhouse.id),
+    )
+    .await;
+    assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .get("http://localhost:3000/api/zone")
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let zones: ZonesResponseDto = response.json().await.unwrap();
+    assert_eq!(zones.zones.len(), 3);
+
+    let asset = AssetDto {
+        location: Some(String::from("North wall")),
+        installed_on: NaiveDate::from_ymd_opt(2025, 3, 1),
+        notes: Some(String::from("Replaced probe in May")),
+    };
+    let response = client
+        .post("http://localhost:3000/api/device")
+        .json(&PostDeviceDtoRequest {
+            address: String::from("192.168.999.999:8080"),
+            can_script: false,
+            name: String::from("ZoneTestDevice"),
+            description: String::from("Device for zone testing"),
+            scraping: false,
+            scrape_interval: None,
+            scrape_timeout: None,
+            zone_id: Some(bench.id),
+            tags: vec![String::from(" tomatoes "), String::from("tomatoes")],
+            asset: asset.clone(),
+        })
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "Failed to create device entry with error: {}",
+        response.text().await.unwrap()
+    );
+    let device: DeviceResponseDto = response.json().await.unwrap();
+    assert_eq!(device.zone_id, Some(bench.id));
+    assert_eq!(device.tags, vec![String::from("tomatoes")]);
+    assert_eq!(device.asset, asset);
+
+    // Filtering by the greenhouse includes the devices on its benches
+    for query in [
+        [("zone", greenhouse.id.to_string())],
+        [("tag", String::from("tomatoes"))],
+    ] {
+        let response = client
+            .get("http://localhost:3000/api/device")
+            .query(&query)
+            .header("Access-Control-Allow-Credentials", "true")
+            .header("Cookie", format!("auth-token={token}"))
+            .send()
+            .await
+            .unwrap();
+        let devices: DevicesResponseDto = response.json().await.unwrap();
+        assert_eq!(devices.devices.len(), 1);
+        assert_eq!(devices.devices[0].id, device.id);
+    }
+    let response = client
+        .get("http://localhost:3000/api/device")
+        .query(&[("tag", "cucumbers")])
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let devices: DevicesResponseDto = response.json().await.unwrap();
+    assert!(devices.devices.is_empty());
+
+    // Zones holding other zones can't be deleted
+    let response = client
+        .delete(format!("http://localhost:3000/api/zone/{}", compartment.id))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let response = client
+        .delete(format!("http://localhost:3000/api/zone/{}", bench.id))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = client
+        .get(format!("http://localhost:3000/api/device/{}", device.id))
+        .header("Access-Control-Allow-Credentials", "true")
+        .header("Cookie", format!("auth-token={token}"))
+        .send()
+        .await
+        .unwrap();
+    let device: DeviceResponseDto = response.json().await.unwrap();
+    assert_eq!(device.zone_id, None);
+
+    context.stop().await;
+}
@@ -5,7 +5,9 @@ use testcontainers_modules::postgres::{self, Postgres};
 use testcontainers_modules::testcontainers::runners::AsyncRunner;
 use tokio::task::JoinHandle;
 
+#[allow(dead_code)]
 pub const TEST_USERNAME: &str = "testuser";
+#[allow(dead_code)]
 pub const TEST_PASSWORD: &str = "testpassword";
 pub const AUTH_SECRET: &str = "testpassword";
 
@@ -221,12 +223,29 @@ async fn start_device_service(
 ) -> tokio::task::JoinHandle<Result<(), std::io::Error>> {
     let device_service_config = device_service::Config {
         database_url: db_url,
+        data_storage_service: Some(String::from("http://localhost:3002")),
         service_port: 3003,
         sentry_url: String::new(),
         scripting_service: String::from("http://localhost:3004"),
         scripting_api: String::from("http://localhost:3100"),
         environment: String::from("test"),
-        prometheus_url: String::from("http://localhost:9090"),
+        prometheus_url: None,
+        prometheus_sink: false,
+        reading_retention_days: 365,
+        reading_raw_days: 7,
+        reading_downsample_secs: 300,
+        scrape_concurrency: 32,
+        offline_after_failures: 3,
+        latitude: Some(52.52),
+        longitude: Some(13.40),
+        timezone: String::from("UTC"),
+        rule_evaluation_secs: 30,
+        rule_log_retention_days: 30,
+        notify_allowlist: vec![String::from("127.0.0.1")],
+        ca_dir: std::env::temp_dir()
+            .join("greenhouse-device-ca")
+            .to_string_lossy()
+            .into_owned(),
     };
 
     let device_pool = device_service::Pool::builder()
@@ -328,12 +347,14 @@ async fn start_scripting_api() -> tokio::task::JoinHandle<Result<(), std::io::Er
     tokio::spawn(async move { axum::serve(listener, api_app).await })
 }
 
+#[allow(dead_code)]
 pub async fn admin_login() -> String {
     register_admin().await;
 
     api_login().await
 }
 
+#[allow(dead_code)]
 pub async fn register_admin() {
     let client = reqwest::Client::new();
     let response = client
@@ -349,6 +370,7 @@ pub async fn register_admin() {
     assert!(response.status().is_success(), "Failed to register admin");
 }
 
+#[allow(dead_code)]
 pub async fn api_login() -> String {
     let client = reqwest::Client::new();
     let response = client
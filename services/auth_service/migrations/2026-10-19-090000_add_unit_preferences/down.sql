@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE preferences DROP COLUMN unit_preferences;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE preferences ADD COLUMN unit_preferences JSONB NOT NULL DEFAULT '{}';
@@ -25,6 +25,7 @@ pub(crate) struct Preferences {
     pub(crate) dashboard_preferences: serde_json::Value,
     pub(crate) alert_preferences: serde_json::Value,
     pub(crate) user_id: Uuid,
+    pub(crate) unit_preferences: serde_json::Value,
 }
 
 impl Preferences {
@@ -32,12 +33,14 @@ impl Preferences {
         user_id: Uuid,
         dashboard_preferences: serde_json::Value,
         alert_preferences: serde_json::Value,
+        unit_preferences: serde_json::Value,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             dashboard_preferences,
             alert_preferences,
             user_id,
+            unit_preferences,
         }
     }
 }
@@ -6,6 +6,7 @@ diesel::table! {
         dashboard_preferences -> Jsonb,
         alert_preferences -> Jsonb,
         user_id -> Uuid,
+        unit_preferences -> Jsonb,
     }
 }
 
@@ -1,5 +1,7 @@
 use super::{Error, HttpResult};
-use crate::database::schema::preferences::{alert_preferences, dashboard_preferences, user_id};
+use crate::database::schema::preferences::{
+    alert_preferences, dashboard_preferences, unit_preferences, user_id,
+};
 use crate::token;
 use crate::{
     AppState,
@@ -244,7 +246,11 @@ pub(crate) async fn check_token(
         return Err(Error::TokenInvalid.into());
     }
 
-    Ok(Json(TokenResponseDto { role: user.role }).into_response())
+    Ok(Json(TokenResponseDto {
+        role: user.role,
+        user_name: user.username,
+    })
+    .into_response())
 }
 
 #[axum::debug_handler]
@@ -275,6 +281,7 @@ pub(crate) async fn get_preferences(
     Ok(UserPreferencesResponseDto {
         dashboard_preferences: pref.dashboard_preferences,
         alert_preferences: pref.alert_preferences,
+        unit_preferences: serde_json::from_value(pref.unit_preferences).unwrap_or_default(),
     })
 }
 
@@ -301,17 +308,22 @@ pub(crate) async fn set_preferences(
         return Err(Error::TokenInvalid.into());
     }
 
+    let units = serde_json::to_value(&request.preferences.unit_preferences)
+        .map_err(|_| Error::InvalidPreferences)?;
+
     diesel::insert_into(preferences)
         .values(Preferences::new(
             user.id,
             request.preferences.dashboard_preferences.clone(),
             request.preferences.alert_preferences.clone(),
+            units.clone(),
         ))
         .on_conflict(user_id)
         .do_update()
         .set((
             dashboard_preferences.eq(request.preferences.dashboard_preferences.clone()),
             alert_preferences.eq(request.preferences.alert_preferences.clone()),
+            unit_preferences.eq(units),
         ))
         .execute(&mut conn)
         .await
@@ -323,5 +335,6 @@ pub(crate) async fn set_preferences(
     Ok(UserPreferencesResponseDto {
         dashboard_preferences: request.preferences.dashboard_preferences,
         alert_preferences: request.preferences.alert_preferences,
+        unit_preferences: request.preferences.unit_preferences,
     })
 }
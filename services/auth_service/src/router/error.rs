@@ -15,6 +15,7 @@ pub(crate) enum Error {
     PasswordIncorrect,
     OneTimeToken,
     TokenInvalid,
+    InvalidPreferences,
     #[from]
     User(database::Error),
     #[from]
@@ -45,6 +46,7 @@ impl HttpErrorMapping for Error {
             Error::PasswordIncorrect => StatusCode::UNAUTHORIZED,
             Error::OneTimeToken => StatusCode::BAD_REQUEST,
             Error::TokenInvalid => StatusCode::UNAUTHORIZED,
+            Error::InvalidPreferences => StatusCode::BAD_REQUEST,
             Error::User(e) => match e {
                 database::Error::InvalidHash => StatusCode::BAD_REQUEST,
                 database::Error::Token(e) => match e {
@@ -71,6 +73,7 @@ impl HttpErrorMapping for Error {
             Error::PasswordIncorrect => String::from("Username or password incorrect"),
             Error::OneTimeToken => String::from("One-time token error"),
             Error::TokenInvalid => String::from("Token invalid"),
+            Error::InvalidPreferences => String::from("Invalid preferences"),
             Error::User(e) => match e {
                 database::Error::InvalidHash => String::from("Invalid hash"),
                 database::Error::Token(e) => match e {
@@ -68,6 +68,9 @@ impl Alert {
         if let Some(datasource_id) = alert_query.datasource_id {
             query = query.filter(alert::datasource_id.eq(datasource_id));
         }
+        if let Some(datasource_ids) = alert_query.datasource_ids {
+            query = query.filter(alert::datasource_id.eq_any(datasource_ids));
+        }
         if let Some(severity) = alert_query.severity {
             let s: Severity = severity.into();
             query = query.filter(alert::severity.eq(s));
@@ -81,6 +84,21 @@ impl Alert {
         })
     }
 
+    /// Deletes every alert of the datasource, e.g. of a purged device.
+    pub(crate) async fn delete_by_datasource(datasource_id: Uuid, pool: &Pool) -> Result<usize> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(alert::table.filter(alert::datasource_id.eq(datasource_id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })
+    }
+
     pub(crate) async fn aggrigate(
         interval_query: IntervalQuery,
         pool: &Pool,
@@ -8,6 +8,7 @@ pub(crate) enum Error {
     Creation,
     Find,
     DatabaseConnection,
+    Deletion,
 }
 
 // region:    --- Error Boilerplate
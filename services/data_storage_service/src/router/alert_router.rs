@@ -2,13 +2,14 @@ use super::error::HttpResult;
 use axum::{
     Json, Router,
     extract::{Query, State},
+    http::StatusCode,
     routing::{get, post},
 };
 use greenhouse_core::data_storage_service_dto::alert_dto::{
     alert::{AlertDto, AlertsDto},
     get_aggrigated_alert::{AggrigatedAlertDto, AggrigatedAlertsDto},
     post_create_alert::CreateAlertDto,
-    query::{AlertQuery, IntervalQuery},
+    query::{AlertQuery, DeleteAlertsQuery, IntervalQuery},
 };
 
 use crate::{AppState, database::alert_models::Alert};
@@ -16,7 +17,7 @@ use crate::{AppState, database::alert_models::Alert};
 pub(crate) fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", post(create_alert))
-        .route("/", get(alert_subset))
+        .route("/", get(alert_subset).delete(delete_alerts))
         .route("/filter", get(filter))
         .with_state(state)
 }
@@ -53,3 +54,11 @@ async fn create_alert(
     let alert = Alert::create(alert, &pool).await?.into();
     Ok(alert)
 }
+
+async fn delete_alerts(
+    State(AppState { config: _, pool }): State<AppState>,
+    Query(query): Query<DeleteAlertsQuery>,
+) -> HttpResult<StatusCode> {
+    Alert::delete_by_datasource(query.datasource_id, &pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -37,6 +37,7 @@ impl HttpErrorMapping for Error {
                 database::Error::Creation => StatusCode::INTERNAL_SERVER_ERROR,
                 database::Error::DatabaseConnection => StatusCode::INTERNAL_SERVER_ERROR,
                 database::Error::Find => StatusCode::NOT_FOUND,
+                database::Error::Deletion => StatusCode::INTERNAL_SERVER_ERROR,
             },
         }
     }
@@ -48,6 +49,7 @@ impl HttpErrorMapping for Error {
                 database::Error::Creation => String::from("Database creation error"),
                 database::Error::DatabaseConnection => String::from("Database connection error"),
                 database::Error::Find => String::from("Database find error"),
+                database::Error::Deletion => String::from("Database deletion error"),
             },
         }
     }
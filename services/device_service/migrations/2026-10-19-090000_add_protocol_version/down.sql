@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE device DROP COLUMN capabilities;
+ALTER TABLE device DROP COLUMN protocol_version;
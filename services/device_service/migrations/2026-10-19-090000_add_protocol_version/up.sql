@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE device ADD COLUMN protocol_version INTEGER;
+ALTER TABLE device ADD COLUMN capabilities TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE device DROP COLUMN tunnel_token_hash;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE reading (
+    id BIGSERIAL PRIMARY KEY,
+    device_id UUID NOT NULL REFERENCES device (id) ON DELETE CASCADE,
+    channel VARCHAR NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    value_type VARCHAR NOT NULL,
+    number DOUBLE PRECISION,
+    text VARCHAR,
+    unit VARCHAR,
+    downsampled BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX reading_device_channel_recorded_at ON reading (device_id, channel, recorded_at);
+CREATE INDEX reading_recorded_at ON reading (recorded_at) WHERE NOT downsampled;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE device DROP COLUMN scrape_timeout;
+ALTER TABLE device DROP COLUMN scrape_interval;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE device ADD COLUMN scrape_interval INTEGER NOT NULL DEFAULT 5;
+ALTER TABLE device ADD COLUMN scrape_timeout INTEGER NOT NULL DEFAULT 4;
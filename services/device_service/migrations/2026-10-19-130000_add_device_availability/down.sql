@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE device_availability_history;
+DROP TABLE device_availability;
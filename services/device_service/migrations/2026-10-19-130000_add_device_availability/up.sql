@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE device_availability (
+    device_id UUID PRIMARY KEY REFERENCES device (id) ON DELETE CASCADE,
+    availability VARCHAR NOT NULL,
+    last_seen TIMESTAMPTZ,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    changed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE device_availability_history (
+    id BIGSERIAL PRIMARY KEY,
+    device_id UUID NOT NULL REFERENCES device (id) ON DELETE CASCADE,
+    availability VARCHAR NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL,
+    consecutive_failures INTEGER NOT NULL
+);
+
+CREATE INDEX device_availability_history_device_changed_at
+    ON device_availability_history (device_id, changed_at);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE device DROP COLUMN deleted_at;
+ALTER TABLE device DROP COLUMN scripting_token;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE device ADD COLUMN scripting_token VARCHAR;
+ALTER TABLE device ADD COLUMN deleted_at TIMESTAMPTZ;
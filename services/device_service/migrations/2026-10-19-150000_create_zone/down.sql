@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX device_tags_idx;
+DROP INDEX device_zone_id_idx;
+ALTER TABLE device DROP COLUMN notes;
+ALTER TABLE device DROP COLUMN installed_on;
+ALTER TABLE device DROP COLUMN location;
+ALTER TABLE device DROP COLUMN tags;
+ALTER TABLE device DROP COLUMN zone_id;
+DROP TABLE zone;
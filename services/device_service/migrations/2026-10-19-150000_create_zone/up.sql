@@ -0,0 +1,17 @@
+-- Your SQL goes here
+CREATE TABLE zone (
+    id UUID PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    kind VARCHAR NOT NULL,
+    parent_id UUID REFERENCES zone(id) ON DELETE RESTRICT,
+    description VARCHAR NOT NULL DEFAULT ''
+);
+CREATE INDEX zone_parent_id_idx ON zone (parent_id);
+
+ALTER TABLE device ADD COLUMN zone_id UUID REFERENCES zone(id) ON DELETE SET NULL;
+ALTER TABLE device ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE device ADD COLUMN location VARCHAR;
+ALTER TABLE device ADD COLUMN installed_on DATE;
+ALTER TABLE device ADD COLUMN notes VARCHAR;
+CREATE INDEX device_zone_id_idx ON device (zone_id);
+CREATE INDEX device_tags_idx ON device USING GIN (tags);
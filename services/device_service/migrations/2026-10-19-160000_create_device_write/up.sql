@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE device_write (
+    id BIGSERIAL PRIMARY KEY,
+    device_id UUID NOT NULL REFERENCES device (id) ON DELETE CASCADE,
+    actor VARCHAR,
+    written_at TIMESTAMPTZ NOT NULL,
+    data JSONB NOT NULL,
+    status VARCHAR NOT NULL,
+    error VARCHAR,
+    idempotency_key VARCHAR
+);
+
+CREATE INDEX device_write_device_written_at ON device_write (device_id, written_at);
@@ -0,0 +1,25 @@
+-- Your SQL goes here
+CREATE TABLE schedule (
+    id UUID PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    device_id UUID NOT NULL REFERENCES device (id) ON DELETE CASCADE,
+    trigger_kind VARCHAR NOT NULL,
+    cron_expression VARCHAR,
+    offset_minutes INTEGER NOT NULL DEFAULT 0,
+    data JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    missed_run_policy VARCHAR NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    handled_until TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE schedule_execution (
+    id BIGSERIAL PRIMARY KEY,
+    schedule_id UUID NOT NULL REFERENCES schedule (id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    executed_at TIMESTAMPTZ NOT NULL,
+    status VARCHAR NOT NULL,
+    error VARCHAR
+);
+
+CREATE INDEX schedule_execution_schedule_executed_at ON schedule_execution (schedule_id, executed_at);
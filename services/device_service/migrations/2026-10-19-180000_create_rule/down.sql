@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE rule_evaluation;
+DROP TABLE rule;
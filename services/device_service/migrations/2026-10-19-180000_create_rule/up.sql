@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE rule (
+    id UUID PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    condition JSONB NOT NULL,
+    actions JSONB NOT NULL,
+    cooldown_seconds INTEGER NOT NULL,
+    last_fired_at TIMESTAMPTZ
+);
+
+CREATE TABLE rule_evaluation (
+    id BIGSERIAL PRIMARY KEY,
+    rule_id UUID NOT NULL REFERENCES rule (id) ON DELETE CASCADE,
+    evaluated_at TIMESTAMPTZ NOT NULL,
+    outcome VARCHAR NOT NULL,
+    conditions JSONB NOT NULL,
+    actions JSONB NOT NULL
+);
+
+CREATE INDEX rule_evaluation_rule_evaluated_at ON rule_evaluation (rule_id, evaluated_at);
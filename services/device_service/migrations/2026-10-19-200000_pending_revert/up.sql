@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE pending_revert (
+    id UUID PRIMARY KEY,
+    rule_id UUID NOT NULL REFERENCES rule (id) ON DELETE CASCADE,
+    device_id UUID NOT NULL REFERENCES device (id) ON DELETE CASCADE,
+    revert_to JSONB NOT NULL,
+    due_at TIMESTAMPTZ NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    idempotency_key VARCHAR NOT NULL
+);
+
+CREATE INDEX pending_revert_due_at ON pending_revert (due_at);
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use greenhouse_core::{
+    data_storage_service_dto::alert_dto::{alert::Severity, post_create_alert::CreateAlertDto},
+    device_service_dto::availability::Availability,
+};
+
+use crate::{
+    AppState,
+    database::{
+        self,
+        availability::{AvailabilityChange, DeviceAvailability, availability_name},
+        device::Device,
+    },
+    router::service::request_create_alert,
+};
+
+/// Updates the availability of `device` after a scrape, which it `answered`
+/// unless it could not be reached or answered with an HTTP error. Changes are
+/// written to the history, and going offline or coming back raises an alert.
+pub(crate) async fn record_scrape(
+    device: &Device,
+    answered: bool,
+    state: &AppState,
+) -> database::Result<()> {
+    let previous = DeviceAvailability::find(device.id, &state.pool).await?;
+    let previous_availability = previous
+        .as_ref()
+        .map_or(Availability::Unknown, DeviceAvailability::availability);
+    let current = next(
+        previous,
+        device,
+        answered,
+        state.config.offline_after_failures,
+        Utc::now(),
+    );
+    current.flush(&state.pool).await?;
+
+    let availability = current.availability();
+    if availability == previous_availability {
+        return Ok(());
+    }
+    tracing::info!(
+        "Device {} is now {}",
+        device.id,
+        availability_name(availability)
+    );
+    AvailabilityChange::from(&current)
+        .insert(&state.pool)
+        .await?;
+    // A device seen for the first time isn't news
+    if availability == Availability::Offline || previous_availability == Availability::Offline {
+        raise_alert(device, &current, state).await;
+    }
+    Ok(())
+}
+
+fn next(
+    previous: Option<DeviceAvailability>,
+    device: &Device,
+    answered: bool,
+    offline_after_failures: u32,
+    now: DateTime<Utc>,
+) -> DeviceAvailability {
+    let mut current = previous.unwrap_or_else(|| DeviceAvailability {
+        device_id: device.id,
+        availability: String::from(availability_name(Availability::Unknown)),
+        last_seen: None,
+        consecutive_failures: 0,
+        changed_at: now,
+    });
+    let availability = if answered {
+        current.last_seen = Some(now);
+        current.consecutive_failures = 0;
+        Availability::Online
+    } else {
+        current.consecutive_failures = current.consecutive_failures.saturating_add(1);
+        if current.consecutive_failures as u32 >= offline_after_failures.max(1) {
+            Availability::Offline
+        } else {
+            current.availability()
+        }
+    };
+    if availability != current.availability() {
+        current.availability = String::from(availability_name(availability));
+        current.changed_at = now;
+    }
+    current
+}
+
+async fn raise_alert(device: &Device, current: &DeviceAvailability, state: &AppState) {
+    let Some(data_storage_service) = &state.config.data_storage_service else {
+        return;
+    };
+    let alert = match current.availability() {
+        Availability::Offline => CreateAlertDto {
+            severity: Severity::Warning,
+            identifier: String::from("device_offline"),
+            value: Some(device.name.clone()),
+            note: Some(format!(
+                "{} did not answer {} scrapes in a row",
+                device.name, current.consecutive_failures
+            )),
+            datasource_id: device.id.to_string(),
+        },
+        _ => CreateAlertDto {
+            severity: Severity::Info,
+            identifier: String::from("device_online"),
+            value: Some(device.name.clone()),
+            note: Some(format!("{} answers again", device.name)),
+            datasource_id: device.id.to_string(),
+        },
+    };
+    if let Err(e) = request_create_alert(data_storage_service, &alert).await {
+        tracing::error!(
+            "Could not raise availability alert for device {}: {:?}",
+            device.id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn device() -> Device {
+        Device::new("sensor", "", "http://sensor", false, true)
+    }
+
+    #[test]
+    fn goes_online_on_the_first_answer() {
+        let device = device();
+        let now = Utc::now();
+
+        let current = next(None, &device, true, 3, now);
+        assert_eq!(current.availability(), Availability::Online);
+        assert_eq!(current.last_seen, Some(now));
+        assert_eq!(current.changed_at, now);
+    }
+
+    #[test]
+    fn goes_offline_after_consecutive_failures() {
+        let device = device();
+        let start = Utc::now();
+        let online = next(None, &device, true, 3, start);
+
+        let mut current = online;
+        for i in 1..=2 {
+            current = next(
+                Some(current),
+                &device,
+                false,
+                3,
+                start + TimeDelta::seconds(i),
+            );
+            assert_eq!(current.availability(), Availability::Online);
+            assert_eq!(current.changed_at, start);
+        }
+        let offline_at = start + TimeDelta::seconds(3);
+        current = next(Some(current), &device, false, 3, offline_at);
+        assert_eq!(current.availability(), Availability::Offline);
+        assert_eq!(current.consecutive_failures, 3);
+        assert_eq!(current.changed_at, offline_at);
+        assert_eq!(current.last_seen, Some(start));
+    }
+
+    #[test]
+    fn resets_the_failures_once_the_device_answers() {
+        let device = device();
+        let start = Utc::now();
+        let failed = next(None, &device, false, 1, start);
+        assert_eq!(failed.availability(), Availability::Offline);
+
+        let back_at = start + TimeDelta::seconds(1);
+        let current = next(Some(failed), &device, true, 1, back_at);
+        assert_eq!(current.availability(), Availability::Online);
+        assert_eq!(current.consecutive_failures, 0);
+        assert_eq!(current.changed_at, back_at);
+    }
+
+    #[test]
+    fn stays_unknown_until_enough_failures() {
+        let device = device();
+        let now = Utc::now();
+
+        let current = next(None, &device, false, 2, now);
+        assert_eq!(current.availability(), Availability::Unknown);
+        // A threshold of 0 still needs one failure
+        let current = next(None, &device, false, 0, now);
+        assert_eq!(current.availability(), Availability::Offline);
+    }
+}
@@ -0,0 +1,175 @@
+use super::{
+    Error, Result,
+    schema::{device_availability, device_availability_history},
+};
+use crate::Pool;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use greenhouse_core::device_service_dto::{
+    availability::{Availability, AvailabilityChangeDto},
+    get_device::DeviceResponseDto,
+};
+use uuid::Uuid;
+
+/// Current availability of a device, kept apart from the device so scrapes
+/// and device updates don't overwrite each other.
+#[derive(Debug, Clone, Queryable, Selectable, AsChangeset, Insertable)]
+#[diesel(table_name = crate::database::schema::device_availability)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct DeviceAvailability {
+    pub(crate) device_id: Uuid,
+    pub(crate) availability: String,
+    pub(crate) last_seen: Option<DateTime<Utc>>,
+    pub(crate) consecutive_failures: i32,
+    /// When the availability last changed.
+    pub(crate) changed_at: DateTime<Utc>,
+}
+
+/// A row of the availability history, written on every change.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::device_availability_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct AvailabilityChange {
+    pub(crate) device_id: Uuid,
+    pub(crate) availability: String,
+    pub(crate) changed_at: DateTime<Utc>,
+    pub(crate) consecutive_failures: i32,
+}
+
+pub(crate) fn availability_name(availability: Availability) -> &'static str {
+    match availability {
+        Availability::Unknown => "unknown",
+        Availability::Online => "online",
+        Availability::Offline => "offline",
+    }
+}
+
+fn parse_availability(name: &str) -> Availability {
+    match name {
+        "online" => Availability::Online,
+        "offline" => Availability::Offline,
+        _ => Availability::Unknown,
+    }
+}
+
+impl DeviceAvailability {
+    pub(crate) fn availability(&self) -> Availability {
+        parse_availability(&self.availability)
+    }
+
+    pub(crate) async fn find(device_id: Uuid, pool: &Pool) -> Result<Option<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device_availability::table
+            .filter(device_availability::device_id.eq(device_id))
+            .select(Self::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn all(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device_availability::table
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn flush(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(device_availability::table)
+            .values(self)
+            .on_conflict(device_availability::device_id)
+            .do_update()
+            .set(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Fills the availability fields of the response for the device.
+    pub(crate) fn apply_to(&self, response: &mut DeviceResponseDto) {
+        response.availability = self.availability();
+        response.last_seen = self.last_seen;
+        response.consecutive_failures = self.consecutive_failures.max(0) as u32;
+    }
+}
+
+impl AvailabilityChange {
+    pub(crate) async fn insert(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(device_availability_history::table)
+            .values(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Changes of the device, oldest first.
+    pub(crate) async fn find_by_device(device_id: Uuid, pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device_availability_history::table
+            .filter(device_availability_history::device_id.eq(device_id))
+            .order(device_availability_history::changed_at.asc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+}
+
+impl From<&DeviceAvailability> for AvailabilityChange {
+    fn from(current: &DeviceAvailability) -> Self {
+        Self {
+            device_id: current.device_id,
+            availability: current.availability.clone(),
+            changed_at: current.changed_at,
+            consecutive_failures: current.consecutive_failures,
+        }
+    }
+}
+
+impl From<AvailabilityChange> for AvailabilityChangeDto {
+    fn from(change: AvailabilityChange) -> Self {
+        Self {
+            availability: parse_availability(&change.availability),
+            changed_at: change.changed_at,
+            consecutive_failures: change.consecutive_failures.max(0) as u32,
+        }
+    }
+}
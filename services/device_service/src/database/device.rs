@@ -1,13 +1,34 @@
 use super::{Error, Result, schema::device};
 use crate::Pool;
+use chrono::{DateTime, NaiveDate, Utc};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
-use greenhouse_core::device_service_dto::get_device::DeviceResponseDto;
+use greenhouse_core::{
+    device_service_dto::{
+        availability::Availability,
+        get_device::{AssetDto, DeviceResponseDto},
+    },
+    smart_device_dto::{
+        config::ValidationErrors,
+        encoding::Encoding,
+        version::{Capability, VersionResponseDto},
+    },
+};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Seconds between two scrapes of devices that don't set their own interval.
+const DEFAULT_SCRAPE_INTERVAL: u32 = 5;
+const DEFAULT_SCRAPE_TIMEOUT: u32 = 4;
+/// Longest allowed interval, a day.
+const MAX_SCRAPE_INTERVAL: u32 = 24 * 60 * 60;
+const MAX_TAG_LENGTH: usize = 64;
+
 #[derive(Debug, Clone, Queryable, Selectable, AsChangeset, Insertable)]
 #[diesel(table_name = crate::database::schema::device)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
+// `flush` writes the whole row, so cleared fields have to be cleared in the table too
+#[diesel(treat_none_as_null = true)]
 pub(crate) struct Device {
     pub(crate) id: Uuid,
     pub(crate) name: String,
@@ -15,6 +36,24 @@ pub(crate) struct Device {
     pub(crate) description: String,
     pub(crate) canscript: bool,
     pub(crate) scraping: bool,
+    pub(crate) protocol_version: Option<i32>,
+    pub(crate) capabilities: Vec<String>,
+    /// SHA-256 of the token the device opens its reverse connection with, if
+    /// one was issued. The token itself is only known to the device.
+    pub(crate) tunnel_token_hash: Option<String>,
+    /// Seconds between two scrapes.
+    pub(crate) scrape_interval: i32,
+    /// Seconds a scrape may take before it is abandoned.
+    pub(crate) scrape_timeout: i32,
+    /// Key the device was activated with, revoked when it is deleted.
+    pub(crate) scripting_token: Option<String>,
+    /// Set while the device is soft deleted.
+    pub(crate) deleted_at: Option<DateTime<Utc>>,
+    pub(crate) zone_id: Option<Uuid>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) location: Option<String>,
+    pub(crate) installed_on: Option<NaiveDate>,
+    pub(crate) notes: Option<String>,
 }
 
 impl Device {
@@ -32,6 +71,127 @@ impl Device {
             address: String::from(device_address),
             canscript: can_script,
             scraping,
+            protocol_version: None,
+            capabilities: Vec::new(),
+            tunnel_token_hash: None,
+            scrape_interval: DEFAULT_SCRAPE_INTERVAL as i32,
+            scrape_timeout: DEFAULT_SCRAPE_TIMEOUT as i32,
+            scripting_token: None,
+            deleted_at: None,
+            zone_id: None,
+            tags: Vec::new(),
+            location: None,
+            installed_on: None,
+            notes: None,
+        }
+    }
+
+    /// Sets the scrape cadence, keeping the current values for unset ones. The
+    /// timeout may not outlast the interval.
+    pub(crate) fn set_scrape_schedule(
+        &mut self,
+        interval: Option<u32>,
+        timeout: Option<u32>,
+    ) -> std::result::Result<(), ValidationErrors> {
+        let interval = interval.unwrap_or(self.scrape_interval as u32);
+        let timeout = timeout.unwrap_or(self.scrape_timeout as u32);
+        let mut errors = ValidationErrors::new();
+        if !(1..=MAX_SCRAPE_INTERVAL).contains(&interval) {
+            errors.add(
+                "scrape_interval",
+                format!("must be between 1 and {MAX_SCRAPE_INTERVAL} seconds"),
+            );
+        }
+        if timeout < 1 {
+            errors.add("scrape_timeout", "must be at least 1 second");
+        } else if timeout > interval {
+            errors.add("scrape_timeout", "must not exceed scrape_interval");
+        }
+        errors.into_result(())?;
+        self.scrape_interval = interval as i32;
+        self.scrape_timeout = timeout as i32;
+        Ok(())
+    }
+
+    /// Replaces the tags. They are trimmed and deduplicated, empty and overlong
+    /// tags are rejected.
+    pub(crate) fn set_tags(
+        &mut self,
+        tags: &[String],
+    ) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags.iter().map(|tag| tag.trim()) {
+            if tag.is_empty() {
+                errors.add("tags", "must not be empty");
+            } else if tag.chars().count() > MAX_TAG_LENGTH {
+                errors.add(
+                    "tags",
+                    format!("{tag} is longer than {MAX_TAG_LENGTH} characters"),
+                );
+            } else if !normalized.iter().any(|known| known == tag) {
+                normalized.push(String::from(tag));
+            }
+        }
+        errors.into_result(())?;
+        self.tags = normalized;
+        Ok(())
+    }
+
+    /// Replaces the asset metadata, blank texts are dropped.
+    pub(crate) fn set_asset(&mut self, asset: &AssetDto) {
+        let text = |text: &Option<String>| {
+            text.as_deref()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(String::from)
+        };
+        self.location = text(&asset.location);
+        self.installed_on = asset.installed_on;
+        self.notes = text(&asset.notes);
+    }
+
+    pub(crate) fn scrape_interval(&self) -> Duration {
+        Duration::from_secs(self.scrape_interval.max(1) as u64)
+    }
+
+    pub(crate) fn scrape_timeout(&self) -> Duration {
+        Duration::from_secs(self.scrape_timeout.max(1) as u64)
+    }
+
+    /// Stores the outcome of the `/version` handshake.
+    pub(crate) fn set_version(&mut self, version: &VersionResponseDto) {
+        self.protocol_version = Some(version.protocol_version as i32);
+        self.capabilities = version
+            .capabilities
+            .iter()
+            .filter_map(|capability| match serde_json::to_value(capability) {
+                Ok(serde_json::Value::String(name)) => Some(name),
+                _ => None,
+            })
+            .collect();
+    }
+
+    pub(crate) fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::Value::String(name.clone()))
+                    .unwrap_or(Capability::Unknown)
+            })
+            .collect()
+    }
+
+    pub(crate) fn supports(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    /// Encoding preferred for requests to the device, CBOR if it speaks it.
+    pub(crate) fn encoding(&self) -> Encoding {
+        if self.supports(Capability::Cbor) {
+            Encoding::Cbor
+        } else {
+            Encoding::Json
         }
     }
 
@@ -42,6 +202,7 @@ impl Device {
         })?;
         device::table
             .filter(device::id.eq(id))
+            .filter(device::deleted_at.is_null())
             .first(&mut conn)
             .await
             .map_err(|e| {
@@ -50,12 +211,63 @@ impl Device {
             })
     }
 
-    pub(crate) async fn all(pool: &Pool) -> Result<Vec<Self>> {
+    /// Like `find_by_id`, but also finds soft deleted devices.
+    pub(crate) async fn find_any_by_id(id: Uuid, pool: &Pool) -> Result<Self> {
         let mut conn = pool.get().await.map_err(|e| {
             sentry::capture_error(&e);
             Error::DatabaseConnection
         })?;
-        device::table.get_results(&mut conn).await.map_err(|e| {
+        device::table
+            .filter(device::id.eq(id))
+            .first(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn find_by_tunnel_token_hash(token_hash: &str, pool: &Pool) -> Result<Self> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device::table
+            .filter(device::tunnel_token_hash.eq(token_hash))
+            .filter(device::deleted_at.is_null())
+            .first(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Soft deleted or active devices, optionally only those in one of `zones`
+    /// or carrying `tag`.
+    pub(crate) async fn filtered(
+        deleted: bool,
+        zones: Option<&[Uuid]>,
+        tag: Option<&str>,
+        pool: &Pool,
+    ) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        let mut query = device::table.into_boxed();
+        query = if deleted {
+            query.filter(device::deleted_at.is_not_null())
+        } else {
+            query.filter(device::deleted_at.is_null())
+        };
+        if let Some(zones) = zones {
+            query = query.filter(device::zone_id.eq_any(zones.to_vec()));
+        }
+        if let Some(tag) = tag {
+            query = query.filter(device::tags.contains(vec![tag.trim().to_string()]));
+        }
+        query.get_results(&mut conn).await.map_err(|e| {
             sentry::capture_error(&e);
             Error::Find
         })
@@ -82,6 +294,22 @@ impl Device {
         Ok(())
     }
 
+    /// Deletes the device for good, its readings and availability with it.
+    pub(crate) async fn delete(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(device::table.filter(device::id.eq(self.id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })?;
+        Ok(())
+    }
+
     pub(crate) async fn get_scraping_devices(pool: &Pool) -> Result<Vec<Self>> {
         let mut conn = pool.get().await.map_err(|e| {
             sentry::capture_error(&e);
@@ -89,6 +317,24 @@ impl Device {
         })?;
         device::table
             .filter(device::scraping.eq(true))
+            .filter(device::deleted_at.is_null())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Devices whose protocol version is still unknown.
+    pub(crate) async fn get_unnegotiated_devices(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device::table
+            .filter(device::protocol_version.is_null())
+            .filter(device::deleted_at.is_null())
             .get_results(&mut conn)
             .await
             .map_err(|e| {
@@ -100,6 +346,7 @@ impl Device {
 
 impl From<Device> for DeviceResponseDto {
     fn from(val: Device) -> Self {
+        let capabilities = val.capabilities();
         DeviceResponseDto {
             id: val.id.to_string(),
             name: val.name,
@@ -107,6 +354,22 @@ impl From<Device> for DeviceResponseDto {
             description: val.description,
             canscript: val.canscript,
             scraping: val.scraping,
+            scrape_interval: val.scrape_interval as u32,
+            scrape_timeout: val.scrape_timeout as u32,
+            protocol_version: val.protocol_version.map(|version| version as u32),
+            capabilities,
+            // Filled in from `DeviceAvailability` where it is known
+            availability: Availability::Unknown,
+            last_seen: None,
+            consecutive_failures: 0,
+            deleted_at: val.deleted_at,
+            zone_id: val.zone_id,
+            tags: val.tags,
+            asset: AssetDto {
+                location: val.location,
+                installed_on: val.installed_on,
+                notes: val.notes,
+            },
         }
     }
 }
@@ -6,6 +6,7 @@ pub(crate) type Result<T> = core::result::Result<T, Error>;
 pub(crate) enum Error {
     Creation,
     DatabaseConnection,
+    Deletion,
     Find,
 }
 
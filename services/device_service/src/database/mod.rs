@@ -1,4 +1,10 @@
+pub(crate) mod availability;
 pub(crate) mod device;
 mod error;
+pub(crate) mod reading;
+pub(crate) mod rule;
+pub(crate) mod schedule;
 pub(crate) mod schema;
+pub(crate) mod write;
+pub(crate) mod zone;
 pub(crate) use self::error::{Error, Result};
@@ -0,0 +1,176 @@
+use super::{Error, Result, schema::reading};
+use crate::Pool;
+use chrono::{DateTime, Utc};
+use diesel::{
+    prelude::*,
+    sql_types::{Double, Timestamptz},
+};
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+/// A single scalar value of a device, flattened out of the `Type` it read.
+/// Booleans are stored as `0`/`1` in `number`, texts and enums in `text`.
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::reading)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Reading {
+    pub(crate) device_id: Uuid,
+    /// Path of the value inside the read, empty for scalar reads.
+    pub(crate) channel: String,
+    pub(crate) recorded_at: DateTime<Utc>,
+    pub(crate) value_type: String,
+    pub(crate) number: Option<f64>,
+    pub(crate) text: Option<String>,
+    pub(crate) unit: Option<String>,
+    /// Whether the row aggregates older raw readings.
+    pub(crate) downsampled: bool,
+}
+
+impl Reading {
+    pub(crate) async fn insert_all(readings: &[Self], pool: &Pool) -> Result<usize> {
+        if readings.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(reading::table)
+            .values(readings)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })
+    }
+
+    pub(crate) async fn find_range(
+        device_id: Uuid,
+        channel: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        pool: &Pool,
+    ) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        reading::table
+            .filter(reading::device_id.eq(device_id))
+            .filter(reading::channel.eq(channel))
+            .filter(reading::recorded_at.between(start, end))
+            .order(reading::recorded_at.asc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Latest reading of the channel recorded at or before `at`.
+    pub(crate) async fn latest_at(
+        device_id: Uuid,
+        channel: &str,
+        at: DateTime<Utc>,
+        pool: &Pool,
+    ) -> Result<Option<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        reading::table
+            .filter(reading::device_id.eq(device_id))
+            .filter(reading::channel.eq(channel))
+            .filter(reading::recorded_at.le(at))
+            .order(reading::recorded_at.desc())
+            .select(Self::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Channels the device has readings for.
+    pub(crate) async fn channels(device_id: Uuid, pool: &Pool) -> Result<Vec<String>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        reading::table
+            .filter(reading::device_id.eq(device_id))
+            .select(reading::channel)
+            .distinct()
+            .order(reading::channel.asc())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Deletes every reading recorded before `cutoff`.
+    pub(crate) async fn delete_before(cutoff: DateTime<Utc>, pool: &Pool) -> Result<usize> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(reading::table.filter(reading::recorded_at.lt(cutoff)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })
+    }
+
+    /// Replaces the raw readings recorded before `cutoff` by one reading per
+    /// channel and `bucket_secs`. Numbers and measurements are averaged, the
+    /// other types keep the last value of the bucket.
+    pub(crate) async fn downsample_before(
+        cutoff: DateTime<Utc>,
+        bucket_secs: f64,
+        pool: &Pool,
+    ) -> Result<usize> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::sql_query(
+            "WITH raw AS (
+                DELETE FROM reading
+                WHERE NOT downsampled AND recorded_at < $1
+                RETURNING device_id, channel, recorded_at, value_type, number, text, unit
+            )
+            INSERT INTO reading
+                (device_id, channel, recorded_at, value_type, number, text, unit, downsampled)
+            SELECT
+                device_id,
+                channel,
+                to_timestamp(floor(extract(epoch FROM recorded_at)::double precision / $2) * $2)
+                    AS bucket,
+                value_type,
+                CASE WHEN value_type IN ('number', 'measurement') THEN avg(number)
+                    ELSE (array_agg(number ORDER BY recorded_at DESC))[1] END,
+                (array_agg(text ORDER BY recorded_at DESC))[1],
+                unit,
+                TRUE
+            FROM raw
+            GROUP BY device_id, channel, value_type, unit, bucket",
+        )
+        .bind::<Timestamptz, _>(cutoff)
+        .bind::<Double, _>(bucket_secs)
+        .execute(&mut conn)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            Error::Deletion
+        })
+    }
+}
@@ -0,0 +1,625 @@
+use super::{
+    Error, Result,
+    schema::{pending_revert, rule, rule_evaluation},
+};
+use crate::Pool;
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use greenhouse_core::{
+    device_service_dto::rule::{
+        ConditionValue, EvaluationOutcome, Operator, RuleAction, RuleCondition, RuleEvaluationDto,
+        RuleRequestDto, RuleResponseDto,
+    },
+    smart_device_dto::{Type, config::ValidationErrors},
+};
+use uuid::Uuid;
+
+/// Deepest nesting of condition groups.
+const MAX_GROUP_DEPTH: usize = 4;
+/// Longest time a condition can be required to hold and a write be held.
+const MAX_DURATION_SECONDS: u32 = 24 * 60 * 60;
+const MAX_COOLDOWN_SECONDS: u32 = 7 * 24 * 60 * 60;
+
+/// Links conditions on device readings to actions.
+#[derive(Debug, Clone, Queryable, Selectable, AsChangeset, Insertable)]
+#[diesel(table_name = crate::database::schema::rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub(crate) struct Rule {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) enabled: bool,
+    pub(crate) condition: serde_json::Value,
+    pub(crate) actions: serde_json::Value,
+    pub(crate) cooldown_seconds: i32,
+    pub(crate) last_fired_at: Option<DateTime<Utc>>,
+}
+
+/// Devices the conditions and actions of `request` refer to, with the field
+/// naming them.
+pub(crate) fn referenced_devices(request: &RuleRequestDto) -> Vec<(String, Uuid)> {
+    fn collect(condition: &RuleCondition, field: String, devices: &mut Vec<(String, Uuid)>) {
+        match condition {
+            RuleCondition::All { conditions } | RuleCondition::Any { conditions } => {
+                for (index, condition) in conditions.iter().enumerate() {
+                    collect(condition, format!("{field}.conditions[{index}]"), devices);
+                }
+            }
+            RuleCondition::Reading(reading) => {
+                devices.push((format!("{field}.device_id"), reading.device_id))
+            }
+        }
+    }
+    let mut devices = Vec::new();
+    collect(&request.condition, String::from("condition"), &mut devices);
+    for (index, action) in request.actions.iter().enumerate() {
+        if let RuleAction::Write { device_id, .. } = action {
+            devices.push((format!("actions[{index}].device_id"), *device_id));
+        }
+    }
+    devices
+}
+
+fn validate_condition(
+    condition: &RuleCondition,
+    field: &str,
+    depth: usize,
+    errors: &mut ValidationErrors,
+) {
+    match condition {
+        RuleCondition::All { conditions } | RuleCondition::Any { conditions } => {
+            if depth >= MAX_GROUP_DEPTH {
+                errors.add(field, format!("groups nest at most {MAX_GROUP_DEPTH} deep"));
+                return;
+            }
+            if conditions.is_empty() {
+                errors.add(format!("{field}.conditions"), "must not be empty");
+            }
+            for (index, condition) in conditions.iter().enumerate() {
+                validate_condition(
+                    condition,
+                    &format!("{field}.conditions[{index}]"),
+                    depth + 1,
+                    errors,
+                );
+            }
+        }
+        RuleCondition::Reading(reading) => {
+            let ordering = !matches!(reading.operator, Operator::Eq | Operator::Ne);
+            if ordering && matches!(reading.value, ConditionValue::Text(_)) {
+                errors.add(format!("{field}.value"), "texts only compare for equality");
+            }
+            if reading.for_seconds > MAX_DURATION_SECONDS {
+                errors.add(
+                    format!("{field}.for_seconds"),
+                    format!("must be at most {MAX_DURATION_SECONDS}"),
+                );
+            }
+        }
+    }
+}
+
+/// Whether notifications may be sent to `url`. Allowlist entries are hosts,
+/// optionally with a port, nothing is allowed if the allowlist is empty.
+pub(crate) fn notify_allowed(url: &str, allowlist: &[String]) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https")
+        && allowlist
+            .iter()
+            .any(|allowed| allowed == host || *allowed == format!("{host}:{port}"))
+}
+
+fn validate_action(
+    action: &RuleAction,
+    field: &str,
+    notify_allowlist: &[String],
+    errors: &mut ValidationErrors,
+) {
+    match action {
+        RuleAction::Write { hold, .. } => {
+            if let Some(hold) = hold
+                && !(1..=MAX_DURATION_SECONDS).contains(&hold.seconds)
+            {
+                errors.add(
+                    format!("{field}.hold.seconds"),
+                    format!("must be between 1 and {MAX_DURATION_SECONDS}"),
+                );
+            }
+        }
+        RuleAction::Alert { identifier, .. } => {
+            if identifier.trim().is_empty() {
+                errors.add(format!("{field}.identifier"), "must not be empty");
+            }
+        }
+        RuleAction::Notify { url, .. } => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.add(format!("{field}.url"), "must be an http or https url");
+            } else if !notify_allowed(url, notify_allowlist) {
+                errors.add(
+                    format!("{field}.url"),
+                    "host is not on the notify allowlist",
+                );
+            }
+        }
+    }
+}
+
+impl Rule {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: String::new(),
+            enabled: false,
+            condition: serde_json::Value::Null,
+            actions: serde_json::Value::Null,
+            cooldown_seconds: 0,
+            last_fired_at: None,
+        }
+    }
+
+    pub(crate) fn condition(&self) -> Option<RuleCondition> {
+        serde_json::from_value(self.condition.clone()).ok()
+    }
+
+    pub(crate) fn actions(&self) -> Vec<RuleAction> {
+        serde_json::from_value(self.actions.clone()).unwrap_or_default()
+    }
+
+    /// Applies `request`, the devices it refers to are checked separately.
+    pub(crate) fn set(
+        &mut self,
+        request: &RuleRequestDto,
+        notify_allowlist: &[String],
+    ) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let name = request.name.trim();
+        if name.is_empty() {
+            errors.add("name", "must not be empty");
+        }
+        validate_condition(&request.condition, "condition", 0, &mut errors);
+        if request.actions.is_empty() {
+            errors.add("actions", "must not be empty");
+        }
+        for (index, action) in request.actions.iter().enumerate() {
+            validate_action(
+                action,
+                &format!("actions[{index}]"),
+                notify_allowlist,
+                &mut errors,
+            );
+        }
+        if request.cooldown_seconds > MAX_COOLDOWN_SECONDS {
+            errors.add(
+                "cooldown_seconds",
+                format!("must be at most {MAX_COOLDOWN_SECONDS}"),
+            );
+        }
+        errors.into_result(())?;
+
+        self.name = String::from(name);
+        self.enabled = request.enabled;
+        self.condition = serde_json::to_value(&request.condition).unwrap_or_default();
+        self.actions = serde_json::to_value(&request.actions).unwrap_or_default();
+        self.cooldown_seconds = request.cooldown_seconds as i32;
+        Ok(())
+    }
+
+    pub(crate) fn into_response(self) -> RuleResponseDto {
+        RuleResponseDto {
+            condition: self.condition().unwrap_or(RuleCondition::All {
+                conditions: Vec::new(),
+            }),
+            actions: self.actions(),
+            id: self.id,
+            name: self.name,
+            enabled: self.enabled,
+            cooldown_seconds: self.cooldown_seconds.max(0) as u32,
+            last_fired_at: self.last_fired_at,
+        }
+    }
+
+    pub(crate) async fn find_by_id(id: Uuid, pool: &Pool) -> Result<Self> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        rule::table
+            .filter(rule::id.eq(id))
+            .select(Self::as_select())
+            .first(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn all(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        rule::table
+            .order(rule::name.asc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn all_enabled(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        rule::table
+            .filter(rule::enabled.eq(true))
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn flush(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(rule::table)
+            .values(self)
+            .on_conflict(rule::id)
+            .do_update()
+            .set(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Records that the rule fired without touching the rest of it, which
+    /// may have been edited in the meantime.
+    pub(crate) async fn mark_fired(&mut self, at: DateTime<Utc>, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        self.last_fired_at = Some(at);
+        diesel::update(rule::table.filter(rule::id.eq(self.id)))
+            .set(rule::last_fired_at.eq(self.last_fired_at))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(rule::table.filter(rule::id.eq(self.id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })?;
+        Ok(())
+    }
+}
+
+/// A check of a rule, kept as evaluation log.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::rule_evaluation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct RuleEvaluation {
+    pub(crate) rule_id: Uuid,
+    pub(crate) evaluated_at: DateTime<Utc>,
+    pub(crate) outcome: String,
+    pub(crate) conditions: serde_json::Value,
+    pub(crate) actions: serde_json::Value,
+}
+
+fn outcome_name(outcome: EvaluationOutcome) -> &'static str {
+    match outcome {
+        EvaluationOutcome::NotMet => "not_met",
+        EvaluationOutcome::Cooldown => "cooldown",
+        EvaluationOutcome::Fired => "fired",
+        EvaluationOutcome::Failed => "failed",
+    }
+}
+
+fn parse_outcome(name: &str) -> EvaluationOutcome {
+    match name {
+        "cooldown" => EvaluationOutcome::Cooldown,
+        "fired" => EvaluationOutcome::Fired,
+        "failed" => EvaluationOutcome::Failed,
+        _ => EvaluationOutcome::NotMet,
+    }
+}
+
+impl RuleEvaluation {
+    pub(crate) fn new(rule_id: Uuid, evaluation: &RuleEvaluationDto) -> Self {
+        Self {
+            rule_id,
+            evaluated_at: evaluation.evaluated_at,
+            outcome: String::from(outcome_name(evaluation.outcome)),
+            conditions: serde_json::to_value(&evaluation.conditions).unwrap_or_default(),
+            actions: serde_json::to_value(&evaluation.actions).unwrap_or_default(),
+        }
+    }
+
+    pub(crate) async fn insert(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(rule_evaluation::table)
+            .values(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// The latest `limit` evaluations of the rule, newest first.
+    pub(crate) async fn find_by_rule(
+        rule_id: Uuid,
+        matched_only: bool,
+        limit: i64,
+        pool: &Pool,
+    ) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        let mut query = rule_evaluation::table
+            .filter(rule_evaluation::rule_id.eq(rule_id))
+            .into_boxed();
+        if matched_only {
+            query =
+                query.filter(rule_evaluation::outcome.ne(outcome_name(EvaluationOutcome::NotMet)));
+        }
+        query
+            .order(rule_evaluation::evaluated_at.desc())
+            .limit(limit)
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Deletes every evaluation before `cutoff`.
+    pub(crate) async fn delete_before(cutoff: DateTime<Utc>, pool: &Pool) -> Result<usize> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(rule_evaluation::table.filter(rule_evaluation::evaluated_at.lt(cutoff)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })
+    }
+}
+
+impl From<RuleEvaluation> for RuleEvaluationDto {
+    fn from(evaluation: RuleEvaluation) -> Self {
+        Self {
+            outcome: parse_outcome(&evaluation.outcome),
+            evaluated_at: evaluation.evaluated_at,
+            conditions: serde_json::from_value(evaluation.conditions).unwrap_or_default(),
+            actions: serde_json::from_value(evaluation.actions).unwrap_or_default(),
+        }
+    }
+}
+
+/// How long a claimed revert is left to its run before it is due again, in
+/// case the service stops in the middle of it.
+const REVERT_LEASE: TimeDelta = TimeDelta::minutes(5);
+/// Wait before the first retry of a failed revert, doubled for every further
+/// attempt up to `MAX_REVERT_BACKOFF`.
+const REVERT_BACKOFF: TimeDelta = TimeDelta::seconds(30);
+const MAX_REVERT_BACKOFF: TimeDelta = TimeDelta::hours(1);
+
+/// Value a held write reverts to, kept until the revert succeeded so holds
+/// outlive restarts and offline devices.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::pending_revert)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct PendingRevert {
+    pub(crate) id: Uuid,
+    pub(crate) rule_id: Uuid,
+    pub(crate) device_id: Uuid,
+    pub(crate) revert_to: serde_json::Value,
+    pub(crate) due_at: DateTime<Utc>,
+    /// Failed attempts so far.
+    pub(crate) attempts: i32,
+    pub(crate) idempotency_key: String,
+}
+
+/// Wait before retrying a revert that failed `attempts` times.
+fn revert_backoff(attempts: i32) -> TimeDelta {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    (REVERT_BACKOFF * 2_i32.pow(doublings)).min(MAX_REVERT_BACKOFF)
+}
+
+impl PendingRevert {
+    pub(crate) fn new(
+        rule_id: Uuid,
+        device_id: Uuid,
+        revert_to: &Type,
+        due_at: DateTime<Utc>,
+        idempotency_key: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            rule_id,
+            device_id,
+            revert_to: serde_json::to_value(revert_to).unwrap_or_default(),
+            due_at,
+            attempts: 0,
+            idempotency_key,
+        }
+    }
+
+    pub(crate) fn revert_to(&self) -> Option<Type> {
+        serde_json::from_value(self.revert_to.clone()).ok()
+    }
+
+    pub(crate) async fn insert(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(pending_revert::table)
+            .values(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Returns the reverts due at `now` and leases them, so a revert still
+    /// running isn't started again by the next pass.
+    pub(crate) async fn claim_due(now: DateTime<Utc>, pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::update(pending_revert::table.filter(pending_revert::due_at.le(now)))
+            .set(pending_revert::due_at.eq(now + REVERT_LEASE))
+            .returning(Self::as_returning())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    /// Records a failed attempt and makes the revert due again after a
+    /// backoff, returns when.
+    pub(crate) async fn retry_later(
+        &mut self,
+        now: DateTime<Utc>,
+        pool: &Pool,
+    ) -> Result<DateTime<Utc>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        self.attempts = self.attempts.saturating_add(1);
+        self.due_at = now + revert_backoff(self.attempts);
+        diesel::update(pending_revert::table.filter(pending_revert::id.eq(self.id)))
+            .set((
+                pending_revert::attempts.eq(self.attempts),
+                pending_revert::due_at.eq(self.due_at),
+            ))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(self.due_at)
+    }
+
+    pub(crate) async fn delete(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(pending_revert::table.filter(pending_revert::id.eq(self.id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist() -> Vec<String> {
+        vec![
+            String::from("hooks.example.com"),
+            String::from("localhost:8080"),
+        ]
+    }
+
+    #[test]
+    fn allows_notifications_to_listed_hosts() {
+        assert!(notify_allowed(
+            "https://hooks.example.com/rule",
+            &allowlist()
+        ));
+        assert!(notify_allowed(
+            "http://hooks.example.com:9000/",
+            &allowlist()
+        ));
+        assert!(notify_allowed("http://localhost:8080/notify", &allowlist()));
+    }
+
+    #[test]
+    fn refuses_notifications_to_other_hosts() {
+        assert!(!notify_allowed(
+            "http://localhost:9000/notify",
+            &allowlist()
+        ));
+        assert!(!notify_allowed("http://localhost/notify", &allowlist()));
+        assert!(!notify_allowed("https://evil.example.com/", &allowlist()));
+        assert!(!notify_allowed(
+            "https://hooks.example.com.evil.net/",
+            &allowlist()
+        ));
+        assert!(!notify_allowed(
+            "https://hooks.example.com@evil.net/",
+            &allowlist()
+        ));
+        assert!(!notify_allowed("ftp://hooks.example.com/", &allowlist()));
+        assert!(!notify_allowed("https://hooks.example.com/", &[]));
+    }
+
+    #[test]
+    fn backs_off_failed_reverts_up_to_an_hour() {
+        assert_eq!(revert_backoff(1), TimeDelta::seconds(30));
+        assert_eq!(revert_backoff(2), TimeDelta::seconds(60));
+        assert_eq!(revert_backoff(4), TimeDelta::seconds(240));
+        assert_eq!(revert_backoff(8), TimeDelta::hours(1));
+        assert_eq!(revert_backoff(i32::MAX), TimeDelta::hours(1));
+    }
+}
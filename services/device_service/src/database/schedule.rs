@@ -0,0 +1,383 @@
+use super::{
+    Error, Result,
+    schema::{schedule, schedule_execution},
+};
+use crate::{
+    Config, Pool,
+    schedules::trigger::{Trigger, parse_cron},
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use greenhouse_core::{
+    device_service_dto::schedule::{
+        ExecutionStatus, MissedRunPolicy, ScheduleExecutionDto, ScheduleRequestDto,
+        ScheduleResponseDto, ScheduleTrigger,
+    },
+    smart_device_dto::{Type, config::ValidationErrors},
+};
+use uuid::Uuid;
+
+/// Solar offsets are limited to half a day so they stay next to their event.
+const MAX_OFFSET_MINUTES: i32 = 720;
+
+/// A value written to a device whenever the trigger fires.
+#[derive(Debug, Clone, Queryable, Selectable, AsChangeset, Insertable)]
+#[diesel(table_name = crate::database::schema::schedule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub(crate) struct Schedule {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) device_id: Uuid,
+    pub(crate) trigger_kind: String,
+    /// Set for cron triggers only.
+    pub(crate) cron_expression: Option<String>,
+    /// Minutes after sunrise or sunset, ignored for cron triggers.
+    pub(crate) offset_minutes: i32,
+    pub(crate) data: serde_json::Value,
+    pub(crate) enabled: bool,
+    pub(crate) missed_run_policy: String,
+    pub(crate) last_run_at: Option<DateTime<Utc>>,
+    /// Runs up to this time were executed or given up on.
+    pub(crate) handled_until: DateTime<Utc>,
+}
+
+fn policy_name(policy: MissedRunPolicy) -> &'static str {
+    match policy {
+        MissedRunPolicy::Skip => "skip",
+        MissedRunPolicy::RunOnce => "run_once",
+    }
+}
+
+fn parse_policy(name: &str) -> MissedRunPolicy {
+    match name {
+        "run_once" => MissedRunPolicy::RunOnce,
+        _ => MissedRunPolicy::Skip,
+    }
+}
+
+impl Schedule {
+    pub(crate) fn new(device_id: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: String::new(),
+            device_id,
+            trigger_kind: String::from("cron"),
+            cron_expression: None,
+            offset_minutes: 0,
+            data: serde_json::Value::Null,
+            enabled: false,
+            missed_run_policy: String::from(policy_name(MissedRunPolicy::Skip)),
+            last_run_at: None,
+            handled_until: Utc::now(),
+        }
+    }
+
+    pub(crate) fn trigger(&self) -> ScheduleTrigger {
+        let offset_minutes = self.offset_minutes;
+        match self.trigger_kind.as_str() {
+            "sunrise" => ScheduleTrigger::Sunrise { offset_minutes },
+            "sunset" => ScheduleTrigger::Sunset { offset_minutes },
+            _ => ScheduleTrigger::Cron {
+                expression: self.cron_expression.clone().unwrap_or_default(),
+            },
+        }
+    }
+
+    pub(crate) fn data(&self) -> Type {
+        serde_json::from_value(self.data.clone()).unwrap_or(Type::None)
+    }
+
+    pub(crate) fn missed_run_policy(&self) -> MissedRunPolicy {
+        parse_policy(&self.missed_run_policy)
+    }
+
+    /// Applies `request`. Solar triggers need the location to be configured.
+    pub(crate) fn set(
+        &mut self,
+        request: &ScheduleRequestDto,
+        config: &Config,
+    ) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let name = request.name.trim();
+        if name.is_empty() {
+            errors.add("name", "must not be empty");
+        }
+        match &request.trigger {
+            ScheduleTrigger::Cron { expression } => {
+                if let Err(e) = parse_cron(expression) {
+                    errors.add(
+                        "trigger.expression",
+                        format!("invalid cron expression: {e}"),
+                    );
+                }
+            }
+            ScheduleTrigger::Sunrise { offset_minutes }
+            | ScheduleTrigger::Sunset { offset_minutes } => {
+                if config.latitude.is_none() || config.longitude.is_none() {
+                    errors.add("trigger.kind", "no location configured for solar triggers");
+                }
+                if offset_minutes.abs() > MAX_OFFSET_MINUTES {
+                    errors.add(
+                        "trigger.offset_minutes",
+                        format!("must be within ±{MAX_OFFSET_MINUTES}"),
+                    );
+                }
+            }
+        }
+        errors.into_result(())?;
+
+        // Runs due under the old trigger or while disabled are not caught up
+        if self.trigger() != request.trigger || (request.enabled && !self.enabled) {
+            self.handled_until = Utc::now();
+        }
+        let (kind, cron_expression, offset_minutes) = match &request.trigger {
+            ScheduleTrigger::Cron { expression } => ("cron", Some(expression.trim()), 0),
+            ScheduleTrigger::Sunrise { offset_minutes } => ("sunrise", None, *offset_minutes),
+            ScheduleTrigger::Sunset { offset_minutes } => ("sunset", None, *offset_minutes),
+        };
+        self.name = String::from(name);
+        self.device_id = request.device_id;
+        self.trigger_kind = String::from(kind);
+        self.cron_expression = cron_expression.map(String::from);
+        self.offset_minutes = offset_minutes;
+        self.data = serde_json::to_value(&request.data).unwrap_or_default();
+        self.enabled = request.enabled;
+        self.missed_run_policy = String::from(policy_name(request.missed_run_policy));
+        Ok(())
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.handled_until = Utc::now();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Next run that is still to be executed, `None` while disabled.
+    pub(crate) fn next_run_at(&self, config: &Config) -> Option<DateTime<Utc>> {
+        if !self.enabled {
+            return None;
+        }
+        Trigger::new(&self.trigger(), config).next_after(self.handled_until)
+    }
+
+    pub(crate) fn into_response(self, config: &Config) -> ScheduleResponseDto {
+        ScheduleResponseDto {
+            next_run_at: self.next_run_at(config),
+            trigger: self.trigger(),
+            data: self.data(),
+            missed_run_policy: self.missed_run_policy(),
+            id: self.id,
+            name: self.name,
+            device_id: self.device_id,
+            enabled: self.enabled,
+            last_run_at: self.last_run_at,
+        }
+    }
+
+    pub(crate) async fn find_by_id(id: Uuid, pool: &Pool) -> Result<Self> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        schedule::table
+            .filter(schedule::id.eq(id))
+            .select(Self::as_select())
+            .first(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn all(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        schedule::table
+            .order(schedule::name.asc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn all_enabled(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        schedule::table
+            .filter(schedule::enabled.eq(true))
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn flush(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(schedule::table)
+            .values(self)
+            .on_conflict(schedule::id)
+            .do_update()
+            .set(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Marks runs up to `until` as handled without touching the rest of the
+    /// schedule, which may have been edited in the meantime.
+    pub(crate) async fn advance(
+        &mut self,
+        until: DateTime<Utc>,
+        ran_at: Option<DateTime<Utc>>,
+        pool: &Pool,
+    ) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        self.handled_until = until;
+        self.last_run_at = ran_at.or(self.last_run_at);
+        diesel::update(
+            schedule::table
+                .filter(schedule::id.eq(self.id))
+                .filter(schedule::handled_until.lt(until)),
+        )
+        .set((
+            schedule::handled_until.eq(self.handled_until),
+            schedule::last_run_at.eq(self.last_run_at),
+        ))
+        .execute(&mut conn)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            Error::Creation
+        })?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(schedule::table.filter(schedule::id.eq(self.id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })?;
+        Ok(())
+    }
+}
+
+/// A run of a schedule, kept as execution log.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::schedule_execution)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct ScheduleExecution {
+    pub(crate) schedule_id: Uuid,
+    pub(crate) scheduled_for: DateTime<Utc>,
+    pub(crate) executed_at: DateTime<Utc>,
+    pub(crate) status: String,
+    pub(crate) error: Option<String>,
+}
+
+fn status_name(status: ExecutionStatus) -> &'static str {
+    match status {
+        ExecutionStatus::Succeeded => "succeeded",
+        ExecutionStatus::Failed => "failed",
+        ExecutionStatus::Missed => "missed",
+    }
+}
+
+fn parse_status(name: &str) -> ExecutionStatus {
+    match name {
+        "succeeded" => ExecutionStatus::Succeeded,
+        "missed" => ExecutionStatus::Missed,
+        _ => ExecutionStatus::Failed,
+    }
+}
+
+impl ScheduleExecution {
+    pub(crate) fn new(
+        schedule_id: Uuid,
+        scheduled_for: DateTime<Utc>,
+        status: ExecutionStatus,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            schedule_id,
+            scheduled_for,
+            executed_at: Utc::now(),
+            status: String::from(status_name(status)),
+            error,
+        }
+    }
+
+    pub(crate) async fn insert(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(schedule_execution::table)
+            .values(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Runs of the schedule, newest first.
+    pub(crate) async fn find_by_schedule(schedule_id: Uuid, pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        schedule_execution::table
+            .filter(schedule_execution::schedule_id.eq(schedule_id))
+            .order(schedule_execution::executed_at.desc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+}
+
+impl From<ScheduleExecution> for ScheduleExecutionDto {
+    fn from(execution: ScheduleExecution) -> Self {
+        Self {
+            status: parse_status(&execution.status),
+            scheduled_for: execution.scheduled_for,
+            executed_at: execution.executed_at,
+            error: execution.error,
+        }
+    }
+}
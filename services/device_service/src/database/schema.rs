@@ -8,5 +8,161 @@ diesel::table! {
         description -> Varchar,
         canscript -> Bool,
         scraping -> Bool,
+        protocol_version -> Nullable<Int4>,
+        capabilities -> Array<Text>,
+        tunnel_token_hash -> Nullable<Varchar>,
+        scrape_interval -> Int4,
+        scrape_timeout -> Int4,
+        scripting_token -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamptz>,
+        zone_id -> Nullable<Uuid>,
+        tags -> Array<Text>,
+        location -> Nullable<Varchar>,
+        installed_on -> Nullable<Date>,
+        notes -> Nullable<Varchar>,
     }
 }
+
+diesel::table! {
+    device_availability (device_id) {
+        device_id -> Uuid,
+        availability -> Varchar,
+        last_seen -> Nullable<Timestamptz>,
+        consecutive_failures -> Int4,
+        changed_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    device_availability_history (id) {
+        id -> Int8,
+        device_id -> Uuid,
+        availability -> Varchar,
+        changed_at -> Timestamptz,
+        consecutive_failures -> Int4,
+    }
+}
+
+diesel::table! {
+    device_write (id) {
+        id -> Int8,
+        device_id -> Uuid,
+        actor -> Nullable<Varchar>,
+        written_at -> Timestamptz,
+        data -> Jsonb,
+        status -> Varchar,
+        error -> Nullable<Varchar>,
+        idempotency_key -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    pending_revert (id) {
+        id -> Uuid,
+        rule_id -> Uuid,
+        device_id -> Uuid,
+        revert_to -> Jsonb,
+        due_at -> Timestamptz,
+        attempts -> Int4,
+        idempotency_key -> Varchar,
+    }
+}
+
+diesel::table! {
+    reading (id) {
+        id -> Int8,
+        device_id -> Uuid,
+        channel -> Varchar,
+        recorded_at -> Timestamptz,
+        value_type -> Varchar,
+        number -> Nullable<Float8>,
+        text -> Nullable<Varchar>,
+        unit -> Nullable<Varchar>,
+        downsampled -> Bool,
+    }
+}
+
+diesel::table! {
+    rule (id) {
+        id -> Uuid,
+        name -> Varchar,
+        enabled -> Bool,
+        condition -> Jsonb,
+        actions -> Jsonb,
+        cooldown_seconds -> Int4,
+        last_fired_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    rule_evaluation (id) {
+        id -> Int8,
+        rule_id -> Uuid,
+        evaluated_at -> Timestamptz,
+        outcome -> Varchar,
+        conditions -> Jsonb,
+        actions -> Jsonb,
+    }
+}
+
+diesel::table! {
+    schedule (id) {
+        id -> Uuid,
+        name -> Varchar,
+        device_id -> Uuid,
+        trigger_kind -> Varchar,
+        cron_expression -> Nullable<Varchar>,
+        offset_minutes -> Int4,
+        data -> Jsonb,
+        enabled -> Bool,
+        missed_run_policy -> Varchar,
+        last_run_at -> Nullable<Timestamptz>,
+        handled_until -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    schedule_execution (id) {
+        id -> Int8,
+        schedule_id -> Uuid,
+        scheduled_for -> Timestamptz,
+        executed_at -> Timestamptz,
+        status -> Varchar,
+        error -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    zone (id) {
+        id -> Uuid,
+        name -> Varchar,
+        kind -> Varchar,
+        parent_id -> Nullable<Uuid>,
+        description -> Varchar,
+    }
+}
+
+diesel::joinable!(device -> zone (zone_id));
+diesel::joinable!(device_availability -> device (device_id));
+diesel::joinable!(device_availability_history -> device (device_id));
+diesel::joinable!(device_write -> device (device_id));
+diesel::joinable!(pending_revert -> device (device_id));
+diesel::joinable!(pending_revert -> rule (rule_id));
+diesel::joinable!(reading -> device (device_id));
+diesel::joinable!(rule_evaluation -> rule (rule_id));
+diesel::joinable!(schedule -> device (device_id));
+diesel::joinable!(schedule_execution -> schedule (schedule_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    device,
+    device_availability,
+    device_availability_history,
+    device_write,
+    pending_revert,
+    reading,
+    rule,
+    rule_evaluation,
+    schedule,
+    schedule_execution,
+    zone,
+);
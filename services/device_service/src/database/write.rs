@@ -0,0 +1,112 @@
+use super::{Error, Result, schema::device_write};
+use crate::Pool;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use greenhouse_core::{
+    device_service_dto::write::WriteAuditDto,
+    smart_device_dto::{
+        Type,
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
+    },
+};
+use uuid::Uuid;
+
+/// A write forwarded to a device, kept as audit trail.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::database::schema::device_write)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct DeviceWrite {
+    pub(crate) device_id: Uuid,
+    /// User the write was done for, `None` for services writing directly.
+    pub(crate) actor: Option<String>,
+    pub(crate) written_at: DateTime<Utc>,
+    pub(crate) data: serde_json::Value,
+    pub(crate) status: String,
+    pub(crate) error: Option<String>,
+    pub(crate) idempotency_key: Option<String>,
+}
+
+fn status_name(status: WriteStatus) -> &'static str {
+    match status {
+        WriteStatus::Applied => "applied",
+        WriteStatus::Unchanged => "unchanged",
+        WriteStatus::Rejected => "rejected",
+        WriteStatus::Failed => "failed",
+    }
+}
+
+fn parse_status(name: &str) -> WriteStatus {
+    match name {
+        "applied" => WriteStatus::Applied,
+        "unchanged" => WriteStatus::Unchanged,
+        "rejected" => WriteStatus::Rejected,
+        _ => WriteStatus::Failed,
+    }
+}
+
+impl DeviceWrite {
+    pub(crate) fn new(
+        device_id: Uuid,
+        actor: Option<String>,
+        request: &WriteRequestDto,
+        response: &WriteResponseDto,
+    ) -> Self {
+        Self {
+            device_id,
+            actor,
+            written_at: Utc::now(),
+            data: serde_json::to_value(&request.data).unwrap_or_default(),
+            status: String::from(status_name(response.status)),
+            error: response.error.clone(),
+            idempotency_key: request.idempotency_key.clone(),
+        }
+    }
+
+    pub(crate) async fn insert(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(device_write::table)
+            .values(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Writes to the device, newest first.
+    pub(crate) async fn find_by_device(device_id: Uuid, pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        device_write::table
+            .filter(device_write::device_id.eq(device_id))
+            .order(device_write::written_at.desc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+}
+
+impl From<DeviceWrite> for WriteAuditDto {
+    fn from(write: DeviceWrite) -> Self {
+        Self {
+            status: parse_status(&write.status),
+            actor: write.actor,
+            written_at: write.written_at,
+            data: serde_json::from_value(write.data).unwrap_or(Type::None),
+            error: write.error,
+            idempotency_key: write.idempotency_key,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use super::{Error, Result, schema::zone};
+use crate::Pool;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use greenhouse_core::{
+    device_service_dto::zone::{ZoneKind, ZoneRequestDto, ZoneResponseDto},
+    smart_device_dto::config::ValidationErrors,
+};
+use uuid::Uuid;
+
+/// A greenhouse, compartment or bench devices are placed in.
+#[derive(Debug, Clone, Queryable, Selectable, AsChangeset, Insertable)]
+#[diesel(table_name = crate::database::schema::zone)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub(crate) struct Zone {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) kind: String,
+    /// Zone this one lies in, `None` for greenhouses.
+    pub(crate) parent_id: Option<Uuid>,
+    pub(crate) description: String,
+}
+
+fn kind_name(kind: ZoneKind) -> &'static str {
+    match kind {
+        ZoneKind::Greenhouse => "greenhouse",
+        ZoneKind::Compartment => "compartment",
+        ZoneKind::Bench => "bench",
+    }
+}
+
+fn parse_kind(name: &str) -> ZoneKind {
+    match name {
+        "compartment" => ZoneKind::Compartment,
+        "bench" => ZoneKind::Bench,
+        _ => ZoneKind::Greenhouse,
+    }
+}
+
+/// Ids of `root` and every zone below it.
+pub(crate) fn subtree(root: Uuid, zones: &[Zone]) -> Vec<Uuid> {
+    let mut ids = vec![root];
+    let mut index = 0;
+    while let Some(&parent) = ids.get(index) {
+        ids.extend(
+            zones
+                .iter()
+                .filter(|zone| zone.parent_id == Some(parent))
+                .map(|zone| zone.id),
+        );
+        index += 1;
+    }
+    ids
+}
+
+impl Zone {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: String::new(),
+            kind: String::from(kind_name(ZoneKind::Greenhouse)),
+            parent_id: None,
+            description: String::new(),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> ZoneKind {
+        parse_kind(&self.kind)
+    }
+
+    /// Applies `request`. The parent has to be of the kind the zone requires,
+    /// `parent` is the zone `request.parent_id` names if it exists.
+    pub(crate) fn set(
+        &mut self,
+        request: &ZoneRequestDto,
+        parent: Option<&Zone>,
+    ) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let name = request.name.trim();
+        if name.is_empty() {
+            errors.add("name", "must not be empty");
+        }
+        match (request.kind.parent(), request.parent_id, parent) {
+            (None, None, _) => {}
+            (None, Some(_), _) => errors.add("parent_id", "a greenhouse has no parent"),
+            (Some(kind), None, _) => {
+                errors.add("parent_id", format!("must be a {}", kind_name(kind)))
+            }
+            (Some(_), Some(_), None) => errors.add("parent_id", "unknown zone"),
+            (Some(kind), Some(_), Some(parent)) if parent.kind() != kind => {
+                errors.add("parent_id", format!("must be a {}", kind_name(kind)))
+            }
+            _ => {}
+        }
+        errors.into_result(())?;
+        self.name = String::from(name);
+        self.kind = String::from(kind_name(request.kind));
+        self.parent_id = request.parent_id;
+        self.description = request.description.clone();
+        Ok(())
+    }
+
+    pub(crate) async fn find(id: Uuid, pool: &Pool) -> Result<Option<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        zone::table
+            .filter(zone::id.eq(id))
+            .select(Self::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn find_by_id(id: Uuid, pool: &Pool) -> Result<Self> {
+        Self::find(id, pool).await?.ok_or(Error::Find)
+    }
+
+    pub(crate) async fn all(pool: &Pool) -> Result<Vec<Self>> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        zone::table
+            .order(zone::name.asc())
+            .select(Self::as_select())
+            .get_results(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Find
+            })
+    }
+
+    pub(crate) async fn has_children(&self, pool: &Pool) -> Result<bool> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::select(diesel::dsl::exists(
+            zone::table.filter(zone::parent_id.eq(self.id)),
+        ))
+        .get_result(&mut conn)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+            Error::Find
+        })
+    }
+
+    pub(crate) async fn flush(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::insert_into(zone::table)
+            .values(self)
+            .on_conflict(zone::id)
+            .do_update()
+            .set(self)
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Creation
+            })?;
+        Ok(())
+    }
+
+    /// Deletes the zone, its devices are left without a zone.
+    pub(crate) async fn delete(&self, pool: &Pool) -> Result<()> {
+        let mut conn = pool.get().await.map_err(|e| {
+            sentry::capture_error(&e);
+            Error::DatabaseConnection
+        })?;
+        diesel::delete(zone::table.filter(zone::id.eq(self.id)))
+            .execute(&mut conn)
+            .await
+            .map_err(|e| {
+                sentry::capture_error(&e);
+                Error::Deletion
+            })?;
+        Ok(())
+    }
+}
+
+impl From<Zone> for ZoneResponseDto {
+    fn from(zone: Zone) -> Self {
+        Self {
+            kind: zone.kind(),
+            id: zone.id,
+            name: zone.name,
+            parent_id: zone.parent_id,
+            description: zone.description,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    database::device::Device,
+    router::{error::Error, service::negotiate_protocol},
+};
+
+/// How often devices that haven't completed the version handshake are retried.
+const HANDSHAKE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runs the version handshake with devices that were unreachable when they
+/// were created or updated, outside of the scrape path.
+pub(crate) fn start_handshakes(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            match Device::get_unnegotiated_devices(&state.pool).await {
+                Ok(devices) => {
+                    for mut device in devices {
+                        let pool = state.pool.clone();
+                        // One unreachable device must not hold up the others
+                        tokio::spawn(async move {
+                            report(device.id, negotiate_protocol(&mut device, &pool).await);
+                        });
+                    }
+                }
+                Err(e) => {
+                    sentry::capture_error(&e);
+                    tracing::error!("Error loading devices for the version handshake: {:?}", e);
+                }
+            }
+            tokio::time::sleep(HANDSHAKE_INTERVAL).await;
+        }
+    });
+}
+
+/// Logs a failed handshake. Unreachable devices are retried later, any other
+/// failure means the device answered with something unexpected.
+pub(crate) fn report(device_id: Uuid, result: Result<(), Error>) {
+    match result {
+        Ok(()) => {}
+        Err(Error::SmartDeviceNotReachable) => {
+            tracing::warn!("Device {device_id} is unreachable, version handshake postponed");
+        }
+        Err(e) => {
+            tracing::error!("Version handshake with device {device_id} failed: {:?}", e);
+        }
+    }
+}
@@ -8,13 +8,21 @@ use diesel::{Connection, PgConnection};
 use diesel_async::AsyncPgConnection;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use greenhouse_core::device_service_dto::endpoints::{RULE, SCHEDULE, ZONE};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use serde::Deserialize;
 use tower_http::trace::TraceLayer;
 
+mod availability;
 pub(crate) mod database;
+mod handshake;
+mod readings;
 mod router;
+mod rules;
+mod schedules;
 mod scrape_service;
+mod tls;
+mod tunnel;
 
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
@@ -28,18 +36,115 @@ pub struct Config {
     pub scripting_service: String,
     #[serde(rename = "DATABASE_URL")]
     pub database_url: String,
-    #[serde(rename = "PROMETHEUS_URL")]
-    pub prometheus_url: String,
+    /// Receives the alerts raised when devices go offline or come back.
+    #[serde(rename = "DATA_STORAGE_SERVICE", default)]
+    pub data_storage_service: Option<String>,
+    /// Queried for history recorded before readings were stored natively.
+    #[serde(rename = "PROMETHEUS_URL", default)]
+    pub prometheus_url: Option<String>,
+    /// Whether scraped readings are also exported as gauges on `/metrics`.
+    #[serde(rename = "PROMETHEUS_SINK", default = "default_prometheus_sink")]
+    pub prometheus_sink: bool,
+    /// Readings older than this are deleted.
+    #[serde(
+        rename = "READING_RETENTION_DAYS",
+        default = "default_reading_retention_days"
+    )]
+    pub reading_retention_days: u32,
+    /// Readings older than this are downsampled.
+    #[serde(rename = "READING_RAW_DAYS", default = "default_reading_raw_days")]
+    pub reading_raw_days: u32,
+    /// Length of the buckets readings are downsampled into.
+    #[serde(
+        rename = "READING_DOWNSAMPLE_SECS",
+        default = "default_reading_downsample_secs"
+    )]
+    pub reading_downsample_secs: u32,
+    /// Maximum number of devices scraped at the same time.
+    #[serde(rename = "SCRAPE_CONCURRENCY", default = "default_scrape_concurrency")]
+    pub scrape_concurrency: u32,
+    /// Failed scrapes in a row after which a device is offline.
+    #[serde(
+        rename = "OFFLINE_AFTER_FAILURES",
+        default = "default_offline_after_failures"
+    )]
+    pub offline_after_failures: u32,
+    /// Location sunrise and sunset schedules are computed for, in degrees.
+    #[serde(rename = "LATITUDE", default)]
+    pub latitude: Option<f64>,
+    #[serde(rename = "LONGITUDE", default)]
+    pub longitude: Option<f64>,
+    /// IANA timezone cron schedules are evaluated in.
+    #[serde(rename = "TIMEZONE", default = "default_timezone")]
+    pub timezone: String,
+    /// How often automation rules are evaluated.
+    #[serde(
+        rename = "RULE_EVALUATION_SECS",
+        default = "default_rule_evaluation_secs"
+    )]
+    pub rule_evaluation_secs: u32,
+    /// Rule evaluations older than this are deleted.
+    #[serde(
+        rename = "RULE_LOG_RETENTION_DAYS",
+        default = "default_rule_log_retention_days"
+    )]
+    pub rule_log_retention_days: u32,
+    /// Hosts, optionally with a port, rules may send notifications to.
+    #[serde(rename = "NOTIFY_ALLOWLIST", default)]
+    pub notify_allowlist: Vec<String>,
     #[serde(rename = "SENTRY_URL")]
     pub sentry_url: String,
     #[serde(rename = "ENVIRONMENT", default = "default_environment")]
     pub environment: String,
+    /// Directory holding the key and certificate of the device CA.
+    #[serde(rename = "CA_DIR", default = "default_ca_dir")]
+    pub ca_dir: String,
 }
 
 fn default_environment() -> String {
     "development".to_string()
 }
 
+fn default_ca_dir() -> String {
+    "config/ca".to_string()
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_rule_evaluation_secs() -> u32 {
+    30
+}
+
+fn default_rule_log_retention_days() -> u32 {
+    30
+}
+
+fn default_prometheus_sink() -> bool {
+    true
+}
+
+fn default_reading_retention_days() -> u32 {
+    365
+}
+
+fn default_reading_raw_days() -> u32 {
+    7
+}
+
+fn default_reading_downsample_secs() -> u32 {
+    300
+}
+
+fn default_scrape_concurrency() -> u32 {
+    32
+}
+
+fn default_offline_after_failures() -> u32 {
+    3
+}
+
 pub type Pool = bb8::Pool<AsyncDieselConnectionManager<AsyncPgConnection>>;
 
 #[derive(FromRef, Clone)]
@@ -50,25 +155,37 @@ pub struct AppState {
 
 pub fn app(config: Config, pool: Pool) -> Router {
     run_migration(&config.database_url);
+    tls::init(&config.ca_dir);
 
     let state = AppState { config, pool };
     let recorder_handle = setup_metrics_recorder();
 
     scrape_service::start_scrape_devices(state.clone());
+    handshake::start_handshakes(state.clone());
+    readings::start_maintenance(state.clone());
+    schedules::start_schedules(state.clone());
+    rules::start_rules(state.clone());
     Router::new()
         .route("/metrics", get(move || ready(recorder_handle.render())))
         .merge(router::device_router::routes(state.clone()))
+        .nest(ZONE, router::zone_router::routes(state.clone()))
+        .nest(SCHEDULE, router::schedule_router::routes(state.clone()))
+        .nest(RULE, router::rule_router::routes(state.clone()))
         .route("/health", get(|| async {}))
         .layer(TraceLayer::new_for_http())
 }
 
 pub fn test_app(config: Config, pool: Pool) -> Router {
     run_migration(&config.database_url);
+    tls::init(&config.ca_dir);
 
     let state = AppState { config, pool };
 
     Router::new()
         .merge(router::device_router::routes(state.clone()))
+        .nest(ZONE, router::zone_router::routes(state.clone()))
+        .nest(SCHEDULE, router::schedule_router::routes(state.clone()))
+        .nest(RULE, router::rule_router::routes(state.clone()))
         .layer(TraceLayer::new_for_http())
 }
 
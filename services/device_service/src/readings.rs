@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use greenhouse_core::smart_device_dto::Type;
+use uuid::Uuid;
+
+use crate::{
+    AppState, Pool,
+    database::{self, reading::Reading},
+};
+
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Stores every scalar value of `data` as a reading of the device. Values
+/// nested in objects and arrays become channels named like their Prometheus
+/// series, e.g. `sensors_0_temperature`.
+pub(crate) async fn ingest(
+    device_id: Uuid,
+    data: &Type,
+    received_at: DateTime<Utc>,
+    pool: &Pool,
+) -> database::Result<usize> {
+    let mut readings = Vec::new();
+    flatten(device_id, String::new(), data, received_at, &mut readings);
+    Reading::insert_all(&readings, pool).await
+}
+
+fn flatten(
+    device_id: Uuid,
+    channel: String,
+    data: &Type,
+    recorded_at: DateTime<Utc>,
+    readings: &mut Vec<Reading>,
+) {
+    let reading = |value_type: &str, number, text, unit| Reading {
+        device_id,
+        channel: channel.clone(),
+        recorded_at,
+        value_type: value_type.to_string(),
+        number,
+        text,
+        unit,
+        downsampled: false,
+    };
+    let nested = |name: &str| {
+        if channel.is_empty() {
+            name.to_string()
+        } else {
+            format!("{channel}_{name}")
+        }
+    };
+    match data {
+        Type::Number(number) => readings.push(reading("number", Some(*number), None, None)),
+        Type::Boolean(boolean) => readings.push(reading(
+            "boolean",
+            Some(if *boolean { 1.0 } else { 0.0 }),
+            None,
+            None,
+        )),
+        Type::Text(text) => readings.push(reading("text", None, Some(text.clone()), None)),
+        Type::Enum(option) => readings.push(reading("enum", None, Some(option.clone()), None)),
+        Type::Measurement(measurement) => readings.push(reading(
+            "measurement",
+            Some(measurement.value),
+            None,
+            Some(measurement.unit.clone()),
+        )),
+        Type::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten(
+                    device_id,
+                    nested(&index.to_string()),
+                    value,
+                    recorded_at,
+                    readings,
+                );
+            }
+        }
+        Type::Object(fields) => {
+            for (name, value) in fields {
+                flatten(device_id, nested(name), value, recorded_at, readings);
+            }
+        }
+        // Stored at the time the device measured it instead of when it was read
+        Type::Timestamped(timestamped) => flatten(
+            device_id,
+            channel,
+            &timestamped.value,
+            timestamped.timestamp,
+            readings,
+        ),
+        Type::Stream | Type::None => {}
+    }
+}
+
+/// Periodically applies the retention and downsampling configured for
+/// readings.
+pub(crate) fn start_maintenance(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = maintain(&state).await {
+                sentry::capture_error(&e);
+                tracing::error!("Reading maintenance failed: {:?}", e);
+            }
+            tokio::time::sleep(MAINTENANCE_INTERVAL).await;
+        }
+    });
+}
+
+async fn maintain(state: &AppState) -> database::Result<()> {
+    let config = &state.config;
+    let now = Utc::now();
+
+    let retention_cutoff = now - TimeDelta::days(config.reading_retention_days.into());
+    let deleted = Reading::delete_before(retention_cutoff, &state.pool).await?;
+
+    // Aligned to the buckets so no bucket is split into a raw and a downsampled part
+    let bucket = TimeDelta::seconds(config.reading_downsample_secs.max(1).into());
+    let raw_cutoff = (now - TimeDelta::days(config.reading_raw_days.into()))
+        .duration_trunc(bucket)
+        .unwrap_or(now);
+    let downsampled =
+        Reading::downsample_before(raw_cutoff, bucket.as_seconds_f64(), &state.pool).await?;
+
+    tracing::info!("Deleted {deleted} expired readings, downsampled into {downsampled} readings");
+    Ok(())
+}
@@ -1,34 +1,64 @@
 use crate::{
-    AppState,
-    database::device::Device,
+    AppState, Pool,
+    database::{
+        availability::{AvailabilityChange, DeviceAvailability},
+        device::Device,
+        write::DeviceWrite,
+        zone::{Zone, subtree},
+    },
+    handshake, readings,
     router::{
-        error::HttpResult,
+        error::{Error, HttpResult, Result},
         prom_service::{get_device_query_timeseries, request_device_query_operations},
+        reading_service::{get_device_reading_channels, get_device_readings},
         service::{
-            request_device_activate, request_device_config, request_device_config_update,
-            request_device_status, request_device_token,
+            activate_scripting, decommission_device, forget_input_type, handshake_device,
+            negotiate_protocol, proxy_device_request, request_delete_alerts, request_device_config,
+            request_device_config_update, request_device_logs, request_device_status,
+            require_capability, secure_device, write_to_device,
         },
     },
+    scrape_service,
+    tunnel::{self, tunnel_handler},
 };
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
     http::{HeaderValue, StatusCode, header},
     response::IntoResponse,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
 };
+use chrono::Utc;
 use greenhouse_core::{
     device_service_dto::{
-        endpoints::{ACTIVATE, CONFIG, STATUS},
-        get_device::{DeviceResponseDto, DevicesResponseDto},
+        availability::AvailabilityHistoryDto,
+        delete_device::DeleteDeviceQuery,
+        endpoints::{
+            ACTIONS, ACTIVATE, AVAILABILITY, CALIBRATION, CONFIG, LOGS, READINGS, RESTORE, STATUS,
+            TUNNEL, WRITE, WRITES,
+        },
+        get_device::{AssetDto, DeviceResponseDto, DevicesQuery, DevicesResponseDto},
         get_timeseries::GetTimeseriesDto,
         operations::OperationsDto,
         post_device::PostDeviceDtoRequest,
         put_device::PutDeviceDtoRequest,
         query::PromQuery,
+        readings::IngestReadingsDto,
+        tunnel::TunnelTokenResponseDto,
+        write::{WriteAuditsDto, WriteQuery},
+    },
+    smart_device_dto::{
+        action::ActionRequestDto,
+        calibration::{CalibrationReferenceRequestDto, CalibrationRequestDto},
+        config::ValidationErrors,
+        endpoints as device_endpoints,
+        logs::LogsQuery,
+        version::Capability,
+        write::WriteRequestDto,
     },
-    smart_device_dto::activation::ActivateRequestDto,
 };
+use reqwest::Method;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 pub(crate) fn routes(state: AppState) -> Router {
@@ -37,10 +67,39 @@ pub(crate) fn routes(state: AppState) -> Router {
         .route("/", get(get_devices))
         .route("/{id}", put(update_device))
         .route("/{id}", get(get_device))
+        .route("/{id}", delete(delete_device))
+        .route(&format!("/{{id}}/{RESTORE}"), post(restore_device))
         .route(&format!("/{{id}}/{ACTIVATE}"), put(activate_device))
         .route(&format!("/{{id}}/{CONFIG}"), put(update_device_config))
         .route(&format!("/{{id}}/{CONFIG}"), get(get_device_config))
         .route(&format!("/{{id}}/{STATUS}"), get(get_device_status))
+        .route(
+            &format!("/{{id}}/{AVAILABILITY}"),
+            get(get_device_availability),
+        )
+        .route(&format!("/{{id}}/{LOGS}"), get(get_device_logs))
+        .route(&format!("/{{id}}/{WRITE}"), post(write_device))
+        .route(&format!("/{{id}}/{WRITES}"), get(get_device_writes))
+        .route(
+            &format!("/{{id}}/{CALIBRATION}"),
+            get(get_device_calibration),
+        )
+        .route(
+            &format!("/{{id}}/{CALIBRATION}/{{channel}}"),
+            put(update_device_calibration).delete(delete_device_calibration),
+        )
+        .route(
+            &format!("/{{id}}/{CALIBRATION}/{{channel}}/reference"),
+            post(reference_device_calibration),
+        )
+        .route(&format!("/{{id}}/{ACTIONS}"), get(get_device_actions))
+        .route(
+            &format!("/{{id}}/{ACTIONS}/{{name}}"),
+            post(invoke_device_action),
+        )
+        .route(&format!("/{{id}}/{TUNNEL}"), post(issue_tunnel_token))
+        .route(&format!("/{TUNNEL}"), get(tunnel_handler))
+        .route(&format!("/{{id}}/{READINGS}"), post(ingest_device_readings))
         .route("/{id}/timeseries", get(get_device_timeseries))
         .route("/{id}/options", get(get_device_query_operations))
         .with_state(state)
@@ -53,14 +112,35 @@ pub(crate) async fn update_device(
     Json(update): Json<PutDeviceDtoRequest>,
 ) -> HttpResult<DeviceResponseDto> {
     let mut entry = Device::find_by_id(id, &pool).await?;
+    entry
+        .set_scrape_schedule(update.scrape_interval, update.scrape_timeout)
+        .map_err(Error::InvalidDevice)?;
+    if entry.address != update.address {
+        // Another device may answer at the new address
+        entry.protocol_version = None;
+        entry.capabilities.clear();
+        forget_input_type(entry.id);
+    }
     entry.name = update.name.clone();
     entry.description = update.description.clone();
     entry.address = update.address.clone();
     entry.canscript = update.can_script;
     entry.scraping = update.scraping;
+    place_device(
+        &mut entry,
+        update.zone_id,
+        update.tags.as_deref(),
+        update.asset.as_ref(),
+        &pool,
+    )
+    .await?;
+    if entry.protocol_version.is_none() {
+        handshake_or_postpone(&mut entry).await?;
+    }
     entry.flush(&pool).await?;
+    scrape_service::devices_changed();
 
-    Ok(entry.into())
+    Ok(with_availability(entry, &pool).await?)
 }
 
 #[axum::debug_handler]
@@ -75,26 +155,156 @@ pub(crate) async fn create_device(
         entry.can_script,
         entry.scraping,
     );
+    device
+        .set_scrape_schedule(entry.scrape_interval, entry.scrape_timeout)
+        .map_err(Error::InvalidDevice)?;
+    place_device(
+        &mut device,
+        Some(entry.zone_id),
+        Some(&entry.tags),
+        Some(&entry.asset),
+        &pool,
+    )
+    .await?;
+    handshake_or_postpone(&mut device).await?;
     device.flush(&pool).await?;
+    scrape_service::devices_changed();
+    // Secured first so the scripting token only travels encrypted
+    if let Err(e) = secure_device(&mut device, &pool).await {
+        tracing::warn!(
+            "Device {} could not be secured, activate it once it is reachable: {:?}",
+            device.id,
+            e
+        );
+        return Ok(device.into());
+    }
 
-    let _ = request_device_activate(
-        &entry.address,
-        ActivateRequestDto {
-            url: config.scripting_api.clone(),
-            token: request_device_token(&config.scripting_service).await?.token,
-        },
-    )
-    .await;
+    match activate_scripting(&mut device, &config, &pool).await {
+        // The device is still created if it can't be activated
+        Err(Error::SmartDeviceNotReachable | Error::SmartDeviceResponse) | Ok(()) => {}
+        Err(e) => return Err(e.into()),
+    }
 
     Ok(device.into())
 }
 
+/// Runs the version handshake. Unreachable devices are still saved and the
+/// handshake is retried in the background, other failures are returned.
+async fn handshake_or_postpone(device: &mut Device) -> Result<()> {
+    match handshake_device(device).await {
+        Err(Error::SmartDeviceNotReachable) => {
+            handshake::report(device.id, Err(Error::SmartDeviceNotReachable));
+            Ok(())
+        }
+        result => result,
+    }
+}
+
 #[axum::debug_handler]
 pub(crate) async fn get_device(
     State(AppState { config: _, pool }): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> HttpResult<DeviceResponseDto> {
-    Ok(Device::find_by_id(id, &pool).await?.into())
+    Ok(with_availability(Device::find_by_id(id, &pool).await?, &pool).await?)
+}
+
+/// Revokes the scripting key of the device and makes it forget the scripting
+/// api. The device is soft deleted and can be restored, unless `purge` is set
+/// which also removes its readings and alerts.
+#[axum::debug_handler]
+pub(crate) async fn delete_device(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteDeviceQuery>,
+) -> HttpResult<StatusCode> {
+    let mut device = Device::find_any_by_id(id, &pool).await?;
+    if device.deleted_at.is_none() {
+        decommission_device(&mut device, &config).await?;
+        device.deleted_at = Some(Utc::now());
+        device.flush(&pool).await?;
+        scrape_service::devices_changed();
+    }
+    if query.purge {
+        match &config.data_storage_service {
+            Some(data_storage_service) => {
+                request_delete_alerts(data_storage_service, id).await?;
+            }
+            None => tracing::warn!(
+                "No data storage service configured, alerts of purged device {id} are kept"
+            ),
+        }
+        device.delete(&pool).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Brings back a soft deleted device and activates it with a new scripting
+/// key. A tunnel token has to be issued again.
+#[axum::debug_handler]
+pub(crate) async fn restore_device(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<DeviceResponseDto> {
+    let mut device = Device::find_any_by_id(id, &pool).await?;
+    if device.deleted_at.take().is_some() {
+        device.flush(&pool).await?;
+        scrape_service::devices_changed();
+        if let Err(e) = activate_scripting(&mut device, &config, &pool).await {
+            tracing::warn!("Restored device {} could not be activated: {:?}", id, e);
+        }
+    }
+    Ok(with_availability(device, &pool).await?)
+}
+
+/// Sets the zone, tags and asset metadata of the device that are given, the
+/// others are kept. The zone has to exist.
+async fn place_device(
+    device: &mut Device,
+    zone_id: Option<Option<Uuid>>,
+    tags: Option<&[String]>,
+    asset: Option<&AssetDto>,
+    pool: &Pool,
+) -> Result<()> {
+    if let Some(zone_id) = zone_id {
+        if let Some(zone_id) = zone_id
+            && Zone::find(zone_id, pool).await?.is_none()
+        {
+            let mut errors = ValidationErrors::new();
+            errors.add("zone_id", "unknown zone");
+            return Err(Error::InvalidDevice(errors));
+        }
+        device.zone_id = zone_id;
+    }
+    if let Some(tags) = tags {
+        device.set_tags(tags).map_err(Error::InvalidDevice)?;
+    }
+    if let Some(asset) = asset {
+        device.set_asset(asset);
+    }
+    Ok(())
+}
+
+async fn with_availability(device: Device, pool: &Pool) -> Result<DeviceResponseDto> {
+    let id = device.id;
+    let mut response: DeviceResponseDto = device.into();
+    if let Some(availability) = DeviceAvailability::find(id, pool).await? {
+        availability.apply_to(&mut response);
+    }
+    Ok(response)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_availability(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<AvailabilityHistoryDto> {
+    Device::find_by_id(id, &pool).await?;
+    Ok(AvailabilityChange::find_by_device(id, &pool)
+        .await?
+        .into_iter()
+        .map(|change| change.into())
+        .collect::<Vec<_>>()
+        .into())
 }
 
 #[axum::debug_handler]
@@ -103,7 +313,7 @@ pub(crate) async fn get_device_config(
     Path(id): Path<Uuid>,
 ) -> HttpResult<impl IntoResponse> {
     let device = Device::find_by_id(id, &pool).await?;
-    let response = request_device_config(&device.address).await?;
+    let response = request_device_config(&device).await?;
     Ok((
         StatusCode::OK,
         [(
@@ -121,8 +331,8 @@ pub(crate) async fn update_device_config(
     Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<StatusCode> {
     let device = Device::find_by_id(id, &pool).await?;
-    let _ = request_device_config_update(&device.address, payload).await?;
-    Ok(StatusCode::OK)
+    let (status, _) = request_device_config_update(&device, payload).await?;
+    Ok(status)
 }
 
 #[axum::debug_handler]
@@ -131,7 +341,7 @@ pub(crate) async fn get_device_status(
     Path(id): Path<Uuid>,
 ) -> HttpResult<impl IntoResponse> {
     let device = Device::find_by_id(id, &pool).await?;
-    let response = request_device_status(&device.address).await?;
+    let response = request_device_status(&device).await?;
     Ok((
         StatusCode::OK,
         [(
@@ -142,14 +352,203 @@ pub(crate) async fn get_device_status(
     ))
 }
 
+#[axum::debug_handler]
+pub(crate) async fn get_device_logs(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<LogsQuery>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Logs).await?;
+    let response = request_device_logs(&device, query).await?;
+    Ok((
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        response,
+    ))
+}
+
+/// Checks the value against the input type the device declares, forwards it
+/// and records the write for the `actor` it was done for.
+#[axum::debug_handler]
+pub(crate) async fn write_device(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WriteQuery>,
+    Json(payload): Json<WriteRequestDto>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Write).await?;
+    let (status, response) = write_to_device(&device, payload, query.actor, &pool).await?;
+    Ok((status, Json(response)))
+}
+
+/// Audit trail of the writes to the device, newest first.
+#[axum::debug_handler]
+pub(crate) async fn get_device_writes(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<WriteAuditsDto> {
+    Device::find_any_by_id(id, &pool).await?;
+    Ok(DeviceWrite::find_by_device(id, &pool)
+        .await?
+        .into_iter()
+        .map(|write| write.into())
+        .collect::<Vec<_>>()
+        .into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_calibration(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Calibration).await?;
+    let response = proxy_device_request(
+        &device,
+        Method::GET,
+        device_endpoints::CALIBRATION,
+        None,
+        Error::InvalidCalibration,
+    )
+    .await?;
+    Ok(json_response(response))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_device_calibration(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+    Json(payload): Json<CalibrationRequestDto>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Calibration).await?;
+    let response = proxy_device_request(
+        &device,
+        Method::PUT,
+        &format!("{}/{channel}", device_endpoints::CALIBRATION),
+        Some(serde_json::json!(payload)),
+        Error::InvalidCalibration,
+    )
+    .await?;
+    Ok(json_response(response))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_device_calibration(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+) -> HttpResult<StatusCode> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Calibration).await?;
+    let (status, _) = proxy_device_request(
+        &device,
+        Method::DELETE,
+        &format!("{}/{channel}", device_endpoints::CALIBRATION),
+        None,
+        Error::InvalidCalibration,
+    )
+    .await?;
+    Ok(status)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn reference_device_calibration(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path((id, channel)): Path<(Uuid, String)>,
+    Json(payload): Json<CalibrationReferenceRequestDto>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Calibration).await?;
+    let response = proxy_device_request(
+        &device,
+        Method::POST,
+        &format!("{}/{channel}/reference", device_endpoints::CALIBRATION),
+        Some(serde_json::json!(payload)),
+        Error::InvalidCalibration,
+    )
+    .await?;
+    Ok(json_response(response))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_device_actions(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Actions).await?;
+    let response = proxy_device_request(
+        &device,
+        Method::GET,
+        device_endpoints::ACTIONS,
+        None,
+        Error::InvalidAction,
+    )
+    .await?;
+    Ok(json_response(response))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn invoke_device_action(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path((id, name)): Path<(Uuid, String)>,
+    Json(payload): Json<ActionRequestDto>,
+) -> HttpResult<impl IntoResponse> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    require_capability(&mut device, &pool, Capability::Actions).await?;
+    let response = proxy_device_request(
+        &device,
+        Method::POST,
+        &format!("{}/{name}", device_endpoints::ACTIONS),
+        Some(serde_json::json!(payload)),
+        Error::InvalidAction,
+    )
+    .await?;
+    Ok(json_response(response))
+}
+
+fn json_response((status, body): (StatusCode, String)) -> impl IntoResponse {
+    (
+        status,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        body,
+    )
+}
+
 #[axum::debug_handler]
 pub(crate) async fn get_devices(
     State(AppState { config: _, pool }): State<AppState>,
+    Query(query): Query<DevicesQuery>,
 ) -> HttpResult<DevicesResponseDto> {
-    let entries = Device::all(&pool).await?;
+    let zones = match query.zone {
+        Some(zone) => Some(subtree(zone, &Zone::all(&pool).await?)),
+        None => None,
+    };
+    let entries =
+        Device::filtered(query.deleted, zones.as_deref(), query.tag.as_deref(), &pool).await?;
+    let availabilities = DeviceAvailability::all(&pool)
+        .await?
+        .into_iter()
+        .map(|availability| (availability.device_id, availability))
+        .collect::<HashMap<_, _>>();
     Ok(entries
         .into_iter()
-        .map(|entry| entry.into())
+        .map(|entry| {
+            let id = entry.id;
+            let mut response: DeviceResponseDto = entry.into();
+            if let Some(availability) = availabilities.get(&id) {
+                availability.apply_to(&mut response);
+            }
+            response
+        })
         .collect::<Vec<DeviceResponseDto>>()
         .into())
 }
@@ -159,30 +558,80 @@ pub(crate) async fn activate_device(
     State(AppState { config, pool }): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> HttpResult<StatusCode> {
-    let device = Device::find_by_id(id, &pool).await?;
-    let _ = request_device_activate(
-        &device.address,
-        ActivateRequestDto {
-            url: config.scripting_api.clone(),
-            token: request_device_token(&config.scripting_service).await?.token,
-        },
-    )
-    .await?;
+    let mut device = Device::find_by_id(id, &pool).await?;
+    // Reactivation usually follows a firmware update, which may change the protocol
+    negotiate_protocol(&mut device, &pool).await?;
+    secure_device(&mut device, &pool).await?;
+    activate_scripting(&mut device, &config, &pool).await?;
     Ok(StatusCode::OK)
 }
 
+/// Issues the token the device opens its reverse connection with, replacing
+/// the previous one.
+#[axum::debug_handler]
+pub(crate) async fn issue_tunnel_token(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<TunnelTokenResponseDto> {
+    let mut device = Device::find_by_id(id, &pool).await?;
+    let token = Uuid::new_v4().to_string();
+    device.tunnel_token_hash = Some(tunnel::hash_token(&token));
+    device.flush(&pool).await?;
+    Ok(TunnelTokenResponseDto { token })
+}
+
+/// Stores readings pushed by the device or another service next to the
+/// scraped ones.
+#[axum::debug_handler]
+pub(crate) async fn ingest_device_readings(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<IngestReadingsDto>,
+) -> HttpResult<StatusCode> {
+    let device = Device::find_by_id(id, &pool).await?;
+    let mut readings = payload.readings;
+    // Normalized like scraped reads, but one bad reading rejects the batch
+    let mut errors = ValidationErrors::new();
+    for (index, reading) in readings.iter_mut().enumerate() {
+        for e in reading.data.normalize_units() {
+            errors.add(format!("readings[{index}].data"), e.to_string());
+        }
+    }
+    errors.into_result(()).map_err(Error::InvalidReadings)?;
+    let received_at = Utc::now();
+    for reading in readings {
+        let recorded_at = reading.timestamp.unwrap_or(received_at);
+        readings::ingest(device.id, &reading.data, recorded_at, &pool).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Served from the stored readings. History recorded before they were stored
+/// is still looked up in Prometheus if it is configured.
 #[axum::debug_handler]
 pub(crate) async fn get_device_timeseries(
-    State(AppState { config, pool: _ }): State<AppState>,
+    State(AppState { config, pool }): State<AppState>,
     Path(id): Path<Uuid>,
     Query(query): Query<PromQuery>,
 ) -> HttpResult<GetTimeseriesDto> {
-    Ok(get_device_query_timeseries(&config.prometheus_url, &id.to_string(), query).await?)
+    let timeseries = get_device_readings(id, &query, &pool).await?;
+    match &config.prometheus_url {
+        Some(prometheus_url) if timeseries.timeseries.is_empty() => {
+            Ok(get_device_query_timeseries(prometheus_url, &id.to_string(), query).await?)
+        }
+        _ => Ok(timeseries),
+    }
 }
 
 async fn get_device_query_operations(
-    State(AppState { config, pool: _ }): State<AppState>,
+    State(AppState { config, pool }): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> HttpResult<OperationsDto> {
-    Ok(request_device_query_operations(&config.prometheus_url, &id.to_string()).await?)
+    let channels = get_device_reading_channels(id, &pool).await?;
+    match &config.prometheus_url {
+        Some(prometheus_url) if channels.operations.is_empty() => {
+            Ok(request_device_query_operations(prometheus_url, &id.to_string()).await?)
+        }
+        _ => Ok(channels),
+    }
 }
@@ -4,6 +4,8 @@ use derive_more::From;
 use greenhouse_core::{
     http_error::{HttpErrorMapping, HttpErrorResponse},
     impl_http_error_from,
+    smart_device_dto::{config::ValidationErrors, version::Capability},
+    units,
 };
 pub(crate) type HttpResult<T> = core::result::Result<T, HttpErrorResponse<Error>>;
 pub(crate) type Result<T> = core::result::Result<T, Error>;
@@ -14,10 +16,29 @@ pub(crate) enum Error {
     SmartDeviceResponse,
     ScriptingApiNotReachable,
     ScriptingApiResponse,
+    DataStorageServiceNotReachable,
+    DataStorageServiceResponse,
+    NotificationNotReachable,
+    NotificationResponse,
     Prometheus(reqwest::Error),
     PrometheusJson(reqwest::Error),
     PrometheusInvalidResultType,
     PrometheusNotImplemented,
+    InvalidStep,
+    Unit(units::Error),
+    ConfigValidation(ValidationErrors),
+    InvalidAction(ValidationErrors),
+    InvalidCalibration(ValidationErrors),
+    InvalidDevice(ValidationErrors),
+    InvalidZone(ValidationErrors),
+    InvalidWrite(ValidationErrors),
+    InvalidSchedule(ValidationErrors),
+    InvalidRule(ValidationErrors),
+    InvalidReadings(ValidationErrors),
+    ZoneNotEmpty,
+    UnsupportedByDevice(Capability),
+    Certificate,
+    TunnelUnauthorized,
     #[from]
     Database(database::Error),
 }
@@ -43,14 +64,34 @@ impl HttpErrorMapping for Error {
             Error::Database(e) => match e {
                 database::Error::Creation => StatusCode::INTERNAL_SERVER_ERROR,
                 database::Error::DatabaseConnection => StatusCode::INTERNAL_SERVER_ERROR,
+                database::Error::Deletion => StatusCode::INTERNAL_SERVER_ERROR,
                 database::Error::Find => StatusCode::NOT_FOUND,
             },
             Error::ScriptingApiNotReachable => StatusCode::SERVICE_UNAVAILABLE,
             Error::ScriptingApiResponse => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::DataStorageServiceNotReachable => StatusCode::SERVICE_UNAVAILABLE,
+            Error::DataStorageServiceResponse => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::NotificationNotReachable => StatusCode::SERVICE_UNAVAILABLE,
+            Error::NotificationResponse => StatusCode::INTERNAL_SERVER_ERROR,
             Error::Prometheus(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Error::PrometheusJson(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Error::PrometheusInvalidResultType => StatusCode::BAD_REQUEST,
             Error::PrometheusNotImplemented => StatusCode::NOT_IMPLEMENTED,
+            Error::InvalidStep => StatusCode::BAD_REQUEST,
+            Error::Unit(_) => StatusCode::BAD_REQUEST,
+            Error::ConfigValidation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidAction(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidCalibration(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidDevice(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidZone(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidWrite(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidSchedule(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidRule(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidReadings(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::ZoneNotEmpty => StatusCode::CONFLICT,
+            Error::UnsupportedByDevice(_) => StatusCode::NOT_IMPLEMENTED,
+            Error::Certificate => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::TunnelUnauthorized => StatusCode::UNAUTHORIZED,
         }
     }
 
@@ -61,14 +102,55 @@ impl HttpErrorMapping for Error {
             Error::Database(e) => match e {
                 database::Error::Creation => String::from("Database creation error"),
                 database::Error::DatabaseConnection => String::from("Database connection error"),
+                database::Error::Deletion => String::from("Database deletion error"),
                 database::Error::Find => String::from("Database find error"),
             },
             Error::ScriptingApiNotReachable => String::from("Scripting api not reachable"),
             Error::ScriptingApiResponse => String::from("Scripting api response error"),
+            Error::DataStorageServiceNotReachable => {
+                String::from("Data storage service not reachable")
+            }
+            Error::DataStorageServiceResponse => {
+                String::from("Data storage service response error")
+            }
+            Error::NotificationNotReachable => String::from("Notification url not reachable"),
+            Error::NotificationResponse => String::from("Notification url response error"),
             Error::Prometheus(e) => format!("Prometheus error: {e}"),
             Error::PrometheusJson(e) => format!("Prometheus json error: {e}"),
             Error::PrometheusInvalidResultType => String::from("Prometheus invalid result type"),
             Error::PrometheusNotImplemented => String::from("Prometheus type not implemented"),
+            Error::InvalidStep => String::from("Invalid step, expected e.g. 15s, 5m, 1h or 1d"),
+            Error::Unit(e) => e.to_string(),
+            Error::ConfigValidation(_) => String::from("Invalid config"),
+            Error::InvalidAction(_) => String::from("Invalid action parameters"),
+            Error::InvalidCalibration(_) => String::from("Invalid calibration"),
+            Error::InvalidDevice(_) => String::from("Invalid device"),
+            Error::InvalidZone(_) => String::from("Invalid zone"),
+            Error::InvalidWrite(_) => String::from("Invalid write"),
+            Error::InvalidSchedule(_) => String::from("Invalid schedule"),
+            Error::InvalidRule(_) => String::from("Invalid rule"),
+            Error::InvalidReadings(_) => String::from("Invalid readings"),
+            Error::ZoneNotEmpty => String::from("Zone still holds other zones"),
+            Error::UnsupportedByDevice(capability) => {
+                format!("Device does not support {capability:?}")
+            }
+            Error::Certificate => String::from("Device certificate could not be issued"),
+            Error::TunnelUnauthorized => String::from("Invalid tunnel token"),
+        }
+    }
+
+    fn to_error_context(&self) -> Option<serde_json::Value> {
+        match self {
+            Error::ConfigValidation(errors)
+            | Error::InvalidAction(errors)
+            | Error::InvalidCalibration(errors)
+            | Error::InvalidDevice(errors)
+            | Error::InvalidZone(errors)
+            | Error::InvalidWrite(errors)
+            | Error::InvalidSchedule(errors)
+            | Error::InvalidRule(errors)
+            | Error::InvalidReadings(errors) => serde_json::to_value(errors).ok(),
+            _ => None,
         }
     }
 }
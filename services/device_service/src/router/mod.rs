@@ -1,4 +1,8 @@
 pub(crate) mod device_router;
-mod error;
+pub(crate) mod error;
 pub(crate) mod prom_service;
+pub(crate) mod reading_service;
+pub(crate) mod rule_router;
+pub(crate) mod schedule_router;
 pub(crate) mod service;
+pub(crate) mod zone_router;
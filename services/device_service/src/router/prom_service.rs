@@ -1,8 +1,11 @@
 use super::error::{Error, Result};
-use greenhouse_core::device_service_dto::{
-    get_timeseries::{GetTimeseriesDto, Measurement, TimeseriesDto, Type},
-    operations::OperationsDto,
-    query::PromQuery,
+use greenhouse_core::{
+    device_service_dto::{
+        get_timeseries::{GetTimeseriesDto, Measurement, TimeseriesDto, Type},
+        operations::OperationsDto,
+        query::PromQuery,
+    },
+    units::Unit,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -64,6 +67,13 @@ pub(crate) async fn get_device_query_timeseries(
         format!("scrape_service_duration_{id}")
     };
 
+    let target_unit = query
+        .unit
+        .as_deref()
+        .map(str::parse::<Unit>)
+        .transpose()
+        .map_err(Error::Unit)?;
+
     // Convert chrono DateTime<Utc> to unix seconds
     let start = query.start.timestamp();
     let end = query.end.timestamp();
@@ -96,13 +106,14 @@ pub(crate) async fn get_device_query_timeseries(
         .await
         .map_err(Error::PrometheusJson)?;
 
-    match resp.data.result[0]
+    let metric_type = resp.data.result[0]
         .metric
         .get("type")
-        .unwrap_or(&String::from("unknown"))
-        .as_str()
-    {
-        "number" => {
+        .cloned()
+        .unwrap_or(String::from("unknown"));
+
+    match metric_type.as_str() {
+        "number" | "timestamp" => {
             let timeseries = resp.data.result[0]
                 .values
                 .iter()
@@ -137,17 +148,51 @@ pub(crate) async fn get_device_query_timeseries(
             let timeseries = resp.data.result[0]
                 .values
                 .iter()
-                .map(|result| TimeseriesDto {
-                    timestamp: result.0,
-                    value: Type::Measurement(Measurement {
+                .map(|result| {
+                    let measurement = Measurement {
                         value: result.1.parse::<f64>().unwrap(),
                         unit: unit.to_string(),
-                    }),
+                    };
+                    let measurement = match target_unit {
+                        Some(target_unit) => {
+                            measurement.convert_to(target_unit).map_err(Error::Unit)?
+                        }
+                        None => measurement,
+                    };
+                    Ok(TimeseriesDto {
+                        timestamp: result.0,
+                        value: Type::Measurement(measurement),
+                    })
                 })
-                .collect::<Vec<TimeseriesDto>>()
+                .collect::<Result<Vec<TimeseriesDto>>>()?
                 .into();
             Ok(timeseries)
         }
+        "text" | "enum" => {
+            // Every state is its own series, a sample of 1 marks the state as active
+            let mut timeseries = resp
+                .data
+                .result
+                .iter()
+                .flat_map(|series| {
+                    let state = series.metric.get("value").cloned().unwrap_or_default();
+                    let metric_type = metric_type.as_str();
+                    series
+                        .values
+                        .iter()
+                        .filter(|result| result.1 == "1")
+                        .map(move |result| TimeseriesDto {
+                            timestamp: result.0,
+                            value: match metric_type {
+                                "text" => Type::Text(state.clone()),
+                                _ => Type::Enum(state.clone()),
+                            },
+                        })
+                })
+                .collect::<Vec<TimeseriesDto>>();
+            timeseries.sort_by_key(|entry| entry.timestamp);
+            Ok(timeseries.into())
+        }
         _ => {
             tracing::error!("Prometheus invalid result type: {}", resp.data.result_type);
             Err(Error::PrometheusInvalidResultType)
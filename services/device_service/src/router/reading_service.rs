@@ -0,0 +1,122 @@
+use super::error::{Error, Result};
+use crate::{Pool, database::reading::Reading};
+use greenhouse_core::{
+    device_service_dto::{
+        get_timeseries::{GetTimeseriesDto, Measurement, TimeseriesDto, Type},
+        operations::OperationsDto,
+        query::PromQuery,
+    },
+    units::Unit,
+};
+use uuid::Uuid;
+
+/// Readings of a channel between `start` and `end`. With a `step` they are
+/// aggregated per step like the readings downsampled by the maintenance.
+pub(crate) async fn get_device_readings(
+    id: Uuid,
+    query: &PromQuery,
+    pool: &Pool,
+) -> Result<GetTimeseriesDto> {
+    let target_unit = query
+        .unit
+        .as_deref()
+        .map(str::parse::<Unit>)
+        .transpose()
+        .map_err(Error::Unit)?;
+    let step = query.step.as_deref().map(parse_step).transpose()?;
+    let channel = query.sub_property.as_deref().unwrap_or_default();
+
+    let readings = Reading::find_range(id, channel, query.start, query.end, pool).await?;
+    let readings = match step {
+        Some(step) => aggregate(readings, step),
+        None => readings,
+    };
+
+    Ok(readings
+        .into_iter()
+        .map(|reading| {
+            let timestamp = reading.recorded_at.timestamp().max(0) as u64;
+            let value = match (reading.value_type.as_str(), reading.number, reading.text) {
+                ("number", Some(number), _) => Type::Number(number),
+                ("boolean", Some(number), _) => Type::Boolean(number != 0.0),
+                ("text", _, Some(text)) => Type::Text(text),
+                ("enum", _, Some(option)) => Type::Enum(option),
+                ("measurement", Some(value), _) => {
+                    let measurement = Measurement {
+                        value,
+                        unit: reading.unit.unwrap_or_default(),
+                    };
+                    Type::Measurement(match target_unit {
+                        Some(target_unit) => {
+                            measurement.convert_to(target_unit).map_err(Error::Unit)?
+                        }
+                        None => measurement,
+                    })
+                }
+                _ => Type::None,
+            };
+            Ok(TimeseriesDto { timestamp, value })
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into())
+}
+
+/// Channels the device has readings for, without the root channel of scalar
+/// reads.
+pub(crate) async fn get_device_reading_channels(id: Uuid, pool: &Pool) -> Result<OperationsDto> {
+    let channels = Reading::channels(id, pool).await?;
+    Ok(channels
+        .into_iter()
+        .filter(|channel| !channel.is_empty())
+        .collect::<Vec<_>>()
+        .into())
+}
+
+/// Parses Prometheus style durations like `15s`, `5m`, `1h` or `1d`, plain
+/// numbers are seconds.
+fn parse_step(step: &str) -> Result<i64> {
+    let (amount, unit) = step.split_at(
+        step.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(step.len()),
+    );
+    let amount: i64 = amount.parse().map_err(|_| Error::InvalidStep)?;
+    let seconds = match unit {
+        "" | "s" => amount,
+        "m" => amount * 60,
+        "h" => amount * 60 * 60,
+        "d" => amount * 60 * 60 * 24,
+        _ => return Err(Error::InvalidStep),
+    };
+    if seconds <= 0 {
+        return Err(Error::InvalidStep);
+    }
+    Ok(seconds)
+}
+
+/// One reading per step, stamped with the start of the step. Numbers and
+/// measurements are averaged, the other types keep the last value.
+fn aggregate(readings: Vec<Reading>, step: i64) -> Vec<Reading> {
+    let mut aggregated: Vec<(Reading, usize)> = Vec::new();
+    for mut reading in readings {
+        let timestamp = reading.recorded_at.timestamp();
+        let bucket = timestamp - timestamp.rem_euclid(step);
+        reading.recorded_at = chrono::DateTime::from_timestamp(bucket, 0).unwrap_or_default();
+        match aggregated.last_mut() {
+            Some((last, count))
+                if last.recorded_at == reading.recorded_at
+                    && last.value_type == reading.value_type
+                    && last.unit == reading.unit =>
+            {
+                if matches!(reading.value_type.as_str(), "number" | "measurement") {
+                    let sum = last.number.unwrap_or_default() * *count as f64;
+                    *count += 1;
+                    last.number = Some((sum + reading.number.unwrap_or_default()) / *count as f64);
+                } else {
+                    *last = reading;
+                }
+            }
+            _ => aggregated.push((reading, 1)),
+        }
+    }
+    aggregated.into_iter().map(|(reading, _)| reading).collect()
+}
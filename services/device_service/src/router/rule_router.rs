@@ -0,0 +1,151 @@
+use crate::{
+    AppState, Config, Pool,
+    database::{
+        device::Device,
+        rule::{Rule, RuleEvaluation, referenced_devices},
+    },
+    router::error::{Error, HttpResult, Result},
+};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use greenhouse_core::{
+    device_service_dto::{
+        endpoints::{DISABLE, ENABLE, EVALUATIONS},
+        rule::{
+            RuleEvaluationDto, RuleEvaluationsDto, RuleEvaluationsQuery, RuleRequestDto,
+            RuleResponseDto, RulesResponseDto,
+        },
+    },
+    smart_device_dto::config::ValidationErrors,
+};
+use uuid::Uuid;
+
+const DEFAULT_EVALUATIONS: u32 = 100;
+const MAX_EVALUATIONS: u32 = 1000;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_rules).post(create_rule))
+        .route("/{id}", get(get_rule).put(update_rule).delete(delete_rule))
+        .route(&format!("/{{id}}/{ENABLE}"), post(enable_rule))
+        .route(&format!("/{{id}}/{DISABLE}"), post(disable_rule))
+        .route(&format!("/{{id}}/{EVALUATIONS}"), get(get_evaluations))
+        .with_state(state)
+}
+
+/// Applies `request` after checking the devices it refers to exist.
+async fn set_rule(
+    rule: &mut Rule,
+    request: &RuleRequestDto,
+    config: &Config,
+    pool: &Pool,
+) -> Result<()> {
+    let mut errors = ValidationErrors::new();
+    for (field, device_id) in referenced_devices(request) {
+        if Device::find_by_id(device_id, pool).await.is_err() {
+            errors.add(field, "unknown device");
+        }
+    }
+    errors.into_result(()).map_err(Error::InvalidRule)?;
+    rule.set(request, &config.notify_allowlist)
+        .map_err(Error::InvalidRule)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_rules(
+    State(AppState { config: _, pool }): State<AppState>,
+) -> HttpResult<RulesResponseDto> {
+    Ok(Rule::all(&pool)
+        .await?
+        .into_iter()
+        .map(|rule| rule.into_response())
+        .collect::<Vec<RuleResponseDto>>()
+        .into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_rule(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    Ok(Rule::find_by_id(id, &pool).await?.into_response())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_rule(
+    State(AppState { config, pool }): State<AppState>,
+    Json(request): Json<RuleRequestDto>,
+) -> HttpResult<RuleResponseDto> {
+    let mut rule = Rule::new();
+    set_rule(&mut rule, &request, &config, &pool).await?;
+    rule.flush(&pool).await?;
+    Ok(rule.into_response())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn update_rule(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RuleRequestDto>,
+) -> HttpResult<RuleResponseDto> {
+    let mut rule = Rule::find_by_id(id, &pool).await?;
+    set_rule(&mut rule, &request, &config, &pool).await?;
+    rule.flush(&pool).await?;
+    Ok(rule.into_response())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_rule(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    Rule::find_by_id(id, &pool).await?.delete(&pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn enable_rule(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    let mut rule = Rule::find_by_id(id, &pool).await?;
+    rule.enabled = true;
+    rule.flush(&pool).await?;
+    Ok(rule.into_response())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn disable_rule(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<RuleResponseDto> {
+    let mut rule = Rule::find_by_id(id, &pool).await?;
+    rule.enabled = false;
+    rule.flush(&pool).await?;
+    Ok(rule.into_response())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_evaluations(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<RuleEvaluationsQuery>,
+) -> HttpResult<RuleEvaluationsDto> {
+    Rule::find_by_id(id, &pool).await?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_EVALUATIONS)
+        .min(MAX_EVALUATIONS);
+    Ok(
+        RuleEvaluation::find_by_rule(id, query.matched_only, limit.into(), &pool)
+            .await?
+            .into_iter()
+            .map(|evaluation| evaluation.into())
+            .collect::<Vec<RuleEvaluationDto>>()
+            .into(),
+    )
+}
@@ -0,0 +1,175 @@
+use crate::{
+    AppState, Config, Pool,
+    database::{
+        device::Device,
+        schedule::{Schedule, ScheduleExecution},
+    },
+    router::error::{Error, HttpResult, Result},
+    schedules::{schedules_changed, trigger::Trigger},
+};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::Utc;
+use greenhouse_core::{
+    device_service_dto::{
+        endpoints::{DISABLE, ENABLE, EXECUTIONS, NEXT_RUNS},
+        schedule::{
+            NextRunsDto, NextRunsQuery, ScheduleExecutionDto, ScheduleExecutionsDto,
+            ScheduleRequestDto, ScheduleResponseDto, SchedulesResponseDto,
+        },
+    },
+    smart_device_dto::config::ValidationErrors,
+};
+use uuid::Uuid;
+
+const DEFAULT_NEXT_RUNS: u32 = 5;
+const MAX_NEXT_RUNS: u32 = 50;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_schedules).post(create_schedule))
+        .route(
+            "/{id}",
+            get(get_schedule)
+                .put(update_schedule)
+                .delete(delete_schedule),
+        )
+        .route(&format!("/{{id}}/{ENABLE}"), post(enable_schedule))
+        .route(&format!("/{{id}}/{DISABLE}"), post(disable_schedule))
+        .route(&format!("/{{id}}/{NEXT_RUNS}"), get(get_next_runs))
+        .route(&format!("/{{id}}/{EXECUTIONS}"), get(get_executions))
+        .with_state(state)
+}
+
+/// Applies `request` after checking the target device exists.
+async fn set_schedule(
+    schedule: &mut Schedule,
+    request: &ScheduleRequestDto,
+    config: &Config,
+    pool: &Pool,
+) -> Result<()> {
+    if Device::find_by_id(request.device_id, pool).await.is_err() {
+        let mut errors = ValidationErrors::new();
+        errors.add("device_id", "unknown device");
+        return Err(Error::InvalidSchedule(errors));
+    }
+    schedule
+        .set(request, config)
+        .map_err(Error::InvalidSchedule)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_schedules(
+    State(AppState { config, pool }): State<AppState>,
+) -> HttpResult<SchedulesResponseDto> {
+    Ok(Schedule::all(&pool)
+        .await?
+        .into_iter()
+        .map(|schedule| schedule.into_response(&config))
+        .collect::<Vec<ScheduleResponseDto>>()
+        .into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_schedule(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    Ok(Schedule::find_by_id(id, &pool)
+        .await?
+        .into_response(&config))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_schedule(
+    State(AppState { config, pool }): State<AppState>,
+    Json(request): Json<ScheduleRequestDto>,
+) -> HttpResult<ScheduleResponseDto> {
+    let mut schedule = Schedule::new(request.device_id);
+    set_schedule(&mut schedule, &request, &config, &pool).await?;
+    schedule.flush(&pool).await?;
+    schedules_changed();
+    Ok(schedule.into_response(&config))
+}
+
+/// Runs that came due under the previous trigger are not caught up.
+#[axum::debug_handler]
+pub(crate) async fn update_schedule(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ScheduleRequestDto>,
+) -> HttpResult<ScheduleResponseDto> {
+    let mut schedule = Schedule::find_by_id(id, &pool).await?;
+    set_schedule(&mut schedule, &request, &config, &pool).await?;
+    schedule.flush(&pool).await?;
+    schedules_changed();
+    Ok(schedule.into_response(&config))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn delete_schedule(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    Schedule::find_by_id(id, &pool).await?.delete(&pool).await?;
+    schedules_changed();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Runs missed while the schedule was disabled are not caught up.
+#[axum::debug_handler]
+pub(crate) async fn enable_schedule(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    let mut schedule = Schedule::find_by_id(id, &pool).await?;
+    schedule.set_enabled(true);
+    schedule.flush(&pool).await?;
+    schedules_changed();
+    Ok(schedule.into_response(&config))
+}
+
+#[axum::debug_handler]
+pub(crate) async fn disable_schedule(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleResponseDto> {
+    let mut schedule = Schedule::find_by_id(id, &pool).await?;
+    schedule.set_enabled(false);
+    schedule.flush(&pool).await?;
+    schedules_changed();
+    Ok(schedule.into_response(&config))
+}
+
+/// Previews when the schedule runs next, also while it is disabled.
+#[axum::debug_handler]
+pub(crate) async fn get_next_runs(
+    State(AppState { config, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<NextRunsQuery>,
+) -> HttpResult<NextRunsDto> {
+    let schedule = Schedule::find_by_id(id, &pool).await?;
+    let count = query.count.unwrap_or(DEFAULT_NEXT_RUNS).min(MAX_NEXT_RUNS);
+    let after = schedule.handled_until.max(Utc::now());
+    Ok(Trigger::new(&schedule.trigger(), &config)
+        .upcoming(after, count as usize)
+        .into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_executions(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ScheduleExecutionsDto> {
+    Schedule::find_by_id(id, &pool).await?;
+    Ok(ScheduleExecution::find_by_schedule(id, &pool)
+        .await?
+        .into_iter()
+        .map(|execution| execution.into())
+        .collect::<Vec<ScheduleExecutionDto>>()
+        .into())
+}
@@ -1,13 +1,214 @@
 use super::error::{Error, Result};
+use crate::{
+    Config, Pool,
+    database::{device::Device, write::DeviceWrite},
+    scrape_service,
+    tls::{self, device_client},
+    tunnel::{self, DeviceRequest},
+};
 use greenhouse_core::{
-    scripting_service_dto::{self, token::TokenDto},
-    smart_device_dto::{activation::ActivateRequestDto, endpoints},
+    data_storage_service_dto::alert_dto::{
+        endpoints::ALERT, post_create_alert::CreateAlertDto, query::DeleteAlertsQuery,
+    },
+    device_service_dto::rule::RuleNotificationDto,
+    http_error::HttpErrorMapping,
+    scripting_service_dto::{
+        self,
+        token::{ScriptingKeyRequestDto, TokenDto},
+    },
+    smart_device_dto::{
+        Type,
+        activation::ActivateRequestDto,
+        certificate::{CertificateDto, CertificateSigningRequestDto},
+        config::{ConfigResponseDto, TypeOption, ValidationErrors},
+        encoding::Encoding,
+        endpoints,
+        logs::LogsQuery,
+        version::{Capability, VersionResponseDto},
+        write::{WriteRequestDto, WriteResponseDto, WriteStatus},
+    },
+};
+use reqwest::{Method, StatusCode, header};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
 };
+use uuid::Uuid;
 
-pub(crate) async fn request_device_config(device_address: &str) -> Result<String> {
-    let resp = reqwest::Client::new()
-        .get(device_address.to_string() + endpoints::CONFIG)
-        .send()
+/// How often and how long to wait for a device to restart on HTTPS.
+const TLS_RESTART_ATTEMPTS: u32 = 10;
+const TLS_RESTART_DELAY: Duration = Duration::from_millis(500);
+/// How long notification urls get to answer.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the input type a device declares is used before its config is
+/// fetched again.
+const INPUT_TYPE_TTL: Duration = Duration::from_secs(60);
+
+/// Input type declared in a device's config, `None` for devices without a
+/// config, which aren't checked.
+type DeclaredInput = Option<Option<TypeOption>>;
+
+/// Declared input type of each device, with when it was fetched.
+static INPUT_TYPES: LazyLock<Mutex<HashMap<Uuid, (Instant, DeclaredInput)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Asks the device for its protocol version. Devices predating the handshake
+/// answer 404 and are treated as the legacy protocol.
+pub(crate) async fn request_device_version(device: &Device) -> Result<VersionResponseDto> {
+    let resp = device_client()
+        .get(device.address.to_string() + endpoints::VERSION)
+        // Creating or updating a device waits for the handshake
+        .timeout(Duration::from_secs(4))
+        .send_to_device(device.id)
+        .await
+        .map_err(|e| {
+            // Offline devices are retried in the background, callers report this
+            tracing::debug!(
+                "Error in get to smart device for version: {:?} for url {}",
+                e,
+                device.address
+            );
+
+            Error::SmartDeviceNotReachable
+        })?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(VersionResponseDto::legacy());
+    }
+    resp.json::<VersionResponseDto>().await.map_err(|e| {
+        sentry::capture_error(&e);
+
+        tracing::error!("Error in version response from smart device: {:?}", e);
+
+        Error::SmartDeviceResponse
+    })
+}
+
+/// Runs the version handshake with the device, without storing the result.
+pub(crate) async fn handshake_device(device: &mut Device) -> Result<()> {
+    let version = request_device_version(device).await?;
+    tracing::debug!(
+        "Device {} speaks protocol version {}",
+        device.id,
+        version.protocol_version
+    );
+    device.set_version(&version);
+    Ok(())
+}
+
+/// Runs the version handshake with the device and stores the result.
+pub(crate) async fn negotiate_protocol(device: &mut Device, pool: &Pool) -> Result<()> {
+    handshake_device(device).await?;
+    device.flush(pool).await?;
+    scrape_service::devices_changed();
+    Ok(())
+}
+
+/// Fails with `UnsupportedByDevice` unless the negotiated protocol of the
+/// device includes `capability`. Runs the handshake if it hasn't happened yet.
+pub(crate) async fn require_capability(
+    device: &mut Device,
+    pool: &Pool,
+    capability: Capability,
+) -> Result<()> {
+    if device.protocol_version.is_none() {
+        negotiate_protocol(device, pool).await?;
+    }
+    if device.supports(capability) {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedByDevice(capability))
+    }
+}
+
+/// Issues the device a certificate from the device CA and switches its
+/// address to HTTPS. Devices without `Capability::Tls` keep plain HTTP.
+/// Called on every activation, so reactivating renews the certificate.
+pub(crate) async fn secure_device(device: &mut Device, pool: &Pool) -> Result<()> {
+    if !device.supports(Capability::Tls) {
+        return Ok(());
+    }
+    let request = request_device_certificate_request(device).await?;
+    let certificate = tls::issue_certificate(&request.csr_pem, device)?;
+    request_device_certificate_install(device, &certificate).await?;
+
+    let mut secured = device.clone();
+    secured.address = device.address.replacen("http://", "https://", 1);
+    // The device restarts its server with the new certificate
+    for _ in 0..TLS_RESTART_ATTEMPTS {
+        tokio::time::sleep(TLS_RESTART_DELAY).await;
+        if request_device_version(&secured).await.is_ok() {
+            tracing::info!("Device {} is served over HTTPS", device.id);
+            device.address = secured.address;
+            device.flush(pool).await?;
+            scrape_service::devices_changed();
+            return Ok(());
+        }
+    }
+    tracing::error!("Device {} did not come up with its certificate", device.id);
+    Err(Error::SmartDeviceNotReachable)
+}
+
+async fn request_device_certificate_request(
+    device: &Device,
+) -> Result<CertificateSigningRequestDto> {
+    let resp = device_client()
+        .get(device.address.to_string() + endpoints::CERTIFICATE)
+        .send_to_device(device.id)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!(
+                "Error in get to smart device for certificate request: {:?} for url {}",
+                e,
+                device.address
+            );
+
+            Error::SmartDeviceNotReachable
+        })?;
+    resp.json::<CertificateSigningRequestDto>()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in certificate request from smart device: {:?}", e);
+
+            Error::SmartDeviceResponse
+        })
+}
+
+async fn request_device_certificate_install(
+    device: &Device,
+    certificate: &CertificateDto,
+) -> Result<()> {
+    let resp = device_client()
+        .put(device.address.to_string() + endpoints::CERTIFICATE)
+        .json(certificate)
+        .send_to_device(device.id)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in certificate install on smart device: {:?}", e);
+
+            Error::SmartDeviceNotReachable
+        })?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        tracing::error!(
+            "Smart device refused its certificate with status {}",
+            resp.status()
+        );
+        Err(Error::SmartDeviceResponse)
+    }
+}
+
+pub(crate) async fn request_device_config(device: &Device) -> Result<String> {
+    let resp = device_client()
+        .get(device.address.to_string() + endpoints::CONFIG)
+        .send_to_device(device.id)
         .await
         .map_err(|e| {
             sentry::capture_error(&e);
@@ -15,7 +216,7 @@ pub(crate) async fn request_device_config(device_address: &str) -> Result<String
             tracing::error!(
                 "Error in get to smart device: {:?} for url {}",
                 e,
-                device_address
+                device.address
             );
 
             Error::SmartDeviceNotReachable
@@ -30,21 +231,33 @@ pub(crate) async fn request_device_config(device_address: &str) -> Result<String
 }
 
 pub(crate) async fn request_device_config_update(
-    device_address: &str,
+    device: &Device,
     body: serde_json::Value,
-) -> Result<String> {
-    let resp = reqwest::Client::new()
-        .post(device_address.to_string() + endpoints::CONFIG)
-        .json(&body)
-        .send()
+) -> Result<(StatusCode, String)> {
+    // The update may change the input type, even if it is refused halfway
+    forget_input_type(device.id);
+    proxy_device_request(
+        device,
+        Method::POST,
+        endpoints::CONFIG,
+        Some(body),
+        Error::ConfigValidation,
+    )
+    .await
+}
+
+pub(crate) async fn request_device_status(device: &Device) -> Result<String> {
+    let resp = device_client()
+        .get(device.address.to_string() + endpoints::STATUS)
+        .send_to_device(device.id)
         .await
         .map_err(|e| {
             sentry::capture_error(&e);
 
             tracing::error!(
-                "Error in post to smart device for config update: {:?} for url {}",
+                "Error in get to smart device: {:?} for url {}",
                 e,
-                device_address
+                device.address
             );
 
             Error::SmartDeviceNotReachable
@@ -58,18 +271,19 @@ pub(crate) async fn request_device_config_update(
     })
 }
 
-pub(crate) async fn request_device_status(device_address: &str) -> Result<String> {
-    let resp = reqwest::Client::new()
-        .get(device_address.to_string() + endpoints::STATUS)
-        .send()
+pub(crate) async fn request_device_logs(device: &Device, query: LogsQuery) -> Result<String> {
+    let resp = device_client()
+        .get(device.address.to_string() + endpoints::LOGS)
+        .query(&query)
+        .send_to_device(device.id)
         .await
         .map_err(|e| {
             sentry::capture_error(&e);
 
             tracing::error!(
-                "Error in get to smart device: {:?} for url {}",
+                "Error in get to smart device for logs: {:?} for url {}",
                 e,
-                device_address
+                device.address
             );
 
             Error::SmartDeviceNotReachable
@@ -83,14 +297,253 @@ pub(crate) async fn request_device_status(device_address: &str) -> Result<String
     })
 }
 
+/// Rejects values that don't match the input type the device declares in its
+/// config. Devices without a config declare nothing and aren't checked, nor
+/// are writes to devices whose config can't be fetched, as the device checks
+/// the value itself.
+async fn validate_device_write(device: &Device, data: &Type) -> Result<()> {
+    let input_type = match declared_input_type(device).await {
+        Ok(Some(input_type)) => input_type,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            tracing::warn!(
+                "Config of device {} unavailable, write is not checked: {:?}",
+                device.id,
+                e
+            );
+            return Ok(());
+        }
+    };
+    let mut errors = ValidationErrors::new();
+    match input_type {
+        None => errors.add("data", "device does not accept writes"),
+        Some(input_type) if !input_type.accepts(data) => {
+            errors.add("data", format!("device expects {input_type:?}"))
+        }
+        Some(_) => {}
+    }
+    errors.into_result(()).map_err(Error::InvalidWrite)
+}
+
+/// The input type from the device's config, cached for `INPUT_TYPE_TTL` so
+/// writes don't each wait for a config round trip.
+async fn declared_input_type(device: &Device) -> Result<DeclaredInput> {
+    let cached = INPUT_TYPES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&device.id)
+        .filter(|(fetched_at, _)| fetched_at.elapsed() < INPUT_TYPE_TTL)
+        .map(|(_, input_type)| input_type.clone());
+    if let Some(input_type) = cached {
+        return Ok(input_type);
+    }
+    let config = request_device_config(device).await?;
+    let config: Option<ConfigResponseDto<serde_json::Value>> = serde_json::from_str(&config)
+        .map_err(|e| {
+            tracing::error!("Error in config from smart device: {:?}", e);
+
+            Error::SmartDeviceResponse
+        })?;
+    let input_type = config.map(|config| config.input_type);
+    INPUT_TYPES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(device.id, (Instant::now(), input_type.clone()));
+    Ok(input_type)
+}
+
+/// Drops the cached input type, e.g. after the device's config changed.
+pub(crate) fn forget_input_type(device_id: Uuid) {
+    INPUT_TYPES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&device_id);
+}
+
+/// Checks `payload` against the input type the device declares, forwards it
+/// and records it in the audit trail, also when it is rejected or the device
+/// can't be reached.
+pub(crate) async fn write_to_device(
+    device: &Device,
+    mut payload: WriteRequestDto,
+    actor: Option<String>,
+    pool: &Pool,
+) -> Result<(StatusCode, WriteResponseDto)> {
+    if payload.idempotency_key.is_none() {
+        payload.idempotency_key = Some(Uuid::new_v4().to_string());
+    }
+    let written = match validate_device_write(device, &payload.data).await {
+        Ok(()) => request_device_write(device, &payload).await,
+        Err(e) => Err(e),
+    };
+    let (status, response) = match written {
+        Ok(written) => written,
+        Err(e) => {
+            let response = match &e {
+                Error::InvalidWrite(errors) => WriteResponseDto::rejected(errors.to_string()),
+                e => WriteResponseDto::failed(e.to_error_message()),
+            };
+            DeviceWrite::new(device.id, actor, &payload, &response)
+                .insert(pool)
+                .await?;
+            return Err(e);
+        }
+    };
+    DeviceWrite::new(device.id, actor, &payload, &response)
+        .insert(pool)
+        .await?;
+    Ok((status, response))
+}
+
+/// Writes `data` on behalf of a schedule or rule, checked like writes
+/// through the API.
+pub(crate) async fn write_device_value(
+    device_id: Uuid,
+    data: Type,
+    idempotency_key: Option<String>,
+    actor: String,
+    pool: &Pool,
+) -> Result<WriteResponseDto> {
+    let mut device = Device::find_by_id(device_id, pool).await?;
+    require_capability(&mut device, pool, Capability::Write).await?;
+    let payload = WriteRequestDto {
+        data,
+        idempotency_key,
+    };
+    let (_, response) = write_to_device(&device, payload, Some(actor), pool).await?;
+    Ok(response)
+}
+
+pub(crate) async fn request_device_write(
+    device: &Device,
+    body: &WriteRequestDto,
+) -> Result<(StatusCode, WriteResponseDto)> {
+    let encoding = device.encoding();
+    let body = encoding.encode(body).map_err(|e| {
+        tracing::error!("Error encoding write for smart device: {:?}", e);
+
+        Error::SmartDeviceResponse
+    })?;
+    let resp = device_client()
+        .post(device.address.to_string() + endpoints::WRITE)
+        .header(header::CONTENT_TYPE, encoding.content_type())
+        .header(header::ACCEPT, encoding.content_type())
+        .body(body)
+        .send_to_device(device.id)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!(
+                "Error in post to smart device for write: {:?} for url {}",
+                e,
+                device.address
+            );
+
+            Error::SmartDeviceNotReachable
+        })?;
+    let status = resp.status();
+    if !device.supports(Capability::WriteAcknowledgement) {
+        return Ok((status, legacy_write_response(status)));
+    }
+    let encoding = response_encoding(&resp);
+    let bytes = resp.bytes().await.map_err(|e| {
+        sentry::capture_error(&e);
+
+        tracing::error!("Error in write response from smart device: {:?}", e);
+
+        Error::SmartDeviceResponse
+    })?;
+    let response = encoding.decode::<WriteResponseDto>(&bytes).map_err(|e| {
+        tracing::error!("Error in write response from smart device: {:?}", e);
+
+        Error::SmartDeviceResponse
+    })?;
+    Ok((status, response))
+}
+
+/// Encoding the device picked for its response, JSON unless it says otherwise.
+pub(crate) fn response_encoding(resp: &reqwest::Response) -> Encoding {
+    resp.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(Encoding::from_content_type)
+        .unwrap_or_default()
+}
+
+/// Devices of protocol version 1 answer writes with a bare status code.
+fn legacy_write_response(status: StatusCode) -> WriteResponseDto {
+    if status.is_success() {
+        WriteResponseDto {
+            status: WriteStatus::Applied,
+            applied: None,
+            state: None,
+            error: None,
+            idempotency_key: None,
+        }
+    } else if status.is_client_error() {
+        WriteResponseDto::rejected(format!("Device rejected the write with {status}"))
+    } else {
+        WriteResponseDto::failed(format!("Device failed the write with {status}"))
+    }
+}
+
+/// Forwards a request to the smart device, keeping the status code so missing
+/// channels or actions and rejected requests reach the caller unchanged. Field
+/// errors the device answers with are mapped through `invalid`, so every
+/// proxied endpoint reports them the same way.
+pub(crate) async fn proxy_device_request(
+    device: &Device,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+    invalid: fn(ValidationErrors) -> Error,
+) -> Result<(StatusCode, String)> {
+    let mut request = device_client().request(method, device.address.to_string() + path);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let resp = request.send_to_device(device.id).await.map_err(|e| {
+        sentry::capture_error(&e);
+
+        tracing::error!(
+            "Error in request to smart device: {:?} for url {}{}",
+            e,
+            device.address,
+            path
+        );
+
+        Error::SmartDeviceNotReachable
+    })?;
+    let status = resp.status();
+    if status == StatusCode::UNPROCESSABLE_ENTITY {
+        let errors = resp.json::<ValidationErrors>().await.map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in validation response from smart device: {:?}", e);
+
+            Error::SmartDeviceResponse
+        })?;
+        return Err(invalid(errors));
+    }
+    let response = resp.text().await.map_err(|e| {
+        sentry::capture_error(&e);
+
+        tracing::error!("Error in response from smart device: {:?}", e);
+
+        Error::SmartDeviceResponse
+    })?;
+    Ok((status, response))
+}
+
 pub(crate) async fn request_device_activate(
-    device_address: &str,
+    device: &Device,
     scripting_api: ActivateRequestDto,
 ) -> Result<String> {
-    let resp = reqwest::Client::new()
-        .post(device_address.to_string() + endpoints::ACTIVATE)
+    let resp = device_client()
+        .post(device.address.to_string() + endpoints::ACTIVATE)
         .json(&scripting_api)
-        .send()
+        .send_to_device(device.id)
         .await
         .map_err(|e| {
             sentry::capture_error(&e);
@@ -99,6 +552,13 @@ pub(crate) async fn request_device_activate(
 
             Error::SmartDeviceNotReachable
         })?;
+    if !resp.status().is_success() {
+        tracing::error!(
+            "Smart device rejected activation with status {}",
+            resp.status()
+        );
+        return Err(Error::SmartDeviceResponse);
+    }
     resp.text().await.map_err(|e| {
         sentry::capture_error(&e);
 
@@ -108,9 +568,100 @@ pub(crate) async fn request_device_activate(
     })
 }
 
-pub(crate) async fn request_device_token(scripting_api_address: &str) -> Result<TokenDto> {
+/// Activates the device with a new scripting key. The key it had before is
+/// revoked once the device took the new one.
+pub(crate) async fn activate_scripting(
+    device: &mut Device,
+    config: &Config,
+    pool: &Pool,
+) -> Result<()> {
+    let token = request_device_token(&config.scripting_service, device.id)
+        .await?
+        .token;
+    let activated = request_device_activate(
+        device,
+        ActivateRequestDto {
+            url: config.scripting_api.clone(),
+            token: token.clone(),
+        },
+    )
+    .await;
+    if let Err(e) = activated {
+        // The device keeps using the key it had, which stays stored
+        if let Err(e) = request_revoke_token(&config.scripting_service, &token).await {
+            tracing::warn!(
+                "Unused scripting key of device {} was not revoked: {:?}",
+                device.id,
+                e
+            );
+        }
+        return Err(e);
+    }
+    let previous = device.scripting_token.replace(token);
+    device.flush(pool).await?;
+    if let Some(previous) = previous
+        && let Err(e) = request_revoke_token(&config.scripting_service, &previous).await
+    {
+        tracing::warn!(
+            "Previous scripting key of device {} was not revoked: {:?}",
+            device.id,
+            e
+        );
+    }
+    Ok(())
+}
+
+/// Cuts the device off before it is deleted: it forgets the scripting api, its
+/// key is revoked and its tunnel is closed. Unreachable devices are only cut
+/// off on our side.
+pub(crate) async fn decommission_device(device: &mut Device, config: &Config) -> Result<()> {
+    if let Err(e) = request_device_deactivate(device).await {
+        tracing::warn!("Device {} could not be deactivated: {:?}", device.id, e);
+    }
+    if let Some(token) = &device.scripting_token {
+        request_revoke_token(&config.scripting_service, token).await?;
+    }
+    device.scripting_token = None;
+    device.tunnel_token_hash = None;
+    tunnel::close(device.id);
+    Ok(())
+}
+
+/// Makes the device forget the scripting api. Devices without
+/// `Capability::Deactivation` are skipped, revoking their token is enough.
+pub(crate) async fn request_device_deactivate(device: &Device) -> Result<()> {
+    if !device.supports(Capability::Deactivation) {
+        return Ok(());
+    }
+    let resp = device_client()
+        .delete(device.address.to_string() + endpoints::ACTIVATE)
+        .send_to_device(device.id)
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in deactivate to smart device: {:?}", e);
+
+            Error::SmartDeviceNotReachable
+        })?;
+    if !resp.status().is_success() {
+        tracing::error!("Smart device failed to deactivate: {}", resp.status());
+        return Err(Error::SmartDeviceResponse);
+    }
+    Ok(())
+}
+
+/// Issues a scripting key bound to the device, so what it sends with the key
+/// is attributed to it.
+pub(crate) async fn request_device_token(
+    scripting_api_address: &str,
+    device_id: Uuid,
+) -> Result<TokenDto> {
     let resp = reqwest::Client::new()
         .post(scripting_api_address.to_string() + scripting_service_dto::endpoints::TOKEN)
+        .json(&ScriptingKeyRequestDto {
+            datasource_id: device_id.to_string(),
+        })
         .send()
         .await
         .map_err(|e| {
@@ -128,3 +679,119 @@ pub(crate) async fn request_device_token(scripting_api_address: &str) -> Result<
         Error::ScriptingApiResponse
     })
 }
+
+pub(crate) async fn request_create_alert(
+    data_storage_address: &str,
+    alert: &CreateAlertDto,
+) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .post(data_storage_address.to_string() + ALERT)
+        .json(alert)
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in post to data storage service: {:?}", e);
+
+            Error::DataStorageServiceNotReachable
+        })?;
+    if !resp.status().is_success() {
+        tracing::error!(
+            "Data storage service rejected alert with status {}",
+            resp.status()
+        );
+        return Err(Error::DataStorageServiceResponse);
+    }
+    Ok(())
+}
+
+pub(crate) async fn request_notification(
+    url: &str,
+    notification: &RuleNotificationDto,
+) -> Result<()> {
+    // Redirects could lead off the hosts on the notify allowlist
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| {
+            tracing::error!("Error building notification client: {:?}", e);
+            Error::NotificationNotReachable
+        })?;
+    let resp = client
+        .post(url)
+        .timeout(NOTIFICATION_TIMEOUT)
+        .json(notification)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!("Error in post of notification: {:?} for url {}", e, url);
+
+            Error::NotificationNotReachable
+        })?;
+    if !resp.status().is_success() {
+        tracing::error!(
+            "Notification url {} rejected notification with status {}",
+            url,
+            resp.status()
+        );
+        return Err(Error::NotificationResponse);
+    }
+    Ok(())
+}
+
+pub(crate) async fn request_revoke_token(
+    scripting_service_address: &str,
+    token: &str,
+) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(scripting_service_address.to_string() + scripting_service_dto::endpoints::TOKEN)
+        .json(&TokenDto {
+            token: token.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in delete to scripting service: {:?}", e);
+
+            Error::ScriptingApiNotReachable
+        })?;
+    if !resp.status().is_success() {
+        tracing::error!(
+            "Scripting service failed to revoke token: {}",
+            resp.status()
+        );
+        return Err(Error::ScriptingApiResponse);
+    }
+    Ok(())
+}
+
+pub(crate) async fn request_delete_alerts(
+    data_storage_address: &str,
+    device_id: Uuid,
+) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .delete(data_storage_address.to_string() + ALERT)
+        .query(&DeleteAlertsQuery {
+            datasource_id: device_id,
+        })
+        .send()
+        .await
+        .map_err(|e| {
+            sentry::capture_error(&e);
+
+            tracing::error!("Error in delete to data storage service: {:?}", e);
+
+            Error::DataStorageServiceNotReachable
+        })?;
+    if !resp.status().is_success() {
+        tracing::error!(
+            "Data storage service failed to delete alerts: {}",
+            resp.status()
+        );
+        return Err(Error::DataStorageServiceResponse);
+    }
+    Ok(())
+}
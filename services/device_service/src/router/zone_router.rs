@@ -0,0 +1,124 @@
+use crate::{
+    AppState,
+    database::{
+        device::Device,
+        zone::{Zone, subtree},
+    },
+    router::{
+        error::{Error, HttpResult},
+        reading_service::get_device_readings,
+    },
+};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use greenhouse_core::device_service_dto::{
+    query::PromQuery,
+    zone::{
+        DeviceTimeseriesDto, ZoneRequestDto, ZoneResponseDto, ZoneTimeseriesDto, ZonesResponseDto,
+    },
+};
+use uuid::Uuid;
+
+pub(crate) fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_zones).post(create_zone))
+        .route("/{id}", get(get_zone).put(update_zone).delete(delete_zone))
+        .route("/{id}/timeseries", get(get_zone_timeseries))
+        .with_state(state)
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_zones(
+    State(AppState { config: _, pool }): State<AppState>,
+) -> HttpResult<ZonesResponseDto> {
+    Ok(Zone::all(&pool)
+        .await?
+        .into_iter()
+        .map(|zone| zone.into())
+        .collect::<Vec<ZoneResponseDto>>()
+        .into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn get_zone(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<ZoneResponseDto> {
+    Ok(Zone::find_by_id(id, &pool).await?.into())
+}
+
+#[axum::debug_handler]
+pub(crate) async fn create_zone(
+    State(AppState { config: _, pool }): State<AppState>,
+    Json(request): Json<ZoneRequestDto>,
+) -> HttpResult<ZoneResponseDto> {
+    let parent = match request.parent_id {
+        Some(parent_id) => Zone::find(parent_id, &pool).await?,
+        None => None,
+    };
+    let mut zone = Zone::new();
+    zone.set(&request, parent.as_ref())
+        .map_err(Error::InvalidZone)?;
+    zone.flush(&pool).await?;
+    Ok(zone.into())
+}
+
+/// The kind of a zone can only change while no zones lie in it, so the
+/// hierarchy stays greenhouse > compartment > bench.
+#[axum::debug_handler]
+pub(crate) async fn update_zone(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ZoneRequestDto>,
+) -> HttpResult<ZoneResponseDto> {
+    let mut zone = Zone::find_by_id(id, &pool).await?;
+    if zone.kind() != request.kind && zone.has_children(&pool).await? {
+        return Err(Error::ZoneNotEmpty.into());
+    }
+    let parent = match request.parent_id {
+        Some(parent_id) => Zone::find(parent_id, &pool).await?,
+        None => None,
+    };
+    zone.set(&request, parent.as_ref())
+        .map_err(Error::InvalidZone)?;
+    zone.flush(&pool).await?;
+    Ok(zone.into())
+}
+
+/// Only empty zones can be deleted, devices in the zone are left without one.
+#[axum::debug_handler]
+pub(crate) async fn delete_zone(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> HttpResult<StatusCode> {
+    let zone = Zone::find_by_id(id, &pool).await?;
+    if zone.has_children(&pool).await? {
+        return Err(Error::ZoneNotEmpty.into());
+    }
+    zone.delete(&pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stored readings of every device in the zone and the zones below it.
+#[axum::debug_handler]
+pub(crate) async fn get_zone_timeseries(
+    State(AppState { config: _, pool }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<PromQuery>,
+) -> HttpResult<ZoneTimeseriesDto> {
+    Zone::find_by_id(id, &pool).await?;
+    let zones = subtree(id, &Zone::all(&pool).await?);
+    let mut devices = Vec::new();
+    for device in Device::filtered(false, Some(&zones), None, &pool).await? {
+        devices.push(DeviceTimeseriesDto {
+            device_id: device.id,
+            timeseries: get_device_readings(device.id, &query, &pool).await?,
+            name: device.name,
+        });
+    }
+    Ok(devices.into())
+}
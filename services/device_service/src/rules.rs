@@ -0,0 +1,671 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::join_all;
+use greenhouse_core::{
+    data_storage_service_dto::alert_dto::post_create_alert::CreateAlertDto,
+    device_service_dto::rule::{
+        ActionResultDto, ConditionResultDto, ConditionValue, EvaluationOutcome, HoldDto, Operator,
+        ReadingCondition, RuleAction, RuleCondition, RuleEvaluationDto, RuleNotificationDto,
+    },
+    http_error::HttpErrorMapping,
+    smart_device_dto::{Type, write::WriteStatus},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState, database,
+    database::{
+        device::Device,
+        reading::Reading,
+        rule::{PendingRevert, Rule, RuleEvaluation, notify_allowed},
+    },
+    router::service::{request_create_alert, request_notification, write_device_value},
+};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Readings older than this, or three scrape intervals of the device if that
+/// is longer, no longer satisfy a condition.
+const STALE_AFTER: TimeDelta = TimeDelta::minutes(15);
+
+/// Periodically evaluates every enabled rule and logs the evaluations. Held
+/// writes due for reverting are reverted first, which right after a start
+/// catches up on those missed while the service was down.
+pub(crate) fn start_rules(state: AppState) {
+    tokio::spawn(async move {
+        let interval = Duration::from_secs(state.config.rule_evaluation_secs.max(1).into());
+        let mut pruned_at: Option<Instant> = None;
+        loop {
+            if let Err(e) = revert_due(&state).await {
+                sentry::capture_error(&e);
+                tracing::error!("Reverting held writes failed: {:?}", e);
+            }
+            if let Err(e) = evaluate_all(&state).await {
+                sentry::capture_error(&e);
+                tracing::error!("Evaluating rules failed: {:?}", e);
+            }
+            if pruned_at.is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL) {
+                let cutoff =
+                    Utc::now() - TimeDelta::days(state.config.rule_log_retention_days.into());
+                match RuleEvaluation::delete_before(cutoff, &state.pool).await {
+                    Ok(deleted) => tracing::info!("Deleted {deleted} expired rule evaluations"),
+                    Err(e) => tracing::error!("Deleting rule evaluations failed: {:?}", e),
+                }
+                pruned_at = Some(Instant::now());
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+async fn evaluate_all(state: &AppState) -> database::Result<()> {
+    let devices: HashMap<Uuid, Device> = Device::filtered(false, None, None, &state.pool)
+        .await?
+        .into_iter()
+        .map(|device| (device.id, device))
+        .collect();
+    for mut rule in Rule::all_enabled(&state.pool).await? {
+        // One failing rule must not keep the others from being evaluated
+        if let Err(e) = evaluate(&mut rule, &devices, state).await {
+            sentry::capture_error(&e);
+            tracing::error!("Evaluating rule {} failed: {:?}", rule.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates the rule and logs the evaluation, the actions of a firing rule
+/// run in the background.
+async fn evaluate(
+    rule: &mut Rule,
+    devices: &HashMap<Uuid, Device>,
+    state: &AppState,
+) -> database::Result<()> {
+    let now = Utc::now();
+    let condition = rule.condition().unwrap_or(RuleCondition::Any {
+        conditions: Vec::new(),
+    });
+    let mut leaves = Vec::new();
+    collect_leaves(&condition, &mut leaves);
+    let mut conditions = Vec::with_capacity(leaves.len());
+    for leaf in leaves {
+        conditions.push(check_reading(leaf, now, devices, state).await?);
+    }
+    let matched = combine(
+        &condition,
+        &mut conditions.iter().map(|result| result.satisfied),
+    );
+
+    let outcome = match (matched, cooling_down(rule, now)) {
+        (false, _) => EvaluationOutcome::NotMet,
+        (true, true) => EvaluationOutcome::Cooldown,
+        (true, false) => {
+            // Marked first so failing actions are not retried on every evaluation
+            rule.mark_fired(now, &state.pool).await?;
+            fire(rule.clone(), now, conditions, state.clone());
+            return Ok(());
+        }
+    };
+    let evaluation = RuleEvaluationDto {
+        evaluated_at: now,
+        outcome,
+        conditions,
+        actions: Vec::new(),
+    };
+    RuleEvaluation::new(rule.id, &evaluation)
+        .insert(&state.pool)
+        .await
+}
+
+fn cooling_down(rule: &Rule, now: DateTime<Utc>) -> bool {
+    rule.last_fired_at
+        .is_some_and(|fired_at| now - fired_at < TimeDelta::seconds(rule.cooldown_seconds.into()))
+}
+
+/// Runs the actions of a fired rule side by side without holding up the
+/// evaluation of the other rules, then logs the evaluation.
+fn fire(rule: Rule, fired_at: DateTime<Utc>, conditions: Vec<ConditionResultDto>, state: AppState) {
+    tokio::spawn(async move {
+        let actions = rule.actions();
+        let actions = join_all(
+            actions
+                .iter()
+                .map(|action| run_action(&rule, action, fired_at, &state)),
+        )
+        .await;
+        let outcome = if actions.iter().all(|action| action.succeeded) {
+            EvaluationOutcome::Fired
+        } else {
+            tracing::warn!("Rule {} fired, but an action failed", rule.name);
+            EvaluationOutcome::Failed
+        };
+        let evaluation = RuleEvaluationDto {
+            evaluated_at: fired_at,
+            outcome,
+            conditions,
+            actions,
+        };
+        if let Err(e) = RuleEvaluation::new(rule.id, &evaluation)
+            .insert(&state.pool)
+            .await
+        {
+            tracing::error!(
+                "Logging the evaluation of rule {} failed: {:?}",
+                rule.name,
+                e
+            );
+        }
+    });
+}
+
+/// Reading conditions in the order `combine` consumes their results.
+fn collect_leaves<'a>(condition: &'a RuleCondition, leaves: &mut Vec<&'a ReadingCondition>) {
+    match condition {
+        RuleCondition::All { conditions } | RuleCondition::Any { conditions } => {
+            for condition in conditions {
+                collect_leaves(condition, leaves);
+            }
+        }
+        RuleCondition::Reading(reading) => leaves.push(reading),
+    }
+}
+
+fn combine(condition: &RuleCondition, results: &mut impl Iterator<Item = bool>) -> bool {
+    match condition {
+        // Counted instead of short circuited so every leaf result is consumed
+        RuleCondition::All { conditions } => {
+            let held = conditions
+                .iter()
+                .filter(|condition| combine(condition, results))
+                .count();
+            held == conditions.len()
+        }
+        RuleCondition::Any { conditions } => {
+            let held = conditions
+                .iter()
+                .filter(|condition| combine(condition, results))
+                .count();
+            held > 0
+        }
+        RuleCondition::Reading(_) => results.next().unwrap_or(false),
+    }
+}
+
+/// Fetches the readings `condition` is checked against, see `judge`.
+async fn check_reading(
+    condition: &ReadingCondition,
+    now: DateTime<Utc>,
+    devices: &HashMap<Uuid, Device>,
+    state: &AppState,
+) -> database::Result<ConditionResultDto> {
+    let Some(device) = devices.get(&condition.device_id) else {
+        return Ok(ConditionResultDto {
+            device_id: condition.device_id,
+            channel: condition.channel.clone(),
+            satisfied: false,
+            value: None,
+            note: Some(String::from("unknown device")),
+        });
+    };
+    let pool = &state.pool;
+    let latest = Reading::latest_at(device.id, &condition.channel, now, pool).await?;
+    let mut window = None;
+    if latest.is_some() && condition.for_seconds > 0 {
+        let window_start = now - TimeDelta::seconds(condition.for_seconds.into());
+        if let Some(first) =
+            Reading::latest_at(device.id, &condition.channel, window_start, pool).await?
+        {
+            let mut readings = vec![first];
+            readings.extend(
+                Reading::find_range(device.id, &condition.channel, window_start, now, pool).await?,
+            );
+            window = Some(readings);
+        }
+    }
+    let stale_after = TimeDelta::from_std(device.scrape_interval() * 3)
+        .unwrap_or(STALE_AFTER)
+        .max(STALE_AFTER);
+    Ok(judge(
+        condition,
+        latest.as_ref(),
+        window.as_deref(),
+        stale_after,
+        now,
+    ))
+}
+
+/// Holds if the latest reading and every reading within `for_seconds`
+/// match, including the one the window starts with. `window` is `None` if
+/// the channel has no reading from before the window.
+fn judge(
+    condition: &ReadingCondition,
+    latest: Option<&Reading>,
+    window: Option<&[Reading]>,
+    stale_after: TimeDelta,
+    now: DateTime<Utc>,
+) -> ConditionResultDto {
+    let mut result = ConditionResultDto {
+        device_id: condition.device_id,
+        channel: condition.channel.clone(),
+        satisfied: false,
+        value: None,
+        note: None,
+    };
+    let Some(latest) = latest else {
+        result.note = Some(String::from("no readings"));
+        return result;
+    };
+    result.value = reading_value(latest);
+    if now - latest.recorded_at > stale_after {
+        result.note = Some(String::from("latest reading is stale"));
+        return result;
+    }
+    if condition.for_seconds == 0 {
+        result.satisfied = matches(latest, condition.operator, &condition.value);
+        return result;
+    }
+    let Some(window) = window else {
+        result.note = Some(String::from("not enough history"));
+        return result;
+    };
+    result.satisfied = window
+        .iter()
+        .all(|reading| matches(reading, condition.operator, &condition.value));
+    result
+}
+
+fn reading_value(reading: &Reading) -> Option<ConditionValue> {
+    reading
+        .number
+        .map(ConditionValue::Number)
+        .or_else(|| reading.text.clone().map(ConditionValue::Text))
+}
+
+fn matches(reading: &Reading, operator: Operator, value: &ConditionValue) -> bool {
+    let ordering = match (value, reading.number, &reading.text) {
+        (ConditionValue::Number(value), Some(number), _) => number.partial_cmp(value),
+        (ConditionValue::Text(value), _, Some(text)) => Some(text.as_str().cmp(value.as_str())),
+        _ => None,
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+    match operator {
+        Operator::Lt => ordering == Ordering::Less,
+        Operator::Le => ordering != Ordering::Greater,
+        Operator::Gt => ordering == Ordering::Greater,
+        Operator::Ge => ordering != Ordering::Less,
+        Operator::Eq => ordering == Ordering::Equal,
+        Operator::Ne => ordering != Ordering::Equal,
+    }
+}
+
+async fn run_action(
+    rule: &Rule,
+    action: &RuleAction,
+    fired_at: DateTime<Utc>,
+    state: &AppState,
+) -> ActionResultDto {
+    let (kind, error) = match action {
+        RuleAction::Write {
+            device_id,
+            data,
+            hold,
+        } => {
+            let idempotency_key = format!("rule-{}-{}", rule.id, fired_at.timestamp());
+            let mut error = write(rule, *device_id, data.clone(), idempotency_key, state).await;
+            if let (None, Some(hold)) = (&error, hold) {
+                error = revert_later(rule, *device_id, hold, fired_at, state)
+                    .await
+                    .err()
+                    .map(|e| format!("Written, but the revert could not be scheduled: {e}"));
+            }
+            ("write", error)
+        }
+        RuleAction::Alert {
+            severity,
+            identifier,
+            note,
+        } => {
+            let error = match &state.config.data_storage_service {
+                Some(data_storage_service) => {
+                    let alert = CreateAlertDto {
+                        severity: severity.clone(),
+                        identifier: identifier.clone(),
+                        value: Some(rule.name.clone()),
+                        note: note.clone(),
+                        datasource_id: rule.id.to_string(),
+                    };
+                    request_create_alert(data_storage_service, &alert)
+                        .await
+                        .err()
+                        .map(|e| e.to_error_message())
+                }
+                None => Some(String::from("No data storage service configured")),
+            };
+            ("alert", error)
+        }
+        RuleAction::Notify { url, message } => {
+            // Checked again as the allowlist may have shrunk since the rule was saved
+            if !notify_allowed(url, &state.config.notify_allowlist) {
+                return ActionResultDto {
+                    kind: String::from("notify"),
+                    succeeded: false,
+                    error: Some(String::from("host is not on the notify allowlist")),
+                };
+            }
+            let notification = RuleNotificationDto {
+                rule_id: rule.id,
+                rule: rule.name.clone(),
+                message: message.clone(),
+                fired_at,
+            };
+            let error = request_notification(url, &notification)
+                .await
+                .err()
+                .map(|e| e.to_error_message());
+            ("notify", error)
+        }
+    };
+    ActionResultDto {
+        kind: String::from(kind),
+        succeeded: error.is_none(),
+        error,
+    }
+}
+
+/// Writes `data` for the rule, returns why the write failed if it did.
+async fn write(
+    rule: &Rule,
+    device_id: Uuid,
+    data: Type,
+    idempotency_key: String,
+    state: &AppState,
+) -> Option<String> {
+    let actor = format!("rule {}", rule.name);
+    match write_device_value(device_id, data, Some(idempotency_key), actor, &state.pool).await {
+        Ok(response) => match response.status {
+            WriteStatus::Applied | WriteStatus::Unchanged => None,
+            WriteStatus::Rejected | WriteStatus::Failed => Some(
+                response
+                    .error
+                    .unwrap_or_else(|| String::from("Device did not apply the write")),
+            ),
+        },
+        Err(e) => Some(e.to_error_message()),
+    }
+}
+
+/// Stores the value a held write reverts to once the hold is over.
+async fn revert_later(
+    rule: &Rule,
+    device_id: Uuid,
+    hold: &HoldDto,
+    fired_at: DateTime<Utc>,
+    state: &AppState,
+) -> database::Result<()> {
+    let due_at = fired_at + TimeDelta::seconds(hold.seconds.into());
+    let idempotency_key = format!("rule-{}-{}-revert", rule.id, fired_at.timestamp());
+    PendingRevert::new(rule.id, device_id, &hold.revert_to, due_at, idempotency_key)
+        .insert(&state.pool)
+        .await
+}
+
+/// Reverts the held writes that are due in the background. A revert stays
+/// stored until it succeeded, failed attempts are retried with a backoff and
+/// logged as evaluation of their rule.
+async fn revert_due(state: &AppState) -> database::Result<()> {
+    for pending in PendingRevert::claim_due(Utc::now(), &state.pool).await? {
+        let state = state.clone();
+        tokio::spawn(revert(pending, state));
+    }
+    Ok(())
+}
+
+async fn revert(mut pending: PendingRevert, state: AppState) {
+    let Some(revert_to) = pending.revert_to() else {
+        // Retrying can't help a value that doesn't deserialize
+        tracing::error!(
+            "Dropping the invalid revert of device {} by rule {}",
+            pending.device_id,
+            pending.rule_id
+        );
+        log_failed_revert(
+            pending.rule_id,
+            String::from("Stored revert value is invalid"),
+            &state,
+        )
+        .await;
+        if let Err(e) = pending.delete(&state.pool).await {
+            tracing::error!("Deleting revert {} failed: {:?}", pending.id, e);
+        }
+        return;
+    };
+    let error = match Rule::find_by_id(pending.rule_id, &state.pool).await {
+        Ok(rule) => {
+            let idempotency_key = pending.idempotency_key.clone();
+            write(&rule, pending.device_id, revert_to, idempotency_key, &state).await
+        }
+        Err(e) => Some(format!("Loading the rule failed: {e}")),
+    };
+    let Some(error) = error else {
+        if let Err(e) = pending.delete(&state.pool).await {
+            tracing::error!("Deleting revert {} failed: {:?}", pending.id, e);
+        }
+        return;
+    };
+    let error = match pending.retry_later(Utc::now(), &state.pool).await {
+        Ok(due_at) => format!("{error}, retrying at {due_at}"),
+        // Still leased, so it is retried once the lease runs out
+        Err(e) => format!("{error}, scheduling the retry failed: {e}"),
+    };
+    tracing::error!(
+        "Rule {} could not revert device {}: {}",
+        pending.rule_id,
+        pending.device_id,
+        error
+    );
+    log_failed_revert(pending.rule_id, error, &state).await;
+}
+
+async fn log_failed_revert(rule_id: Uuid, error: String, state: &AppState) {
+    let evaluation = RuleEvaluationDto {
+        evaluated_at: Utc::now(),
+        outcome: EvaluationOutcome::Failed,
+        conditions: Vec::new(),
+        actions: vec![ActionResultDto {
+            kind: String::from("revert"),
+            succeeded: false,
+            error: Some(error),
+        }],
+    };
+    if let Err(e) = RuleEvaluation::new(rule_id, &evaluation)
+        .insert(&state.pool)
+        .await
+    {
+        tracing::error!("Logging the revert of rule {} failed: {:?}", rule_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap() + TimeDelta::seconds(seconds)
+    }
+
+    fn number(seconds: i64, value: f64) -> Reading {
+        Reading {
+            device_id: Uuid::nil(),
+            channel: String::new(),
+            recorded_at: at(seconds),
+            value_type: String::from("number"),
+            number: Some(value),
+            text: None,
+            unit: None,
+            downsampled: false,
+        }
+    }
+
+    fn text(value: &str) -> Reading {
+        Reading {
+            value_type: String::from("text"),
+            number: None,
+            text: Some(String::from(value)),
+            ..number(0, 0.0)
+        }
+    }
+
+    fn above(value: f64, for_seconds: u32) -> ReadingCondition {
+        ReadingCondition {
+            device_id: Uuid::nil(),
+            channel: String::new(),
+            operator: Operator::Gt,
+            value: ConditionValue::Number(value),
+            for_seconds,
+        }
+    }
+
+    fn leaf() -> RuleCondition {
+        RuleCondition::Reading(above(0.0, 0))
+    }
+
+    fn all(conditions: Vec<RuleCondition>) -> RuleCondition {
+        RuleCondition::All { conditions }
+    }
+
+    fn any(conditions: Vec<RuleCondition>) -> RuleCondition {
+        RuleCondition::Any { conditions }
+    }
+
+    fn holds(condition: &RuleCondition, results: &[bool]) -> bool {
+        let mut results = results.iter().copied();
+        let held = combine(condition, &mut results);
+        assert_eq!(results.next(), None, "not every leaf result was consumed");
+        held
+    }
+
+    #[test]
+    fn combines_nested_groups() {
+        // a AND (b OR c)
+        let condition = all(vec![leaf(), any(vec![leaf(), leaf()])]);
+        assert!(holds(&condition, &[true, false, true]));
+        assert!(holds(&condition, &[true, true, false]));
+        assert!(!holds(&condition, &[true, false, false]));
+        assert!(!holds(&condition, &[false, true, true]));
+
+        // (a AND b) OR c
+        let condition = any(vec![all(vec![leaf(), leaf()]), leaf()]);
+        assert!(holds(&condition, &[true, true, false]));
+        assert!(holds(&condition, &[false, false, true]));
+        assert!(!holds(&condition, &[true, false, false]));
+    }
+
+    #[test]
+    fn combines_empty_groups() {
+        assert!(holds(&all(Vec::new()), &[]));
+        assert!(!holds(&any(Vec::new()), &[]));
+    }
+
+    #[test]
+    fn compares_numbers_at_the_boundary() {
+        let equal = number(0, 20.0);
+        let value = ConditionValue::Number(20.0);
+        let cases = [
+            (Operator::Lt, false),
+            (Operator::Le, true),
+            (Operator::Gt, false),
+            (Operator::Ge, true),
+            (Operator::Eq, true),
+            (Operator::Ne, false),
+        ];
+        for (operator, expected) in cases {
+            assert_eq!(matches(&equal, operator, &value), expected, "{operator:?}");
+        }
+        assert!(matches(&number(0, 19.9), Operator::Lt, &value));
+        assert!(matches(&number(0, 20.1), Operator::Gt, &value));
+    }
+
+    #[test]
+    fn never_matches_mismatched_or_undefined_values() {
+        let number_value = ConditionValue::Number(1.0);
+        let text_value = ConditionValue::Text(String::from("open"));
+        for operator in [Operator::Eq, Operator::Ne] {
+            assert!(!matches(&text("open"), operator, &number_value));
+            assert!(!matches(&number(0, 1.0), operator, &text_value));
+            assert!(!matches(&number(0, f64::NAN), operator, &number_value));
+        }
+        assert!(matches(&text("open"), Operator::Eq, &text_value));
+        assert!(matches(&text("closed"), Operator::Ne, &text_value));
+    }
+
+    #[test]
+    fn checks_only_the_latest_reading_without_a_window() {
+        let result = judge(
+            &above(20.0, 0),
+            Some(&number(-10, 21.0)),
+            None,
+            STALE_AFTER,
+            at(0),
+        );
+        assert!(result.satisfied);
+        assert_eq!(result.value, Some(ConditionValue::Number(21.0)));
+    }
+
+    #[test]
+    fn requires_every_reading_in_the_window_to_match() {
+        let condition = above(20.0, 60);
+        let latest = number(-5, 21.0);
+        let window = [number(-70, 22.0), number(-40, 21.0), latest.clone()];
+        let result = judge(&condition, Some(&latest), Some(&window), STALE_AFTER, at(0));
+        assert!(result.satisfied);
+
+        // The reading the window starts with counts as well
+        let window = [number(-70, 19.0), number(-40, 21.0), latest.clone()];
+        let result = judge(&condition, Some(&latest), Some(&window), STALE_AFTER, at(0));
+        assert!(!result.satisfied);
+
+        let window = [number(-70, 22.0), number(-40, 20.0), latest.clone()];
+        let result = judge(&condition, Some(&latest), Some(&window), STALE_AFTER, at(0));
+        assert!(!result.satisfied);
+    }
+
+    #[test]
+    fn does_not_hold_without_enough_fresh_readings() {
+        let condition = above(20.0, 60);
+        let result = judge(&condition, None, None, STALE_AFTER, at(0));
+        assert_eq!(result.note.as_deref(), Some("no readings"));
+
+        let latest = number(-5, 21.0);
+        let result = judge(&condition, Some(&latest), None, STALE_AFTER, at(0));
+        assert!(!result.satisfied);
+        assert_eq!(result.note.as_deref(), Some("not enough history"));
+
+        let stale = number(-16 * 60, 21.0);
+        let window = [stale.clone()];
+        let result = judge(&condition, Some(&stale), Some(&window), STALE_AFTER, at(0));
+        assert!(!result.satisfied);
+        assert_eq!(result.note.as_deref(), Some("latest reading is stale"));
+    }
+
+    #[test]
+    fn cools_down_after_firing() {
+        let mut rule = Rule::new();
+        rule.cooldown_seconds = 300;
+        assert!(!cooling_down(&rule, at(0)));
+
+        rule.last_fired_at = Some(at(0));
+        assert!(cooling_down(&rule, at(299)));
+        assert!(!cooling_down(&rule, at(300)));
+
+        rule.cooldown_seconds = 0;
+        assert!(!cooling_down(&rule, at(0)));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    AppState, database,
+    database::schedule::{Schedule, ScheduleExecution},
+    router::service::write_device_value,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use greenhouse_core::{
+    device_service_dto::schedule::{ExecutionStatus, MissedRunPolicy},
+    http_error::HttpErrorMapping,
+    smart_device_dto::write::WriteStatus,
+};
+use std::time::Duration;
+use tokio::sync::Notify;
+use trigger::Trigger;
+
+pub(crate) mod sun;
+pub(crate) mod trigger;
+
+/// Runs due longer ago than this were missed, e.g. while the service was down.
+const MISSED_AFTER: TimeDelta = TimeDelta::minutes(2);
+/// Longest the runner sleeps, so schedules whose next run can't be computed
+/// yet are picked up eventually.
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Occurrences looked at per schedule and pass, bounds catching up on
+/// schedules firing every second.
+const MAX_OCCURRENCES: usize = 10_000;
+
+static SCHEDULES_CHANGED: Notify = Notify::const_new();
+
+/// Wakes the runner so changed schedules are picked up right away.
+pub(crate) fn schedules_changed() {
+    SCHEDULES_CHANGED.notify_one();
+}
+
+/// Executes schedules as they come due.
+pub(crate) fn start_schedules(state: AppState) {
+    if state.config.timezone.parse::<Tz>().is_err() {
+        tracing::warn!(
+            "Unknown timezone {}, evaluating schedules in UTC",
+            state.config.timezone
+        );
+    }
+    tokio::spawn(async move {
+        loop {
+            let wait = match run_due(&state).await {
+                Ok(Some(next)) => (next - Utc::now()).to_std().unwrap_or_default(),
+                Ok(None) => IDLE_INTERVAL,
+                Err(e) => {
+                    sentry::capture_error(&e);
+                    tracing::error!("Running schedules failed: {:?}", e);
+                    IDLE_INTERVAL
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait.min(IDLE_INTERVAL)) => {}
+                _ = SCHEDULES_CHANGED.notified() => {}
+            }
+        }
+    });
+}
+
+/// Runs every schedule that came due, returns when the next one is due.
+async fn run_due(state: &AppState) -> database::Result<Option<DateTime<Utc>>> {
+    let now = Utc::now();
+    let mut earliest: Option<DateTime<Utc>> = None;
+    for mut schedule in Schedule::all_enabled(&state.pool).await? {
+        let trigger = Trigger::new(&schedule.trigger(), &state.config);
+        let mut cursor = schedule.handled_until;
+        let mut previous = None;
+        let mut due = 0;
+        let mut upcoming = None;
+        while due < MAX_OCCURRENCES {
+            match trigger.next_after(cursor) {
+                Some(next) if next <= now => {
+                    previous = Some(cursor);
+                    cursor = next;
+                    due += 1;
+                }
+                next => {
+                    upcoming = next;
+                    break;
+                }
+            }
+        }
+        // Not caught up yet, look again right away
+        let upcoming = if due == MAX_OCCURRENCES {
+            Some(now)
+        } else {
+            upcoming
+        };
+        earliest = match (earliest, upcoming) {
+            (Some(earliest), Some(upcoming)) => Some(earliest.min(upcoming)),
+            (earliest, upcoming) => earliest.or(upcoming),
+        };
+        if due == 0 {
+            continue;
+        }
+
+        let latest = cursor;
+        let on_time = now - latest <= MISSED_AFTER;
+        let run = on_time || schedule.missed_run_policy() == MissedRunPolicy::RunOnce;
+        let (missed, last_missed) = if run {
+            (due - 1, previous)
+        } else {
+            (due, Some(latest))
+        };
+        // Advanced before running so a crash doesn't repeat the write
+        schedule
+            .advance(latest, run.then_some(now), &state.pool)
+            .await?;
+        if let Some(last_missed) = last_missed
+            && missed > 0
+        {
+            ScheduleExecution::new(
+                schedule.id,
+                last_missed,
+                ExecutionStatus::Missed,
+                Some(format!("{missed} run(s) missed")),
+            )
+            .insert(&state.pool)
+            .await?;
+        }
+        if run {
+            let state = state.clone();
+            tokio::spawn(async move { execute(&schedule, latest, &state).await });
+        }
+    }
+    Ok(earliest)
+}
+
+async fn execute(schedule: &Schedule, scheduled_for: DateTime<Utc>, state: &AppState) {
+    let idempotency_key = format!("schedule-{}-{}", schedule.id, scheduled_for.timestamp());
+    let written = write_device_value(
+        schedule.device_id,
+        schedule.data(),
+        Some(idempotency_key),
+        format!("schedule {}", schedule.name),
+        &state.pool,
+    )
+    .await;
+    let (status, error) = match written {
+        Ok(response) => match response.status {
+            WriteStatus::Applied | WriteStatus::Unchanged => (ExecutionStatus::Succeeded, None),
+            WriteStatus::Rejected | WriteStatus::Failed => {
+                (ExecutionStatus::Failed, response.error)
+            }
+        },
+        Err(e) => (ExecutionStatus::Failed, Some(e.to_error_message())),
+    };
+    if let Some(error) = &error {
+        tracing::warn!("Schedule {} failed: {}", schedule.name, error);
+    }
+    if let Err(e) = ScheduleExecution::new(schedule.id, scheduled_for, status, error)
+        .insert(&state.pool)
+        .await
+    {
+        sentry::capture_error(&e);
+        tracing::error!(
+            "Recording run of schedule {} failed: {:?}",
+            schedule.name,
+            e
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Sunrise and sunset after the sunrise equation, accurate to a few minutes
+//! which is plenty for switching lights and shades.
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SolarEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// Julian day of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+/// Julian day of the J2000 epoch.
+const J2000: f64 = 2_451_545.0;
+/// Sun altitude at sunrise and sunset, corrected for refraction and the
+/// sun's radius.
+const HORIZON_DEGREES: f64 = -0.833;
+const AXIAL_TILT_DEGREES: f64 = 23.4397;
+
+/// When `event` happens on `date` at the given location, longitude positive
+/// east. `None` during polar day and night.
+pub(crate) fn solar_event(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+    event: SolarEvent,
+) -> Option<DateTime<Utc>> {
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let day = (date - j2000).num_days() as f64;
+
+    let mean_solar_time = day - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_solar_time).rem_euclid(360.0);
+    let anomaly_rad = anomaly.to_radians();
+    let center = 1.9148 * anomaly_rad.sin()
+        + 0.02 * (2.0 * anomaly_rad).sin()
+        + 0.0003 * (3.0 * anomaly_rad).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+    let ecliptic_rad = ecliptic_longitude.to_radians();
+    let transit =
+        J2000 + mean_solar_time + 0.0053 * anomaly_rad.sin() - 0.0069 * (2.0 * ecliptic_rad).sin();
+
+    let declination_sin = ecliptic_rad.sin() * AXIAL_TILT_DEGREES.to_radians().sin();
+    let declination_cos = (1.0 - declination_sin * declination_sin).sqrt();
+    let latitude_rad = latitude.to_radians();
+    let hour_angle_cos = (HORIZON_DEGREES.to_radians().sin()
+        - latitude_rad.sin() * declination_sin)
+        / (latitude_rad.cos() * declination_cos);
+    if !(-1.0..=1.0).contains(&hour_angle_cos) {
+        return None;
+    }
+    let hour_angle = hour_angle_cos.acos().to_degrees();
+
+    let julian_day = match event {
+        SolarEvent::Sunrise => transit - hour_angle / 360.0,
+        SolarEvent::Sunset => transit + hour_angle / 360.0,
+    };
+    let millis = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86_400_000.0).round() as i64;
+    DateTime::from_timestamp_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn assert_near(event: Option<DateTime<Utc>>, (h, m): (u32, u32), date: NaiveDate) {
+        let expected = Utc.from_utc_datetime(&date.and_hms_opt(h, m, 0).unwrap());
+        let event = event.unwrap();
+        assert!(
+            (event - expected).num_minutes().abs() <= 3,
+            "{event} is not near {expected}"
+        );
+    }
+
+    #[test]
+    fn computes_sunrise_and_sunset_of_known_places() {
+        let cases = [
+            // Berlin on the summer solstice
+            ((52.52, 13.405), (2024, 6, 21), (2, 43), (19, 33)),
+            // New York on the winter solstice
+            ((40.7128, -74.006), (2024, 12, 21), (12, 16), (21, 32)),
+            // Equator at Greenwich on the equinox
+            ((0.0, 0.0), (2024, 3, 20), (6, 4), (18, 11)),
+        ];
+        for ((latitude, longitude), (y, m, d), sunrise, sunset) in cases {
+            let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            assert_near(
+                solar_event(date, latitude, longitude, SolarEvent::Sunrise),
+                sunrise,
+                date,
+            );
+            assert_near(
+                solar_event(date, latitude, longitude, SolarEvent::Sunset),
+                sunset,
+                date,
+            );
+        }
+    }
+
+    #[test]
+    fn has_no_events_during_polar_day_and_night() {
+        let (latitude, longitude) = (69.65, 18.96);
+        for date in [(2024, 6, 21), (2024, 12, 21)] {
+            let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+            assert_eq!(
+                solar_event(date, latitude, longitude, SolarEvent::Sunrise),
+                None
+            );
+            assert_eq!(
+                solar_event(date, latitude, longitude, SolarEvent::Sunset),
+                None
+            );
+        }
+    }
+}
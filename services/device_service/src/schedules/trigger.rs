@@ -0,0 +1,210 @@
+use super::sun::{SolarEvent, solar_event};
+use crate::Config;
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use greenhouse_core::device_service_dto::schedule::ScheduleTrigger;
+use std::str::FromStr;
+
+/// How far ahead solar triggers are searched, covers the polar night.
+const SOLAR_SEARCH_DAYS: i64 = 400;
+
+/// Parses a cron expression. Five field expressions are accepted and run at
+/// second zero of the minute.
+pub(crate) fn parse_cron(expression: &str) -> Result<cron::Schedule, cron::error::Error> {
+    let expression = expression.trim();
+    if expression.split_whitespace().count() == 5 {
+        cron::Schedule::from_str(&format!("0 {expression}"))
+    } else {
+        cron::Schedule::from_str(expression)
+    }
+}
+
+/// Timezone cron expressions are evaluated in, UTC if the configured one is
+/// unknown.
+pub(crate) fn timezone(config: &Config) -> Tz {
+    config.timezone.parse().unwrap_or(Tz::UTC)
+}
+
+/// A schedule trigger prepared to compute its runs, the cron expression is
+/// parsed once instead of for every run.
+pub(crate) enum Trigger {
+    Cron {
+        schedule: Box<cron::Schedule>,
+        timezone: Tz,
+    },
+    Solar {
+        event: SolarEvent,
+        offset: TimeDelta,
+        /// Latitude and longitude, solar triggers never fire without them.
+        location: Option<(f64, f64)>,
+    },
+    /// Cron expression that doesn't parse, e.g. stored by an older version.
+    Never,
+}
+
+impl Trigger {
+    pub(crate) fn new(trigger: &ScheduleTrigger, config: &Config) -> Self {
+        let (event, offset_minutes) = match trigger {
+            ScheduleTrigger::Cron { expression } => {
+                return match parse_cron(expression) {
+                    Ok(schedule) => Trigger::Cron {
+                        schedule: Box::new(schedule),
+                        timezone: timezone(config),
+                    },
+                    Err(_) => Trigger::Never,
+                };
+            }
+            ScheduleTrigger::Sunrise { offset_minutes } => (SolarEvent::Sunrise, *offset_minutes),
+            ScheduleTrigger::Sunset { offset_minutes } => (SolarEvent::Sunset, *offset_minutes),
+        };
+        Trigger::Solar {
+            event,
+            offset: TimeDelta::minutes(offset_minutes.into()),
+            location: config.latitude.zip(config.longitude),
+        }
+    }
+
+    /// First time the trigger fires strictly after `after`.
+    pub(crate) fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (event, offset, (latitude, longitude)) = match self {
+            Trigger::Cron { schedule, timezone } => {
+                return schedule
+                    .after(&after.with_timezone(timezone))
+                    .next()
+                    .map(|next| next.with_timezone(&Utc));
+            }
+            Trigger::Solar {
+                event,
+                offset,
+                location,
+            } => (*event, *offset, (*location)?),
+            Trigger::Never => return None,
+        };
+        // Starts a day early as the offset may move yesterday's event past `after`
+        let first = (after - offset).date_naive().pred_opt()?;
+        first
+            .iter_days()
+            .take(SOLAR_SEARCH_DAYS as usize)
+            .filter_map(|date| solar_event(date, latitude, longitude, event))
+            .map(|time| time + offset)
+            .find(|time| *time > after)
+    }
+
+    /// The next `count` times the trigger fires after `after`.
+    pub(crate) fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::with_capacity(count);
+        let mut cursor = after;
+        while runs.len() < count {
+            let Some(next) = self.next_after(cursor) else {
+                break;
+            };
+            runs.push(next);
+            cursor = next;
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn cron(expression: &str, timezone: Tz) -> Trigger {
+        Trigger::Cron {
+            schedule: Box::new(parse_cron(expression).unwrap()),
+            timezone,
+        }
+    }
+
+    fn berlin(event: SolarEvent, offset_minutes: i64) -> Trigger {
+        Trigger::Solar {
+            event,
+            offset: TimeDelta::minutes(offset_minutes),
+            location: Some((52.52, 13.405)),
+        }
+    }
+
+    #[test]
+    fn runs_five_field_expressions_at_second_zero() {
+        let trigger = cron("*/15 * * * *", Tz::UTC);
+
+        assert_eq!(
+            trigger.upcoming(utc(2024, 5, 1, 10, 7), 2),
+            [utc(2024, 5, 1, 10, 15), utc(2024, 5, 1, 10, 30)]
+        );
+    }
+
+    #[test]
+    fn keeps_cron_runs_on_local_time_across_dst() {
+        let trigger = cron("0 9 * * *", Tz::Europe__Berlin);
+
+        // 09:00 CET is 08:00 UTC, 09:00 CEST is 07:00 UTC
+        assert_eq!(
+            trigger.upcoming(utc(2024, 3, 30, 7, 0), 2),
+            [utc(2024, 3, 30, 8, 0), utc(2024, 3, 31, 7, 0)]
+        );
+        assert_eq!(
+            trigger.upcoming(utc(2024, 10, 26, 6, 0), 2),
+            [utc(2024, 10, 26, 7, 0), utc(2024, 10, 27, 8, 0)]
+        );
+    }
+
+    #[test]
+    fn never_fires_for_invalid_expressions() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "SERVICE_PORT": 0,
+            "SCRIPTING_API": "",
+            "SCRIPTING_SERVICE": "",
+            "DATABASE_URL": "",
+            "SENTRY_URL": "",
+        }))
+        .unwrap();
+        let trigger = ScheduleTrigger::Cron {
+            expression: String::from("not cron"),
+        };
+
+        assert!(matches!(Trigger::new(&trigger, &config), Trigger::Never));
+        assert_eq!(Trigger::Never.next_after(utc(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn finds_the_next_solar_event_with_its_offset() {
+        // Berlin sets at 19:33 UTC on the solstice
+        let next = berlin(SolarEvent::Sunset, -30)
+            .next_after(utc(2024, 6, 21, 12, 0))
+            .unwrap();
+        assert!((next - utc(2024, 6, 21, 19, 3)).num_minutes().abs() <= 3);
+
+        // Today's sunrise has passed, tomorrow's is next
+        let next = berlin(SolarEvent::Sunrise, 0)
+            .next_after(utc(2024, 6, 21, 12, 0))
+            .unwrap();
+        assert_eq!(next.date_naive(), utc(2024, 6, 22, 0, 0).date_naive());
+    }
+
+    #[test]
+    fn moves_yesterdays_event_past_midnight_with_the_offset() {
+        // Sunset at 19:33 UTC plus six hours is 01:33 the next day
+        let next = berlin(SolarEvent::Sunset, 360)
+            .next_after(utc(2024, 6, 22, 0, 0))
+            .unwrap();
+        assert!((next - utc(2024, 6, 22, 1, 33)).num_minutes().abs() <= 3);
+    }
+
+    #[test]
+    fn never_fires_solar_triggers_without_a_location() {
+        let trigger = Trigger::Solar {
+            event: SolarEvent::Sunrise,
+            offset: TimeDelta::zero(),
+            location: None,
+        };
+
+        assert_eq!(trigger.next_after(utc(2024, 6, 21, 0, 0)), None);
+    }
+}
@@ -9,7 +9,6 @@ pub(crate) type Result<T> = core::result::Result<T, Error>;
 pub(crate) enum Error {
     Request,
     Json,
-    ServiceIsTooSlow,
     #[from]
     Database(database::Error),
 }
@@ -1,90 +1,209 @@
 mod error;
+mod schedule;
 
+use chrono::Utc;
 use error::{Error, Result};
 use greenhouse_core::smart_device_dto::{Type, read::ReadResponseDto};
-use metrics::gauge;
-use std::time::{Duration, Instant};
+use metrics::{counter, gauge};
+use reqwest::header;
+use schedule::Schedule;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{Notify, Semaphore},
+    task::JoinHandle,
+};
 use uuid::Uuid;
 
-use crate::{AppState, database::device::Device};
+use crate::{
+    AppState, availability, database::device::Device, readings, router::service::response_encoding,
+    tls::device_client, tunnel::DeviceRequest,
+};
+
+// Last label reported per text/enum metric, so the previous state's series can be reset
+static LABEL_STATES: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// How long the scraping devices are cached. Changes made through the api
+/// reload them right away, see `devices_changed`.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+static DEVICES_CHANGED: Notify = Notify::const_new();
+
+/// Devices whose previous scrape is still running or waiting for a slot.
+static IN_FLIGHT: LazyLock<Mutex<HashSet<Uuid>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// What every scrape shares: one pooled client and the slots limiting how many
+/// devices are read at the same time.
+#[derive(Clone)]
+struct Scraper {
+    state: AppState,
+    client: reqwest::Client,
+    slots: Arc<Semaphore>,
+}
 
 pub(crate) fn start_scrape_devices(state: AppState) {
+    let scraper = Scraper {
+        client: device_client(),
+        slots: Arc::new(Semaphore::new(
+            state.config.scrape_concurrency.max(1) as usize
+        )),
+        state,
+    };
     tokio::spawn(async move {
-        let gauge = gauge!(
-            "scrape_service_duration",
-            &[("device", "scripting_service")]
-        );
+        let mut schedule = Schedule::default();
+        let mut devices = Vec::new();
+        let mut refresh_at = Instant::now();
         loop {
-            let scrape_interval = Duration::from_secs(5);
-            let now = Instant::now();
-            if let Err(e) = scrape_devices(state.clone()).await {
-                sentry::capture_error(&e);
-                tracing::error!("Error scraping devices: {:?}", e);
+            if Instant::now() >= refresh_at {
+                match Device::get_scraping_devices(&scraper.state.pool).await {
+                    Ok(loaded) => devices = loaded,
+                    Err(e) => {
+                        sentry::capture_error(&e);
+                        tracing::error!("Error loading devices to scrape: {:?}", e);
+                    }
+                }
+                refresh_at = Instant::now() + REFRESH_INTERVAL;
             }
-            let elapsed = now.elapsed();
-            gauge.set(elapsed.as_secs_f64());
-
-            tracing::debug!("Scrape service took {} seconds", elapsed.as_secs_f64());
-
-            if elapsed < scrape_interval {
-                tokio::time::sleep(scrape_interval - elapsed).await;
-                continue;
+            for device in schedule.due(&devices, Instant::now()) {
+                scraper.spawn(device);
+            }
+            let wakeup = schedule
+                .next_wakeup()
+                .map_or(refresh_at, |wakeup| wakeup.min(refresh_at));
+            tokio::select! {
+                _ = tokio::time::sleep_until(wakeup.into()) => {}
+                _ = DEVICES_CHANGED.notified() => refresh_at = Instant::now(),
             }
-            // sentry Error service is to slow
-            sentry::capture_error(&Error::ServiceIsTooSlow);
-            tracing::error!(
-                "Service is too slow and took {} seconds",
-                elapsed.as_secs_f64()
-            );
         }
     });
 }
 
-async fn scrape_devices(state: AppState) -> Result<()> {
-    let devices = Device::get_scraping_devices(&state.pool).await?;
-    let mut handles = Vec::new();
-    for scrape_devices in devices {
-        tracing::debug!("Scraping device: {}", scrape_devices.address);
-        handles.push(read_device(
-            scrape_devices.id,
-            scrape_devices.address.clone(),
-        ));
+/// Reloads the scraping devices, so new devices, changed addresses and
+/// intervals are picked up right away.
+pub(crate) fn devices_changed() {
+    DEVICES_CHANGED.notify_one();
+}
+
+impl Scraper {
+    /// Scrapes `device` in the background once a slot is free.
+    fn spawn(&self, device: Device) {
+        let scraper = self.clone();
+        spawn_scrape(self.slots.clone(), device.id, async move {
+            scraper.scrape(device).await;
+        });
     }
 
-    for handle in handles {
-        match handle.await {
+    async fn scrape(&self, device: Device) {
+        let id = device.id;
+        tracing::debug!("Scraping device: {}", device.address);
+        let device_label = id.to_string();
+        let now = Instant::now();
+        let result = self.read_device(&device).await;
+        // Failing to decode or store a read still means the device answered
+        let answered = !matches!(result, Err(Error::Request));
+        if let Err(e) = availability::record_scrape(&device, answered, &self.state).await {
+            tracing::error!("Error recording availability of device {id}: {:?}", e);
+        }
+        match result {
             Ok(()) => {
-                tracing::debug!("Device scraped successfully");
+                tracing::debug!("Device {id} scraped successfully");
+                counter!("scrape_service_success_total", "device" => device_label.clone())
+                    .increment(1);
+                gauge!("scrape_service_last_success", "device" => device_label.clone())
+                    .set(Utc::now().timestamp() as f64);
             }
             Err(e) => {
-                tracing::error!("Error scraping device: {:?}", e);
+                tracing::error!("Error scraping device {id}: {:?}", e);
+                counter!("scrape_service_failure_total", "device" => device_label.clone())
+                    .increment(1);
             }
         }
+        gauge!("scrape_service_duration", "device" => device_label)
+            .set(now.elapsed().as_secs_f64());
     }
 
-    Ok(())
-}
+    async fn read_device(&self, device: &Device) -> Result<()> {
+        let state = &self.state;
+        let id = device.id;
 
-async fn read_device(id: Uuid, address: String) -> Result<()> {
-    let client = reqwest::Client::new();
+        let response = self
+            .client
+            .get(format!("{}/read", device.address))
+            .header(header::ACCEPT, device.encoding().content_type())
+            .timeout(device.scrape_timeout())
+            .send_to_device(device.id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Error scraping device: {:?}", e);
 
-    let response: ReadResponseDto = client
-        .get(format!("{address}/read"))
-        .timeout(Duration::from_secs(4))
-        .send()
-        .await
-        .map_err(|e| {
-            tracing::error!("Error scraping device: {:?}", e);
+                Error::Request
+            })?;
+        if !response.status().is_success() {
+            tracing::error!("Device {id} answered with {}", response.status());
+            return Err(Error::Request);
+        }
+        let encoding = response_encoding(&response);
+        let bytes = response.bytes().await.map_err(|_| Error::Json)?;
+        let mut response: ReadResponseDto = encoding.decode(&bytes).map_err(|_| Error::Json)?;
 
-            Error::Request
-        })?
-        .json()
-        .await
-        .map_err(|_| Error::Json)?;
+        // Values in unknown units can't be compared or converted, so they are not stored
+        for e in response.data.normalize_units() {
+            tracing::warn!("Device {} reported an unsupported unit: {}", id, e);
+        }
+        readings::ingest(id, &response.data, Utc::now(), &state.pool).await?;
+        if state.config.prometheus_sink {
+            generate_metric(format!("scrape_service_duration_{id}"), &response.data);
+        }
 
-    generate_metric(format!("scrape_service_duration_{id}"), &response.data);
+        Ok(())
+    }
+}
 
-    Ok(())
+/// Runs `scrape` of device `id` in the background once one of `slots` is
+/// free. Skipped while the previous scrape of the device is still running or
+/// waiting for a slot, which happens when it answers slower than its interval.
+fn spawn_scrape<F>(slots: Arc<Semaphore>, id: Uuid, scrape: F) -> Option<JoinHandle<()>>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let Some(in_flight) = InFlight::claim(id) else {
+        tracing::debug!("Previous scrape of device {id} is still running, skipping");
+        counter!("scrape_service_skipped_total", "device" => id.to_string()).increment(1);
+        return None;
+    };
+    Some(tokio::spawn(async move {
+        let _in_flight = in_flight;
+        // The semaphore is never closed
+        let _slot = slots.acquire().await;
+        scrape.await;
+    }))
+}
+
+/// Marks a device as in flight until dropped, so a panicking scrape doesn't
+/// keep the device from being scraped again.
+struct InFlight(Uuid);
+
+impl InFlight {
+    fn claim(id: Uuid) -> Option<Self> {
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id)
+            .then(|| Self(id))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.0);
+    }
 }
 
 fn generate_metric(name: String, data: &Type) {
@@ -98,6 +217,18 @@ fn generate_metric(name: String, data: &Type) {
             let b = if *data { 1.0 } else { 0.0 };
             gauge.set(b);
         }
+        Type::Text(data) => {
+            generate_label_metric(name, "text", data);
+        }
+        Type::Enum(data) => {
+            generate_label_metric(name, "enum", data);
+        }
+        Type::Array(data) => {
+            for (index, value) in data.iter().enumerate() {
+                let next_name = format!("{name}_{index}");
+                generate_metric(next_name, value);
+            }
+        }
         Type::Object(data) => {
             for (key, value) in data.iter() {
                 let next_name = format!("{name}_{key}");
@@ -108,6 +239,12 @@ fn generate_metric(name: String, data: &Type) {
             let gauge = gauge!(name, "type" => "measurement", "unit" => data.unit.clone());
             gauge.set(data.value);
         }
+        Type::Timestamped(data) => {
+            // Prometheus stamps samples at scrape time, the device timestamp is kept as its own series
+            let gauge = gauge!(format!("{name}_timestamp"), &[("type", "timestamp")]);
+            gauge.set(data.timestamp.timestamp() as f64);
+            generate_metric(name, &data.value);
+        }
         Type::Stream => {
             tracing::debug!("Not implemented: received stream");
         }
@@ -116,3 +253,85 @@ fn generate_metric(name: String, data: &Type) {
         }
     }
 }
+
+// Strings can't be stored as gauge values, so the state is carried in a label
+// and the series of the current state is set to 1 while all others are 0.
+fn generate_label_metric(name: String, metric_type: &'static str, value: &str) {
+    let previous = LABEL_STATES
+        .lock()
+        .ok()
+        .and_then(|mut states| states.insert(name.clone(), value.to_string()));
+    if let Some(previous) = previous
+        && previous != value
+    {
+        let gauge = gauge!(name.clone(), "type" => metric_type, "value" => previous);
+        gauge.set(0.0);
+    }
+    let gauge = gauge!(name, "type" => metric_type, "value" => value.to_string());
+    gauge.set(1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn limits_concurrent_scrapes_to_the_slots() {
+        let slots = Arc::new(Semaphore::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+
+        let scrapes: Vec<_> = (0..6)
+            .map(|_| {
+                let (running, most_running) = (running.clone(), most_running.clone());
+                spawn_scrape(slots.clone(), Uuid::new_v4(), async move {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_running.fetch_max(now_running, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+                .unwrap()
+            })
+            .collect();
+        for scrape in scrapes {
+            scrape.await.unwrap();
+        }
+
+        assert_eq!(most_running.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn skips_devices_whose_previous_scrape_is_running() {
+        let slots = Arc::new(Semaphore::new(1));
+        let id = Uuid::new_v4();
+        let (finish, finished) = oneshot::channel::<()>();
+
+        let first = spawn_scrape(slots.clone(), id, async move {
+            let _ = finished.await;
+        })
+        .unwrap();
+        assert!(spawn_scrape(slots.clone(), id, async {}).is_none());
+        // Other devices are still scraped
+        let other = spawn_scrape(slots.clone(), Uuid::new_v4(), async {}).unwrap();
+
+        finish.send(()).unwrap();
+        first.await.unwrap();
+        other.await.unwrap();
+        assert!(spawn_scrape(slots, id, async {}).is_some());
+    }
+
+    #[tokio::test]
+    async fn scrapes_a_device_again_after_a_panic() {
+        let slots = Arc::new(Semaphore::new(1));
+        let id = Uuid::new_v4();
+
+        let panicking = spawn_scrape(slots.clone(), id, async { panic!("scrape failed") });
+        assert!(panicking.unwrap().await.is_err());
+
+        assert!(spawn_scrape(slots, id, async {}).is_some());
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, time::Instant};
+
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::database::device::Device;
+
+/// Share of the interval scrapes are randomly moved by, so devices with the
+/// same interval don't all fire at once.
+const JITTER: f64 = 0.1;
+
+/// When each scraping device is polled next.
+#[derive(Default)]
+pub(super) struct Schedule {
+    next: HashMap<Uuid, Instant>,
+}
+
+impl Schedule {
+    /// Devices due at `now`. Their next scrape is planned one interval later.
+    /// Devices that stopped scraping are dropped from the schedule.
+    pub(super) fn due(&mut self, devices: &[Device], now: Instant) -> Vec<Device> {
+        let mut rng = rand::rng();
+        self.next
+            .retain(|id, _| devices.iter().any(|device| device.id == *id));
+
+        let mut due = Vec::new();
+        for device in devices {
+            let interval = device.scrape_interval();
+            let latest = now + interval.mul_f64(1.0 + JITTER);
+            let next = self
+                .next
+                .entry(device.id)
+                // New devices start within the first jitter window
+                .or_insert_with(|| now + interval.mul_f64(rng.random_range(0.0..JITTER)));
+            // A shortened interval takes effect right away
+            if *next > latest {
+                *next = now;
+            }
+            if *next > now {
+                continue;
+            }
+            let jitter = rng.random_range(-JITTER..=JITTER);
+            let planned = *next + interval.mul_f64(1.0 + jitter);
+            // A device that fell behind isn't scraped repeatedly to catch up
+            *next = if planned > now {
+                planned
+            } else {
+                now + interval
+            };
+            due.push(device.clone());
+        }
+        due
+    }
+
+    pub(super) fn next_wakeup(&self) -> Option<Instant> {
+        self.next.values().min().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn device(interval: u32) -> Device {
+        let mut device = Device::new("sensor", "", "http://sensor", false, true);
+        device.set_scrape_schedule(Some(interval), Some(1)).unwrap();
+        device
+    }
+
+    #[test]
+    fn starts_new_devices_within_the_first_jitter_window() {
+        let devices = [device(100)];
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+
+        let scraped = schedule.due(&devices, start).len()
+            + schedule
+                .due(&devices, start + Duration::from_secs(10))
+                .len();
+        assert_eq!(scraped, 1);
+    }
+
+    #[test]
+    fn plans_the_next_scrape_one_jittered_interval_later() {
+        let devices = [device(100)];
+        let mut schedule = Schedule::default();
+        let now = Instant::now();
+        schedule.next.insert(devices[0].id, now);
+
+        assert_eq!(schedule.due(&devices, now).len(), 1);
+        let next = schedule.next_wakeup().unwrap();
+        assert!(next >= now + Duration::from_secs(90));
+        assert!(next <= now + Duration::from_secs(110));
+        assert!(
+            schedule
+                .due(&devices, now + Duration::from_secs(89))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn scrapes_a_late_device_once_instead_of_catching_up() {
+        let devices = [device(100)];
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+        schedule.next.insert(devices[0].id, start);
+
+        let now = start + Duration::from_secs(1000);
+        assert_eq!(schedule.due(&devices, now).len(), 1);
+        assert_eq!(schedule.next_wakeup(), Some(now + Duration::from_secs(100)));
+        assert!(schedule.due(&devices, now).is_empty());
+    }
+
+    #[test]
+    fn applies_a_shortened_interval_right_away() {
+        let devices = [device(10)];
+        let mut schedule = Schedule::default();
+        let now = Instant::now();
+        // Planned with the previous interval of an hour
+        schedule
+            .next
+            .insert(devices[0].id, now + Duration::from_secs(3000));
+
+        assert_eq!(schedule.due(&devices, now).len(), 1);
+    }
+
+    #[test]
+    fn drops_devices_that_stopped_scraping() {
+        let devices = [device(100), device(100)];
+        let mut schedule = Schedule::default();
+        let now = Instant::now();
+        schedule
+            .next
+            .insert(devices[0].id, now + Duration::from_secs(50));
+        schedule
+            .next
+            .insert(devices[1].id, now + Duration::from_secs(60));
+
+        assert!(schedule.due(&devices[1..], now).is_empty());
+        assert_eq!(schedule.next_wakeup(), Some(now + Duration::from_secs(60)));
+    }
+}
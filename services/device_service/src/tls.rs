@@ -0,0 +1,245 @@
+use std::{
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use chrono::{Datelike, Utc};
+use greenhouse_core::smart_device_dto::certificate::CertificateDto;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertificateSigningRequestParams,
+    DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose, SanType,
+    date_time_ymd,
+};
+
+use crate::{
+    database::device::Device,
+    router::error::{Error, Result},
+};
+
+/// Years a device certificate is valid, reactivating the device renews it.
+const CERTIFICATE_VALIDITY_YEARS: i32 = 2;
+const CA_VALIDITY_YEARS: i32 = 20;
+
+static AUTHORITY: OnceLock<Arc<CertificateAuthority>> = OnceLock::new();
+
+/// The internal CA issuing device certificates. Its key and certificate are
+/// stored in `CA_DIR` and created on the first start.
+struct CertificateAuthority {
+    certificate: Certificate,
+    key: KeyPair,
+    client: reqwest::Client,
+}
+
+/// Loads or creates the CA, later calls keep the first one.
+pub(crate) fn init(ca_dir: &str) {
+    if AUTHORITY.get().is_some() {
+        return;
+    }
+    let authority = CertificateAuthority::load_or_create(Path::new(ca_dir))
+        .unwrap_or_else(|e| panic!("Failed to set up the device CA in {ca_dir}: {e}"));
+    let _ = AUTHORITY.set(Arc::new(authority));
+}
+
+/// Client for requests to devices. It only trusts the device CA, so devices
+/// served over HTTPS are authenticated by their issued certificate.
+pub(crate) fn device_client() -> reqwest::Client {
+    AUTHORITY
+        .get()
+        .map(|authority| authority.client.clone())
+        .unwrap_or_default()
+}
+
+/// Signs the certificate request of `device`. The certificate names the device
+/// id and the host of its address, whatever the request asked for.
+pub(crate) fn issue_certificate(csr_pem: &str, device: &Device) -> Result<CertificateDto> {
+    let authority = AUTHORITY.get().ok_or(Error::Certificate)?;
+    let mut request = CertificateSigningRequestParams::from_pem(csr_pem).map_err(|e| {
+        tracing::error!(
+            "Invalid certificate signing request from {}: {e}",
+            device.id
+        );
+        Error::Certificate
+    })?;
+
+    let now = Utc::now();
+    let mut params = CertificateParams::default();
+    params.distinguished_name = DistinguishedName::new();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, device.id.to_string());
+    params.subject_alt_names = vec![subject_alt_name(&device.address)?];
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.not_before = date_time_ymd(now.year(), now.month() as u8, 1);
+    params.not_after = date_time_ymd(
+        now.year() + CERTIFICATE_VALIDITY_YEARS,
+        now.month() as u8,
+        1,
+    );
+    params.use_authority_key_identifier_extension = true;
+    request.params = params;
+
+    let certificate = request
+        .signed_by(&authority.certificate, &authority.key)
+        .map_err(|e| {
+            tracing::error!("Could not sign certificate for {}: {e}", device.id);
+            Error::Certificate
+        })?;
+    Ok(CertificateDto {
+        certificate_pem: certificate.pem(),
+        ca_certificate_pem: authority.certificate.pem(),
+    })
+}
+
+fn subject_alt_name(address: &str) -> Result<SanType> {
+    let host = reqwest::Url::parse(address)
+        .ok()
+        .and_then(|url| {
+            url.host_str()
+                .map(|host| host.trim_matches(['[', ']']).to_string())
+        })
+        .ok_or(Error::Certificate)?;
+    match host.parse::<IpAddr>() {
+        Ok(ip) => Ok(SanType::IpAddress(ip)),
+        Err(_) => host
+            .try_into()
+            .map(SanType::DnsName)
+            .map_err(|_| Error::Certificate),
+    }
+}
+
+impl CertificateAuthority {
+    fn load_or_create(ca_dir: &Path) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let key_path = ca_dir.join("ca.key.pem");
+        let certificate_path = ca_dir.join("ca.pem");
+
+        let (key, certificate_pem) = match std::fs::read_to_string(&key_path) {
+            Ok(key_pem) => (
+                KeyPair::from_pem(&key_pem)?,
+                std::fs::read_to_string(&certificate_path)?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("Creating device CA in {}", ca_dir.display());
+                let key = KeyPair::generate()?;
+                let certificate = ca_params(Utc::now().year()).self_signed(&key)?;
+                std::fs::create_dir_all(ca_dir)?;
+                write_private(&key_path, &key.serialize_pem())?;
+                std::fs::write(&certificate_path, certificate.pem())?;
+                (key, certificate.pem())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        // Re-signing the stored parameters yields an issuer with the same name
+        // and key, which is all that signing needs.
+        let certificate =
+            CertificateParams::from_ca_cert_pem(&certificate_pem)?.self_signed(&key)?;
+        let client = reqwest::Client::builder()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(reqwest::Certificate::from_pem(certificate_pem.as_bytes())?)
+            .build()?;
+        Ok(Self {
+            certificate,
+            key,
+            client,
+        })
+    }
+}
+
+fn ca_params(year: i32) -> CertificateParams {
+    let mut params = CertificateParams::default();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "Greenhouse device CA");
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    params.not_before = date_time_ymd(year, 1, 1);
+    params.not_after = date_time_ymd(year + CA_VALIDITY_YEARS, 1, 1);
+    params
+}
+
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::DnValue;
+
+    fn issue_for(address: &str, requested_name: &str) -> (Device, CertificateParams) {
+        let ca_dir = std::env::temp_dir().join(format!("greenhouse-ca-{}", uuid::Uuid::new_v4()));
+        init(ca_dir.to_str().unwrap());
+
+        let device = Device::new("sensor", "", address, false, true);
+        let mut request = CertificateParams::new(vec![requested_name.to_string()]).unwrap();
+        request
+            .distinguished_name
+            .push(DnType::CommonName, requested_name);
+        let csr_pem = request
+            .serialize_request(&KeyPair::generate().unwrap())
+            .unwrap()
+            .pem()
+            .unwrap();
+
+        let issued = issue_certificate(&csr_pem, &device).unwrap();
+        let params = CertificateParams::from_ca_cert_pem(&issued.certificate_pem).unwrap();
+        (device, params)
+    }
+
+    #[test]
+    fn issues_certificate_for_device_id_and_address() {
+        let (device, params) = issue_for("http://10.0.0.7:8080", "bank.example");
+
+        assert_eq!(
+            params.distinguished_name.get(&DnType::CommonName),
+            Some(&DnValue::Utf8String(device.id.to_string()))
+        );
+        // The names the device asked for are ignored
+        assert_eq!(
+            params.subject_alt_names,
+            vec![SanType::IpAddress("10.0.0.7".parse().unwrap())]
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_signing_request() {
+        init(
+            std::env::temp_dir()
+                .join(format!("greenhouse-ca-{}", uuid::Uuid::new_v4()))
+                .to_str()
+                .unwrap(),
+        );
+        let device = Device::new("sensor", "", "http://10.0.0.7:8080", false, true);
+
+        assert!(matches!(
+            issue_certificate("not a request", &device),
+            Err(Error::Certificate)
+        ));
+    }
+
+    #[test]
+    fn names_the_host_of_the_address() {
+        assert_eq!(
+            subject_alt_name("http://192.168.1.20:80").unwrap(),
+            SanType::IpAddress("192.168.1.20".parse().unwrap())
+        );
+        assert_eq!(
+            subject_alt_name("https://[fd00::1]:8443").unwrap(),
+            SanType::IpAddress("fd00::1".parse().unwrap())
+        );
+        assert_eq!(
+            subject_alt_name("http://greenhouse-sensor.local:3000").unwrap(),
+            SanType::DnsName("greenhouse-sensor.local".try_into().unwrap())
+        );
+        assert!(subject_alt_name("10.0.0.7:8080").is_err());
+        assert!(subject_alt_name("not an address").is_err());
+    }
+}
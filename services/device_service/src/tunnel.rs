@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{
+        LazyLock, PoisonError, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocketUpgrade},
+    },
+    http::{HeaderMap, header},
+    response::Response,
+};
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use greenhouse_core::smart_device_dto::{
+    encoding::Encoding,
+    tunnel::{TUNNEL_TOKEN_HEADER, TunnelRequestDto, TunnelResponseDto},
+};
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    database::device::Device,
+    handshake,
+    router::{
+        error::{Error as HttpError, HttpResult},
+        service::negotiate_protocol,
+    },
+};
+
+/// Used when the request doesn't set its own timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    Request(reqwest::Error),
+    /// The connection dropped before the device answered.
+    Closed,
+    Timeout,
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+// endregion: --- Error Boilerplate
+
+/// Open reverse connections by device id.
+static TUNNELS: LazyLock<RwLock<HashMap<Uuid, Tunnel>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+type Exchange = (TunnelRequestDto, oneshot::Sender<TunnelResponseDto>);
+
+#[derive(Clone)]
+struct Tunnel {
+    connection: u64,
+    requests: mpsc::Sender<Exchange>,
+}
+
+/// Sends requests to devices through their reverse connection if they hold
+/// one, and directly to their address otherwise. Callers can't tell the two
+/// apart.
+pub(crate) trait DeviceRequest {
+    async fn send_to_device(self, device_id: Uuid) -> Result<reqwest::Response>;
+}
+
+impl DeviceRequest for reqwest::RequestBuilder {
+    async fn send_to_device(self, device_id: Uuid) -> Result<reqwest::Response> {
+        let (client, request) = self.build_split();
+        let request = request.map_err(Error::Request)?;
+        if let Some((tunnel, path)) = tunnel_for(device_id, request.url()) {
+            match tunnel.send(&request, path).await {
+                // The device may have reconnected or become reachable directly
+                Err(Error::Closed) => {
+                    tracing::debug!("Tunnel closed, sending {} directly", request.url());
+                }
+                result => return result,
+            }
+        }
+        client.execute(request).await.map_err(Error::Request)
+    }
+}
+
+/// The tunnel of the device and the path and query the request goes to. The
+/// host of the url is ignored, devices behind NAT may share an address.
+fn tunnel_for(device_id: Uuid, url: &reqwest::Url) -> Option<(Tunnel, String)> {
+    let tunnel = TUNNELS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&device_id)
+        .cloned()?;
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    Some((tunnel, path))
+}
+
+/// Only the hash of a tunnel token is stored. Tokens are random, so a plain
+/// SHA-256 is enough and keeps them searchable.
+pub(crate) fn hash_token(token: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, token.as_bytes())
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        })
+}
+
+impl Tunnel {
+    async fn send(&self, request: &reqwest::Request, path: String) -> Result<reqwest::Response> {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let tunneled = TunnelRequestDto {
+            id: 0,
+            method: request.method().to_string(),
+            path,
+            content_type: header(header::CONTENT_TYPE),
+            accept: header(header::ACCEPT),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default(),
+        };
+
+        let (respond, response) = oneshot::channel();
+        self.requests
+            .send((tunneled, respond))
+            .await
+            .map_err(|_| Error::Closed)?;
+        let timeout = request.timeout().copied().unwrap_or(DEFAULT_TIMEOUT);
+        let response = tokio::time::timeout(timeout, response)
+            .await
+            .map_err(|_| Error::Timeout)?
+            .map_err(|_| Error::Closed)?;
+
+        let mut builder = axum::http::Response::builder().status(response.status);
+        if let Some(content_type) = response.content_type {
+            builder = builder.header(header::CONTENT_TYPE, content_type);
+        }
+        builder
+            .body(response.body)
+            .map(reqwest::Response::from)
+            .map_err(|_| Error::Closed)
+    }
+}
+
+/// Drops the connection of the device, if it has one.
+pub(crate) fn close(id: Uuid) {
+    TUNNELS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&id);
+}
+
+/// Accepts the reverse connection of a device presenting its tunnel token.
+pub(crate) async fn tunnel_handler(
+    State(AppState { config: _, pool }): State<AppState>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> HttpResult<Response> {
+    let token = headers
+        .get(TUNNEL_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(HttpError::TunnelUnauthorized)?;
+    let mut device = Device::find_by_tunnel_token_hash(&hash_token(token), &pool)
+        .await
+        .map_err(|_| HttpError::TunnelUnauthorized)?;
+
+    Ok(upgrade.on_upgrade(move |socket| async move {
+        let (requests, incoming) = mpsc::channel(16);
+        let connection = CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        TUNNELS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                device.id,
+                Tunnel {
+                    connection,
+                    requests,
+                },
+            );
+        tracing::info!("Device {} connected through its tunnel", device.id);
+
+        let id = device.id;
+        if device.protocol_version.is_none() {
+            tokio::spawn(async move {
+                handshake::report(device.id, negotiate_protocol(&mut device, &pool).await);
+            });
+        }
+        let (sink, stream) = socket.split();
+        run_tunnel(sink, stream, incoming).await;
+
+        let mut tunnels = TUNNELS.write().unwrap_or_else(PoisonError::into_inner);
+        // A reconnect may already have replaced this connection
+        if tunnels.get(&id).is_some_and(|t| t.connection == connection) {
+            tunnels.remove(&id);
+        }
+        tracing::info!("Tunnel of device {id} closed");
+    }))
+}
+
+/// Relays requests to the device over `sink` and matches the responses read
+/// from `stream`, until either side closes.
+async fn run_tunnel<W, R, E>(mut sink: W, mut stream: R, mut incoming: mpsc::Receiver<Exchange>)
+where
+    W: Sink<Message> + Unpin,
+    R: Stream<Item = core::result::Result<Message, E>> + Unpin,
+{
+    let mut pending: HashMap<u64, oneshot::Sender<TunnelResponseDto>> = HashMap::new();
+    let mut next_id = 0;
+    loop {
+        tokio::select! {
+            exchange = incoming.recv() => {
+                // The tunnel was closed on our side
+                let Some((mut request, respond)) = exchange else {
+                    return;
+                };
+                next_id += 1;
+                request.id = next_id;
+                let Ok(bytes) = Encoding::Cbor.encode(&request) else {
+                    continue;
+                };
+                if sink.send(Message::Binary(bytes.into())).await.is_err() {
+                    return;
+                }
+                pending.insert(request.id, respond);
+            }
+            message = stream.next() => match message {
+                Some(Ok(Message::Binary(bytes))) => {
+                    match Encoding::Cbor.decode::<TunnelResponseDto>(&bytes) {
+                        Ok(response) => {
+                            if let Some(respond) = pending.remove(&response.id) {
+                                let _ = respond.send(response);
+                            }
+                        }
+                        Err(e) => tracing::warn!("Malformed tunnel response: {e}"),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+        // Callers that timed out don't wait for their response anymore
+        pending.retain(|_, respond| !respond.is_closed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc as socket;
+
+    fn register(device_id: Uuid) -> mpsc::Receiver<Exchange> {
+        let (requests, incoming) = mpsc::channel(1);
+        let connection = CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        TUNNELS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                device_id,
+                Tunnel {
+                    connection,
+                    requests,
+                },
+            );
+        incoming
+    }
+
+    fn connection_of(device_id: Uuid) -> u64 {
+        TUNNELS.read().unwrap()[&device_id].connection
+    }
+
+    #[test]
+    fn routes_by_device_id_not_address() {
+        // Both devices answer at the same address behind a NAT
+        let url = reqwest::Url::parse("http://192.168.0.10:8080/logs?limit=5").unwrap();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let _first = register(first);
+        let _second = register(second);
+
+        let (tunnel, path) = tunnel_for(second, &url).unwrap();
+        assert_eq!(tunnel.connection, connection_of(second));
+        assert_eq!(path, "/logs?limit=5");
+        let (tunnel, _) = tunnel_for(first, &url).unwrap();
+        assert_eq!(tunnel.connection, connection_of(first));
+
+        assert!(tunnel_for(Uuid::new_v4(), &url).is_none());
+        close(first);
+        assert!(tunnel_for(first, &url).is_none());
+        close(second);
+    }
+
+    #[test]
+    fn hashes_tokens() {
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn matches_responses_to_requests() {
+        let (sink, mut sent) = socket::unbounded::<Message>();
+        let (received, stream) = socket::unbounded::<core::result::Result<Message, ()>>();
+        let (requests, incoming) = mpsc::channel(1);
+        let tunnel = tokio::spawn(run_tunnel(sink, stream, incoming));
+
+        let (respond, response) = oneshot::channel();
+        let request = TunnelRequestDto {
+            id: 0,
+            method: String::from("GET"),
+            path: String::from("/read"),
+            content_type: None,
+            accept: None,
+            body: Vec::new(),
+        };
+        requests.send((request, respond)).await.unwrap();
+
+        let Some(Message::Binary(bytes)) = sent.next().await else {
+            panic!("expected a binary frame");
+        };
+        let request: TunnelRequestDto = Encoding::Cbor.decode(&bytes).unwrap();
+        assert_eq!(request.path, "/read");
+        // A response nobody waits for is dropped
+        for id in [request.id + 1, request.id] {
+            let response = TunnelResponseDto {
+                id,
+                status: 200,
+                content_type: None,
+                body: id.to_be_bytes().to_vec(),
+            };
+            let bytes = Encoding::Cbor.encode(&response).unwrap();
+            received
+                .unbounded_send(Ok(Message::Binary(bytes.into())))
+                .unwrap();
+        }
+
+        let response = response.await.unwrap();
+        assert_eq!(response.id, request.id);
+        assert_eq!(response.body, request.id.to_be_bytes());
+
+        // The tunnel ends when the device disconnects
+        received.close_channel();
+        tunnel.await.unwrap();
+    }
+}
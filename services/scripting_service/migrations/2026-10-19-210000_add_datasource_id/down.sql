@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE scripting_device DROP COLUMN datasource_id;
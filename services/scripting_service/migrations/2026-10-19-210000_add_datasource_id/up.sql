@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE scripting_device ADD COLUMN datasource_id TEXT;
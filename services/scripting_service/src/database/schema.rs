@@ -3,5 +3,6 @@
 diesel::table! {
     scripting_device (scriptig_key) {
         scriptig_key -> Text,
+        datasource_id -> Nullable<Text>,
     }
 }
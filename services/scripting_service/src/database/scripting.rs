@@ -7,4 +7,6 @@ use serde::Deserialize;
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub(crate) struct ScriptingDevice {
     pub(crate) scriptig_key: String,
+    /// Datasource the key was issued to, unknown for keys issued before.
+    pub(crate) datasource_id: Option<String>,
 }
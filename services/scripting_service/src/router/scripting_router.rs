@@ -12,7 +12,9 @@ use axum::{
 use diesel::ExpressionMethods;
 use diesel::query_dsl::methods::FilterDsl;
 use diesel_async::RunQueryDsl;
-use greenhouse_core::scripting_service_dto::token::TokenDto;
+use greenhouse_core::scripting_service_dto::token::{
+    ScriptingKeyDto, ScriptingKeyRequestDto, TokenDto,
+};
 use reqwest::StatusCode;
 use uuid::Uuid;
 
@@ -24,14 +26,17 @@ pub(crate) fn routes(state: AppState) -> Router {
         .with_state(state)
 }
 
+/// Issues a key, bound to the datasource given in the body if there is one.
 #[axum::debug_handler]
 pub(crate) async fn generate_scripting_key(
     State(AppState { config: _, pool }): State<AppState>,
+    request: Option<Json<ScriptingKeyRequestDto>>,
 ) -> HttpResult<TokenDto> {
     let token = Uuid::new_v4().to_string();
 
     let device = ScriptingDevice {
         scriptig_key: token.clone(),
+        datasource_id: request.map(|Json(request)| request.datasource_id),
     };
 
     let mut conn = pool.get().await.map_err(|e| {
@@ -50,25 +55,31 @@ pub(crate) async fn generate_scripting_key(
     Ok(TokenDto { token })
 }
 
+/// Answers with the datasource the key was issued to.
 pub(crate) async fn check_scripting_key(
     State(AppState { config: _, pool }): State<AppState>,
     Json(check_token_dto_request): Json<TokenDto>,
-) -> HttpResult<StatusCode> {
+) -> HttpResult<ScriptingKeyDto> {
     let mut conn = pool.get().await.map_err(|e| {
         sentry::capture_error(&e);
         Error::DatabaseConnection
     })?;
 
-    let _ = scripting_device::table
+    let device = scripting_device::table
         .filter(scripting_device::scriptig_key.eq(check_token_dto_request.token))
         .first::<ScriptingDevice>(&mut conn)
         .await
-        .map_err(|e| {
-            sentry::capture_error(&e);
-            Error::NotFound
-        });
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => Error::NotFound,
+            e => {
+                sentry::capture_error(&e);
+                Error::DatabaseConnection
+            }
+        })?;
 
-    Ok(StatusCode::OK)
+    Ok(ScriptingKeyDto {
+        datasource_id: device.datasource_id,
+    })
 }
 
 pub(crate) async fn delete_scripting_key(
@@ -80,14 +91,14 @@ pub(crate) async fn delete_scripting_key(
         Error::DatabaseConnection
     })?;
 
-    let _ = diesel::delete(scripting_device::table)
+    diesel::delete(scripting_device::table)
         .filter(scripting_device::scriptig_key.eq(check_token_dto_request.token))
         .execute(&mut conn)
         .await
         .map_err(|e| {
             sentry::capture_error(&e);
-            Error::NotFound
-        });
+            Error::DatabaseConnection
+        })?;
 
     Ok(StatusCode::OK)
 }